@@ -1 +1,3 @@
-pub mod nodes;
\ No newline at end of file
+pub mod nodes;
+pub mod printer;
+pub mod span;
\ No newline at end of file
@@ -1,102 +1,354 @@
-#[derive(Debug, Clone, PartialEq)]
-pub enum Type {
-    Integer,
-    Float,
-    String,
-    Boolean,
-    Void,
-    Custom(String),
-}
-
-#[derive(Debug, Clone)]
-pub struct Variable {
-    pub name: String,
-    pub var_type: Type,
-}
-
-#[derive(Debug, Clone)]
-pub enum Expression {
-    Literal(Literal),
-    Variable(String),
-    BinaryOperation {
-        left: Box<Expression>,
-        operator: BinaryOperator,
-        right: Box<Expression>,
-    },
-    FunctionCall {
-        name: String,
-        arguments: Vec<Expression>,
-    },
-}
-
-#[derive(Debug, Clone)]
-pub enum Literal {
-    Integer(i64),
-    Float(f64),
-    String(String),
-    Boolean(bool),
-}
-
-#[derive(Debug, Clone)]
-pub enum BinaryOperator {
-    Add,
-    Subtract,
-    Multiply,
-    Divide,
-    Equal,
-    NotEqual,
-    LessThan,
-    GreaterThan,
-}
-
-#[derive(Debug, Clone)]
-pub struct Ritual {
-    pub name: String,
-    pub parameters: Vec<Variable>,
-    pub return_type: Type,
-    pub body: Vec<Statement>,
-}
-
-#[derive(Debug, Clone)]
-pub enum Statement {
-    VariableDeclaration {
-        variable: Variable,
-        initializer: Option<Expression>,
-    },
-    Assignment {
-        name: String,
-        value: Expression,
-    },
-    RitualCall {
-        name: String,
-        arguments: Vec<Expression>,
-    },
-    Conditional {
-        condition: Expression,
-        true_branch: Vec<Statement>,
-        false_branch: Option<Vec<Statement>>,
-    },
-    Cycle {
-        condition: Option<Expression>,
-        body: Vec<Statement>,
-    },
-    Return(Option<Expression>),
-}
-
-#[derive(Debug, Clone)]
-pub struct Being {
-    pub name: String,
-    pub rituals: Vec<Ritual>,
-    pub variables: Vec<Variable>,
-}
-
-#[derive(Debug, Clone)]
-pub struct Realm {
-    pub name: String,
-    pub beings: Vec<Being>,
-}
-
-#[derive(Debug, Clone)]
-pub struct Program {
-    pub realms: Vec<Realm>,
-}
\ No newline at end of file
+pub use super::span::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub enum Type {
+    Integer,
+    Float,
+    String,
+    Boolean,
+    Void,
+    /// Essence o being; `Realm::Nome` per i tipi di un altro realm
+    Custom(String),
+    /// `circular persistent hive<float>[3, 4]`: una struttura multidimensionale di dimensioni fisse
+    Hive {
+        element: Box<Type>,
+        dimensions: Vec<usize>,
+        /// Gli indici fuori dai limiti ricominciano dall'inizio della dimensione
+        circular: bool,
+        persistent: bool,
+    },
+    /// Tipo di un'espressione che non ha superato l'analisi semantica
+    Error,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Variable {
+    pub name: String,
+    pub var_type: Type,
+    /// Posizione del nome nella dichiarazione
+    pub span: Span,
+    /// Posizione del tipo nella dichiarazione
+    pub type_span: Span,
+}
+
+/// An annotation such as `@allow(unused_variables)` placed before a realm, being or ritual
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Attribute {
+    pub name: String,
+    pub arguments: Vec<String>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub enum Expression {
+    Literal(Literal, Span),
+    Variable(String, Span),
+    /// Being named together with its realm: `Realm::Being`
+    BeingReference {
+        realm: String,
+        being: String,
+        span: Span,
+    },
+    BinaryOperation {
+        left: Box<Expression>,
+        operator: BinaryOperator,
+        right: Box<Expression>,
+        span: Span,
+    },
+    UnaryOperation {
+        operator: UnaryOperator,
+        operand: Box<Expression>,
+        span: Span,
+    },
+    FunctionCall {
+        name: String,
+        arguments: Vec<Expression>,
+        span: Span,
+    },
+    /// `object.field`
+    FieldAccess {
+        object: Box<Expression>,
+        field: String,
+        span: Span,
+    },
+    /// `object.ritual(arguments)`
+    MethodCall {
+        object: Box<Expression>,
+        name: String,
+        arguments: Vec<Expression>,
+        span: Span,
+    },
+    /// `Essence { field: value, ... }`, anche `Realm::Essence { ... }`
+    Construction {
+        essence: String,
+        fields: Vec<FieldInit>,
+        span: Span,
+    },
+    /// `hive[i, j]`, con un indice per dimensione
+    Index {
+        object: Box<Expression>,
+        indices: Vec<Expression>,
+        span: Span,
+    },
+    // Espressione non riconosciuta dal parser (recupero dagli errori)
+    Error(Span),
+}
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Literal(_, span) |
+            Expression::Variable(_, span) |
+            Expression::BeingReference { span, .. } |
+            Expression::BinaryOperation { span, .. } |
+            Expression::UnaryOperation { span, .. } |
+            Expression::FunctionCall { span, .. } |
+            Expression::FieldAccess { span, .. } |
+            Expression::MethodCall { span, .. } |
+            Expression::Construction { span, .. } |
+            Expression::Index { span, .. } |
+            Expression::Error(span) => span.clone(),
+        }
+    }
+}
+
+/// A `field: value` pair in a construction expression
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct FieldInit {
+    pub name: String,
+    pub value: Expression,
+    /// Posizione del nome del campo
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub enum Literal {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Equal,
+    NotEqual,
+    LessThan,
+    GreaterThan,
+    LessEqual,
+    GreaterEqual,
+    /// `&&`, valuta l'operando destro solo se il sinistro è vero
+    And,
+    /// `||`, valuta l'operando destro solo se il sinistro è falso
+    Or,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub enum UnaryOperator {
+    /// `-`
+    Negate,
+    /// `!`
+    Not,
+}
+
+/// Whether a ritual or being variable can be used from other beings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub enum Visibility {
+    #[default]
+    Private,
+    Public,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Ritual {
+    pub attributes: Vec<Attribute>,
+    pub visibility: Visibility,
+    pub name: String,
+    pub parameters: Vec<Variable>,
+    pub return_type: Type,
+    /// Posizione del tipo di ritorno; vuota subito dopo i parametri se è omesso
+    pub return_type_span: Span,
+    pub body: Vec<Statement>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub enum Statement {
+    VariableDeclaration {
+        variable: Variable,
+        initializer: Option<Expression>,
+        span: Span,
+    },
+    Assignment {
+        name: String,
+        value: Expression,
+        span: Span,
+    },
+    /// `object.field = value;`
+    FieldAssignment {
+        object: Expression,
+        field: String,
+        value: Expression,
+        span: Span,
+    },
+    /// `hive[i, j] = value;`
+    IndexAssignment {
+        object: Expression,
+        indices: Vec<Expression>,
+        value: Expression,
+        span: Span,
+    },
+    RitualCall {
+        name: String,
+        arguments: Vec<Expression>,
+        span: Span,
+    },
+    Conditional {
+        condition: Expression,
+        true_branch: Vec<Statement>,
+        false_branch: Option<Vec<Statement>>,
+        span: Span,
+    },
+    Cycle {
+        condition: Option<Expression>,
+        body: Vec<Statement>,
+        span: Span,
+    },
+    Return(Option<Expression>, Span),
+    /// Chiamata usata come statement, ad esempio `being.ritual(argomenti);`
+    Expression(Expression, Span),
+    // Statement non riconosciuto dal parser (recupero dagli errori)
+    Error(Span),
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::VariableDeclaration { span, .. } |
+            Statement::Assignment { span, .. } |
+            Statement::FieldAssignment { span, .. } |
+            Statement::IndexAssignment { span, .. } |
+            Statement::RitualCall { span, .. } |
+            Statement::Conditional { span, .. } |
+            Statement::Cycle { span, .. } |
+            Statement::Return(_, span) |
+            Statement::Expression(_, span) |
+            Statement::Error(span) => span.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct BeingVariable {
+    pub visibility: Visibility,
+    pub variable: Variable,
+    pub initializer: Option<Expression>,
+    /// Dichiarata in un blocco `memory`: il runtime la conserva da un'esecuzione all'altra
+    pub memory: bool,
+    pub span: Span,
+}
+
+/// A typed event declared in a `perceptions { ... }` block, handled by the ritual of the same name
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Perception {
+    pub name: String,
+    pub parameters: Vec<Variable>,
+    pub span: Span,
+}
+
+/// A native ritual declared in an `extensions { ... }` block and implemented by the host
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Extension {
+    pub visibility: Visibility,
+    pub name: String,
+    pub parameters: Vec<Variable>,
+    pub return_type: Type,
+    /// Posizione del tipo di ritorno; vuota subito dopo i parametri se è omesso
+    pub return_type_span: Span,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Being {
+    pub attributes: Vec<Attribute>,
+    pub name: String,
+    pub rituals: Vec<Ritual>,
+    pub variables: Vec<BeingVariable>,
+    pub perceptions: Vec<Perception>,
+    pub extensions: Vec<Extension>,
+    pub span: Span,
+}
+
+impl Being {
+    /// Name of the ritual run once when the being is created, after the variable initializers
+    pub const INIT_RITUAL: &'static str = "init";
+
+    pub fn init_ritual(&self) -> Option<&Ritual> {
+        self.rituals.iter().find(|ritual| ritual.name == Self::INIT_RITUAL)
+    }
+
+    pub fn perception(&self, name: &str) -> Option<&Perception> {
+        self.perceptions.iter().find(|perception| perception.name == name)
+    }
+
+    pub fn extension(&self, name: &str) -> Option<&Extension> {
+        self.extensions.iter().find(|extension| extension.name == name)
+    }
+}
+
+/// A plain data record declared with `essence Name { field: type; ... }`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Essence {
+    pub attributes: Vec<Attribute>,
+    pub name: String,
+    pub fields: Vec<Variable>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Realm {
+    pub attributes: Vec<Attribute>,
+    pub name: String,
+    pub essences: Vec<Essence>,
+    pub beings: Vec<Being>,
+    pub span: Span,
+}
+
+/// An `import "file.nervs";` declaration at the top of a source file
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Import {
+    /// Percorso come scritto nel sorgente, senza virgolette
+    pub path: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+pub struct Program {
+    /// Import del file principale; dopo il caricamento i realm importati sono già in `realms`
+    pub imports: Vec<Import>,
+    pub realms: Vec<Realm>,
+}
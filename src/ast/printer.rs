@@ -0,0 +1,391 @@
+// Stampa canonica del sorgente Nervs a partire dall'AST (usata da `nervs fmt`)
+//
+// I commenti vengono scartati dal lexer e quindi non sono preservati.
+
+use super::nodes::*;
+
+const INDENT: &str = "    ";
+
+/// Formats a program in the canonical Nervs style
+pub fn format_program(program: &Program) -> String {
+    let mut printer = Printer { out: String::new(), depth: 0 };
+    for import in &program.imports {
+        printer.line(&format!("import \"{}\";", import.path));
+    }
+    if !program.imports.is_empty() && !program.realms.is_empty() {
+        printer.out.push('\n');
+    }
+    for (i, realm) in program.realms.iter().enumerate() {
+        if i > 0 {
+            printer.out.push('\n');
+        }
+        printer.realm(realm);
+    }
+    printer.out
+}
+
+struct Printer {
+    out: String,
+    depth: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    // Un attributo per riga, prima della dichiarazione a cui si riferisce
+    fn attributes(&mut self, attributes: &[Attribute]) {
+        for attribute in attributes {
+            let text = if attribute.arguments.is_empty() {
+                format!("@{}", attribute.name)
+            } else {
+                format!("@{}({})", attribute.name, attribute.arguments.join(", "))
+            };
+            self.line(&text);
+        }
+    }
+
+    fn realm(&mut self, realm: &Realm) {
+        self.attributes(&realm.attributes);
+        self.line(&format!("realm {} {{", realm.name));
+        self.depth += 1;
+        // Le essence precedono i being
+        for (i, essence) in realm.essences.iter().enumerate() {
+            if i > 0 {
+                self.out.push('\n');
+            }
+            self.essence(essence);
+        }
+        for (i, being) in realm.beings.iter().enumerate() {
+            if i > 0 || !realm.essences.is_empty() {
+                self.out.push('\n');
+            }
+            self.being(being);
+        }
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn essence(&mut self, essence: &Essence) {
+        self.attributes(&essence.attributes);
+        self.line(&format!("essence {} {{", essence.name));
+        self.depth += 1;
+        for field in &essence.fields {
+            self.line(&format!("{}: {};", field.name, type_name(&field.var_type)));
+        }
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn being(&mut self, being: &Being) {
+        self.attributes(&being.attributes);
+        self.line(&format!("being {} {{", being.name));
+        self.depth += 1;
+        // Le variabili consecutive di memoria formano un unico blocco `memory`
+        let mut variables = being.variables.iter().peekable();
+        while let Some(var) = variables.next() {
+            if !var.memory {
+                self.being_variable(var);
+                continue;
+            }
+            self.line("memory {");
+            self.depth += 1;
+            self.being_variable(var);
+            while let Some(var) = variables.next_if(|var| var.memory) {
+                self.being_variable(var);
+            }
+            self.depth -= 1;
+            self.line("}");
+        }
+        if !being.perceptions.is_empty() {
+            if !being.variables.is_empty() {
+                self.out.push('\n');
+            }
+            self.line("perceptions {");
+            self.depth += 1;
+            for perception in &being.perceptions {
+                self.line(&format!("{}({});", perception.name, parameter_list(&perception.parameters)));
+            }
+            self.depth -= 1;
+            self.line("}");
+        }
+        if !being.extensions.is_empty() {
+            if !being.variables.is_empty() || !being.perceptions.is_empty() {
+                self.out.push('\n');
+            }
+            self.line("extensions {");
+            self.depth += 1;
+            for extension in &being.extensions {
+                self.line(&format!(
+                    "{};",
+                    signature(extension.visibility, &extension.name, &extension.parameters, &extension.return_type)
+                ));
+            }
+            self.depth -= 1;
+            self.line("}");
+        }
+        let has_sections = !being.variables.is_empty() || !being.perceptions.is_empty() || !being.extensions.is_empty();
+        for (i, ritual) in being.rituals.iter().enumerate() {
+            if i > 0 || has_sections {
+                self.out.push('\n');
+            }
+            self.ritual(ritual);
+        }
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn being_variable(&mut self, var: &BeingVariable) {
+        let text = declaration(&var.variable, var.initializer.as_ref());
+        self.line(&format!("{}{}", visibility(var.visibility), text));
+    }
+
+    fn ritual(&mut self, ritual: &Ritual) {
+        self.attributes(&ritual.attributes);
+        self.line(&format!(
+            "{} {{",
+            signature(ritual.visibility, &ritual.name, &ritual.parameters, &ritual.return_type)
+        ));
+        self.block(&ritual.body);
+        self.line("}");
+    }
+
+    fn block(&mut self, statements: &[Statement]) {
+        self.depth += 1;
+        for stmt in statements {
+            self.statement(stmt);
+        }
+        self.depth -= 1;
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::VariableDeclaration { variable, initializer, .. } => {
+                let text = declaration(variable, initializer.as_ref());
+                self.line(&text);
+            }
+            Statement::Assignment { name, value, .. } => {
+                self.line(&format!("{} = {};", name, expression(value)));
+            }
+            Statement::FieldAssignment { object, field, value, .. } => {
+                let target = operand(object, POSTFIX_PRECEDENCE, false);
+                self.line(&format!("{}.{} = {};", target, field, expression(value)));
+            }
+            Statement::IndexAssignment { object, indices, value, .. } => {
+                let target = operand(object, POSTFIX_PRECEDENCE, false);
+                self.line(&format!("{}[{}] = {};", target, argument_list(indices), expression(value)));
+            }
+            Statement::RitualCall { name, arguments, .. } => {
+                self.line(&format!("{}({});", name, argument_list(arguments)));
+            }
+            Statement::Conditional { condition, true_branch, false_branch, .. } => {
+                self.line(&format!("if ({}) {{", expression(condition)));
+                self.block(true_branch);
+                self.else_branch(false_branch.as_deref());
+            }
+            Statement::Cycle { condition, body, .. } => {
+                match condition {
+                    Some(cond) => self.line(&format!("cycle ({}) {{", expression(cond))),
+                    None => self.line("cycle {"),
+                }
+                self.block(body);
+                self.line("}");
+            }
+            Statement::Return(Some(value), _) => self.line(&format!("return {};", expression(value))),
+            Statement::Return(None, _) => self.line("return;"),
+            Statement::Expression(expr, _) => self.line(&format!("{};", expression(expr))),
+            Statement::Error(_) => self.line("/* error */;"),
+        }
+    }
+
+    // Chiude il ramo vero, stampando `else if` per le catene di condizioni
+    fn else_branch(&mut self, false_branch: Option<&[Statement]>) {
+        match false_branch {
+            None => self.line("}"),
+            Some([Statement::Conditional { condition, true_branch, false_branch, .. }]) => {
+                self.line(&format!("}} else if ({}) {{", expression(condition)));
+                self.block(true_branch);
+                self.else_branch(false_branch.as_deref());
+            }
+            Some(statements) => {
+                self.line("} else {");
+                self.block(statements);
+                self.line("}");
+            }
+        }
+    }
+}
+
+// La visibilità predefinita è privata e non viene scritta
+fn visibility(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "public ",
+        Visibility::Private => "",
+    }
+}
+
+fn declaration(variable: &Variable, initializer: Option<&Expression>) -> String {
+    match initializer {
+        Some(init) => format!("{} {}: {};", type_name(&variable.var_type), variable.name, expression(init)),
+        None => format!("{} {};", type_name(&variable.var_type), variable.name),
+    }
+}
+
+/// Gets the source spelling of a type
+pub fn type_name(t: &Type) -> String {
+    match t {
+        Type::Integer => "int".to_string(),
+        Type::Float => "float".to_string(),
+        Type::String => "string".to_string(),
+        Type::Boolean => "bool".to_string(),
+        Type::Void => "void".to_string(),
+        Type::Custom(name) => name.clone(),
+        Type::Hive { element, dimensions, circular, persistent } => {
+            let dimensions: Vec<String> = dimensions.iter().map(usize::to_string).collect();
+            format!(
+                "{}{}hive<{}>[{}]",
+                if *circular { "circular " } else { "" },
+                if *persistent { "persistent " } else { "" },
+                type_name(element),
+                dimensions.join(", ")
+            )
+        }
+        Type::Error => "{error}".to_string(),
+    }
+}
+
+// `ritual nome(parametri) tipo`, senza tipo di ritorno se è void
+fn signature(vis: Visibility, name: &str, parameters: &[Variable], return_type: &Type) -> String {
+    let return_type = match return_type {
+        Type::Void => String::new(),
+        t => format!(" {}", type_name(t)),
+    };
+    format!("{}ritual {}({}){}", visibility(vis), name, parameter_list(parameters), return_type)
+}
+
+/// Formats declared parameters as `a: int, b: float`
+pub fn parameter_list(parameters: &[Variable]) -> String {
+    let parameters: Vec<String> = parameters.iter()
+        .map(|param| format!("{}: {}", param.name, type_name(&param.var_type)))
+        .collect();
+    parameters.join(", ")
+}
+
+fn argument_list(arguments: &[Expression]) -> String {
+    arguments.iter().map(expression).collect::<Vec<_>>().join(", ")
+}
+
+// Precedenza degli operatori binari, dal più largo al più stretto (come nel parser)
+fn precedence(operator: &BinaryOperator) -> u8 {
+    match operator {
+        BinaryOperator::Or => 1,
+        BinaryOperator::And => 2,
+        BinaryOperator::Equal | BinaryOperator::NotEqual => 3,
+        BinaryOperator::LessThan | BinaryOperator::GreaterThan |
+        BinaryOperator::LessEqual | BinaryOperator::GreaterEqual => 4,
+        BinaryOperator::Add | BinaryOperator::Subtract => 5,
+        BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 6,
+    }
+}
+
+const UNARY_PRECEDENCE: u8 = 7;
+const POSTFIX_PRECEDENCE: u8 = 8;
+
+/// Gets the source spelling of a binary operator
+pub fn operator_symbol(operator: &BinaryOperator) -> &'static str {
+    match operator {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Modulo => "%",
+        BinaryOperator::Equal => "==",
+        BinaryOperator::NotEqual => "!=",
+        BinaryOperator::LessThan => "<",
+        BinaryOperator::GreaterThan => ">",
+        BinaryOperator::LessEqual => "<=",
+        BinaryOperator::GreaterEqual => ">=",
+        BinaryOperator::And => "&&",
+        BinaryOperator::Or => "||",
+    }
+}
+
+/// Gets the source spelling of a unary operator
+pub fn unary_symbol(operator: &UnaryOperator) -> &'static str {
+    match operator {
+        UnaryOperator::Negate => "-",
+        UnaryOperator::Not => "!",
+    }
+}
+
+/// Formats an expression with the minimal parentheses required by precedence
+pub fn expression(expr: &Expression) -> String {
+    match expr {
+        Expression::Literal(literal, _) => match literal {
+            Literal::Integer(n) => n.to_string(),
+            Literal::Float(f) => {
+                // Il lexer richiede sempre la parte decimale
+                let text = f.to_string();
+                if text.contains('.') { text } else { format!("{}.0", text) }
+            }
+            Literal::String(s) => format!("\"{}\"", s),
+            Literal::Boolean(b) => b.to_string(),
+        },
+        Expression::Variable(name, _) => name.clone(),
+        Expression::BeingReference { realm, being, .. } => format!("{}::{}", realm, being),
+        Expression::BinaryOperation { left, operator, right, .. } => {
+            let level = precedence(operator);
+            // Associatività a sinistra: a destra servono parentesi anche a pari precedenza
+            let left_text = operand(left, level, false);
+            let right_text = operand(right, level, true);
+            format!("{} {} {}", left_text, operator_symbol(operator), right_text)
+        }
+        // Gli operatori unari annidati sono separati da parentesi: `-(-x)` invece di `--x`
+        Expression::UnaryOperation { operator, operand: inner, .. } => match **inner {
+            Expression::UnaryOperation { .. } => format!("{}({})", unary_symbol(operator), expression(inner)),
+            _ => format!("{}{}", unary_symbol(operator), operand(inner, UNARY_PRECEDENCE, false)),
+        },
+        Expression::FunctionCall { name, arguments, .. } => {
+            format!("{}({})", name, argument_list(arguments))
+        }
+        // L'accesso ai membri lega più di ogni operatore binario
+        Expression::FieldAccess { object, field, .. } => {
+            format!("{}.{}", operand(object, POSTFIX_PRECEDENCE, false), field)
+        }
+        Expression::MethodCall { object, name, arguments, .. } => {
+            format!("{}.{}({})", operand(object, POSTFIX_PRECEDENCE, false), name, argument_list(arguments))
+        }
+        Expression::Index { object, indices, .. } => {
+            format!("{}[{}]", operand(object, POSTFIX_PRECEDENCE, false), argument_list(indices))
+        }
+        Expression::Construction { essence, fields, .. } if fields.is_empty() => format!("{} {{}}", essence),
+        Expression::Construction { essence, fields, .. } => {
+            let fields: Vec<String> = fields.iter()
+                .map(|field| format!("{}: {}", field.name, expression(&field.value)))
+                .collect();
+            format!("{} {{ {} }}", essence, fields.join(", "))
+        }
+        Expression::Error(_) => "/* error */".to_string(),
+    }
+}
+
+fn operand(expr: &Expression, parent: u8, right: bool) -> String {
+    let text = expression(expr);
+    match expr {
+        Expression::BinaryOperation { operator, .. } => {
+            let level = precedence(operator);
+            if level < parent || (right && level == parent) {
+                format!("({})", text)
+            } else {
+                text
+            }
+        }
+        Expression::UnaryOperation { .. } if UNARY_PRECEDENCE < parent => format!("({})", text),
+        _ => text,
+    }
+}
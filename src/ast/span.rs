@@ -0,0 +1,127 @@
+use std::ops::Range;
+
+/// Byte range of a node or token in the source text
+///
+/// The files of a program share a single offset space (see [`SourceMap::add_file`]), so a span
+/// also identifies the file it belongs to.
+pub type Span = Range<usize>;
+
+/// Maps byte offsets of the source files of a program to file names and line and column positions
+pub struct SourceMap {
+    /// The main file first, then the imported files in loading order
+    files: Vec<SourceFile>,
+}
+
+struct SourceFile {
+    name: String,
+    source: String,
+    /// Offset of the first byte of the file in the shared offset space
+    start: usize,
+    /// Byte offset at which each line starts, relative to the file
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(name: String, source: String, start: usize) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        SourceFile { name, source, start, line_starts }
+    }
+
+    // Indice della riga (da 0) che contiene un offset relativo al file
+    fn line_index(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        }
+    }
+}
+
+impl SourceMap {
+    /// Creates a source map for a named main source file
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Self {
+        SourceMap { files: vec![SourceFile::new(name.into(), source.into(), 0)] }
+    }
+
+    /// Adds another source file and returns the offset of its first byte; spans of the file
+    /// must be shifted by this offset
+    pub fn add_file(&mut self, name: impl Into<String>, source: impl Into<String>) -> usize {
+        // Un byte di separazione: la fine di un file non coincide con l'inizio del successivo
+        let start = self.files.last().map(|file| file.start + file.source.len() + 1).unwrap_or(0);
+        self.files.push(SourceFile::new(name.into(), source.into(), start));
+        start
+    }
+
+    /// Gets the name of the main source file
+    pub fn name(&self) -> &str {
+        &self.files[0].name
+    }
+
+    /// Gets the full text of the main source file
+    pub fn source(&self) -> &str {
+        &self.files[0].source
+    }
+
+    // File che contiene un offset; gli offset oltre la fine appartengono all'ultimo file
+    fn file(&self, offset: usize) -> &SourceFile {
+        let index = self.files.partition_point(|file| file.start <= offset);
+        &self.files[index.saturating_sub(1)]
+    }
+
+    /// Gets the name of the file containing a byte offset
+    pub fn file_name(&self, offset: usize) -> &str {
+        &self.file(offset).name
+    }
+
+    /// Converts an offset of the shared offset space to a byte offset in its own file
+    pub fn local_offset(&self, offset: usize) -> usize {
+        let file = self.file(offset);
+        (offset - file.start).min(file.source.len())
+    }
+
+    /// Resolves a byte offset to a 1-based (line, column) pair within its file
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let file = self.file(offset);
+        let offset = self.local_offset(offset);
+        let line = file.line_index(offset);
+        let column = file.source[file.line_starts[line]..offset].chars().count();
+        (line + 1, column + 1)
+    }
+
+    /// Gets the text of the line containing a byte offset, without the trailing newline
+    pub fn line_text(&self, offset: usize) -> &str {
+        let file = self.file(offset);
+        let line = file.line_index(self.local_offset(offset));
+        let start = file.line_starts[line];
+        let end = file.line_starts.get(line + 1).copied().unwrap_or(file.source.len());
+        file.source[start..end].trim_end_matches(['\n', '\r'])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_resolve_to_lines_and_columns() {
+        let map = SourceMap::new("main.nervs", "realm A {\r\n  città x;\n}");
+        assert_eq!(map.line_col(0), (1, 1));
+        assert_eq!(map.line_col(13), (2, 3));
+        // Le colonne contano i caratteri, non i byte
+        assert_eq!(map.line_col(20), (2, 9));
+        assert_eq!(map.line_text(15), "  città x;");
+        assert_eq!(map.line_col(23), (3, 1));
+    }
+
+    #[test]
+    fn added_files_have_their_own_offsets() {
+        let mut map = SourceMap::new("main.nervs", "one\ntwo");
+        let start = map.add_file("lib.nervs", "three");
+        assert_eq!(start, 8);
+        assert_eq!(map.file_name(6), "main.nervs");
+        assert_eq!(map.file_name(start + 2), "lib.nervs");
+        assert_eq!(map.line_col(start + 2), (1, 3));
+        assert_eq!(map.line_text(start), "three");
+    }
+}
@@ -1,10 +1,1064 @@
-use crate::ast::nodes::Program;
-use std::path::Path;
-use std::error::Error;
-
-/// Generates C code from the AST
-pub fn generate_code(_program: &Program, _output_dir: &Path) -> Result<(), Box<dyn Error>> {
-    // Placeholder implementation
-    println!("Code generation not yet implemented");
-    Ok(())
-}
\ No newline at end of file
+// Traduzione di un programma già verificato in C99 portabile
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::error::Error;
+use crate::ast::nodes::{BinaryOperator, Being, Essence, Expression, Literal, Program, Realm, Ritual, Statement, Type, UnaryOperator, Variable};
+use super::CodegenError;
+
+/// Header with the being structs and ritual prototypes
+pub const HEADER_FILE: &str = "nervs.h";
+/// Implementation of every ritual
+pub const SOURCE_FILE: &str = "nervs.c";
+/// Entry point creating and initialising one instance of each being
+pub const MAIN_FILE: &str = "main.c";
+
+/// Generates C code from the AST
+pub fn generate_code(program: &Program, output_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let files = [
+        (HEADER_FILE, header(program)?),
+        (SOURCE_FILE, source(program)?),
+        (MAIN_FILE, main_stub(program)),
+    ];
+
+    std::fs::create_dir_all(output_dir)?;
+    for (name, contents) in files {
+        std::fs::write(output_dir.join(name), contents)?;
+    }
+    Ok(())
+}
+
+// Parole riservate del C (e il parametro implicito `self`) che non possono essere usate come identificatori
+const RESERVED: &[&str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else",
+    "enum", "extern", "float", "for", "goto", "if", "inline", "int", "long", "register",
+    "restrict", "return", "short", "signed", "sizeof", "static", "struct", "switch", "typedef",
+    "union", "unsigned", "void", "volatile", "while", "bool", "true", "false", "main", "self",
+];
+
+// Nome C di una variabile locale, di un parametro o di un campo
+fn identifier(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("nv_{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+// Nome C della struct di un being, prefissato dal realm
+fn being_type(realm: &Realm, being: &Being) -> String {
+    format!("{}__{}", realm.name, being.name)
+}
+
+// Nome C della struct di un'essence, prefissato dal realm come per i being
+fn essence_type(realm: &Realm, essence: &Essence) -> String {
+    format!("{}__{}", realm.name, essence.name)
+}
+
+// Confronto campo per campo tra due valori di un'essence
+fn equals_function(realm: &Realm, essence: &Essence) -> String {
+    format!("{}__{}__equals", realm.name, essence.name)
+}
+
+fn find_essence<'r>(realm: &'r Realm, name: &str) -> Option<&'r Essence> {
+    realm.essences.iter().find(|essence| essence.name == name)
+}
+
+// Realm in cui è dichiarato un tipo e nome del tipo al suo interno; i nomi senza realm
+// (`Nome` invece di `Realm::Nome`) appartengono a `realm`
+fn resolve_name<'p, 'n>(program: &'p Program, realm: &'p Realm, name: &'n str) -> Option<(&'p Realm, &'n str)> {
+    match name.split_once("::") {
+        Some((realm_name, name)) => Some((program.realms.iter().find(|r| r.name == realm_name)?, name)),
+        None => Some((realm, name)),
+    }
+}
+
+fn find_essence_type<'p>(program: &'p Program, realm: &'p Realm, name: &str) -> Option<(&'p Realm, &'p Essence)> {
+    let (realm, name) = resolve_name(program, realm, name)?;
+    Some((realm, find_essence(realm, name)?))
+}
+
+fn find_being_type<'p>(program: &'p Program, realm: &'p Realm, name: &str) -> Option<(&'p Realm, &'p Being)> {
+    let (realm, name) = resolve_name(program, realm, name)?;
+    Some((realm, realm.beings.iter().find(|being| being.name == name)?))
+}
+
+// Tipo scritto in `realm`, con il nome del realm esplicito: resta valido anche in altri realm
+fn qualified(realm: &Realm, t: Type) -> Type {
+    match t {
+        Type::Custom(name) if !name.contains("::") => Type::Custom(format!("{}::{}", realm.name, name)),
+        other => other,
+    }
+}
+
+// Nome C della struct di una hive, condivisa da tutte le hive con le stesse dimensioni
+fn hive_type(dimensions: &[usize]) -> String {
+    let dimensions: Vec<String> = dimensions.iter().map(usize::to_string).collect();
+    format!("nervs_hive_{}", dimensions.join("x"))
+}
+
+fn hive_equals_function(dimensions: &[usize]) -> String {
+    format!("{}_equals", hive_type(dimensions))
+}
+
+fn ritual_function(realm: &Realm, being: &Being, ritual: &str) -> String {
+    format!("{}__{}__{}", realm.name, being.name, ritual)
+}
+
+fn init_function(realm: &Realm, being: &Being) -> String {
+    format!("nervs_init__{}__{}", realm.name, being.name)
+}
+
+// Istanza globale di un being, creata e inizializzata da `main`
+fn instance(realm: &Realm, being: &Being) -> String {
+    format!("{}_instance", being_type(realm, being))
+}
+
+// Un valore di tipo being è un puntatore alla sua istanza; le essence e le hive sono struct passate per valore
+fn c_type(program: &Program, realm: &Realm, t: &Type) -> Result<String, CodegenError> {
+    match t {
+        Type::Integer => Ok("int64_t".to_string()),
+        Type::Float => Ok("double".to_string()),
+        Type::String => Ok("const char *".to_string()),
+        Type::Boolean => Ok("bool".to_string()),
+        Type::Void => Ok("void".to_string()),
+        Type::Custom(name) => match (find_essence_type(program, realm, name), find_being_type(program, realm, name)) {
+            (Some((realm, essence)), _) => Ok(essence_type(realm, essence)),
+            (None, Some((realm, being))) => Ok(format!("{} *", being_type(realm, being))),
+            (None, None) => Err(CodegenError::InvalidProgram),
+        },
+        Type::Hive { dimensions, .. } => Ok(hive_type(dimensions)),
+        Type::Error => Err(CodegenError::InvalidProgram),
+    }
+}
+
+// Valore iniziale delle variabili dichiarate senza inizializzatore
+fn default_value(program: &Program, realm: &Realm, t: &Type) -> Result<String, CodegenError> {
+    match t {
+        Type::Integer => Ok("0".to_string()),
+        Type::Float => Ok("0.0".to_string()),
+        Type::String => Ok("\"\"".to_string()),
+        Type::Boolean => Ok("false".to_string()),
+        Type::Custom(name) => match find_essence_type(program, realm, name) {
+            Some((realm, essence)) => Ok(format!("({}){{0}}", essence_type(realm, essence))),
+            None => Ok("NULL".to_string()),
+        },
+        Type::Hive { dimensions, .. } => Ok(format!("({}){{0}}", hive_type(dimensions))),
+        _ => Err(CodegenError::Unsupported(format!("variable of type {}", crate::ast::printer::type_name(t)))),
+    }
+}
+
+fn declaration(program: &Program, realm: &Realm, t: &Type, name: &str) -> Result<String, CodegenError> {
+    let ty = c_type(program, realm, t)?;
+    // Evita lo spazio doppio nei puntatori: `const char *name`
+    Ok(if ty.ends_with('*') { format!("{}{}", ty, name) } else { format!("{} {}", ty, name) })
+}
+
+// Prototipo di un ritual o di un'estensione, che riceve l'istanza del being come primo argomento
+fn signature(
+    program: &Program,
+    realm: &Realm,
+    being: &Being,
+    name: &str,
+    parameters: &[Variable],
+    return_type: &Type,
+) -> Result<String, CodegenError> {
+    let mut declarations = vec![format!("{} *self", being_type(realm, being))];
+    for param in parameters {
+        declarations.push(declaration(program, realm, &param.var_type, &identifier(&param.name))?);
+    }
+    let function = ritual_function(realm, being, name);
+    Ok(format!("{}({})", declaration(program, realm, return_type, &function)?, declarations.join(", ")))
+}
+
+// Testo C con indentazione a quattro spazi
+#[derive(Default)]
+struct Emitter {
+    out: String,
+    indent: usize,
+}
+
+impl Emitter {
+    fn line(&mut self, text: impl AsRef<str>) {
+        let text = text.as_ref();
+        if !text.is_empty() {
+            self.out.push_str(&"    ".repeat(self.indent));
+            self.out.push_str(text);
+        }
+        self.out.push('\n');
+    }
+
+    fn open(&mut self, text: impl AsRef<str>) {
+        self.line(text);
+        self.indent += 1;
+    }
+
+    fn close(&mut self, text: impl AsRef<str>) {
+        self.indent -= 1;
+        self.line(text);
+    }
+}
+
+fn beings(program: &Program) -> impl Iterator<Item = (&Realm, &Being)> {
+    program.realms.iter().flat_map(|realm| realm.beings.iter().map(move |being| (realm, being)))
+}
+
+// Dimensioni di tutte le hive dichiarate nel programma: le hive non hanno letterali, quindi ogni
+// valore di tipo hive ha un tipo scritto in una dichiarazione
+fn hive_shapes(program: &Program) -> BTreeSet<Vec<usize>> {
+    fn add(t: &Type, shapes: &mut BTreeSet<Vec<usize>>) {
+        if let Type::Hive { dimensions, .. } = t {
+            shapes.insert(dimensions.clone());
+        }
+    }
+
+    fn statements(body: &[Statement], shapes: &mut BTreeSet<Vec<usize>>) {
+        for stmt in body {
+            match stmt {
+                Statement::VariableDeclaration { variable, .. } => add(&variable.var_type, shapes),
+                Statement::Conditional { true_branch, false_branch, .. } => {
+                    statements(true_branch, shapes);
+                    statements(false_branch.as_deref().unwrap_or_default(), shapes);
+                }
+                Statement::Cycle { body, .. } => statements(body, shapes),
+                _ => {}
+            }
+        }
+    }
+
+    let mut shapes = BTreeSet::new();
+    for realm in &program.realms {
+        for essence in &realm.essences {
+            essence.fields.iter().for_each(|field| add(&field.var_type, &mut shapes));
+        }
+        for being in &realm.beings {
+            being.variables.iter().for_each(|var| add(&var.variable.var_type, &mut shapes));
+            for ritual in &being.rituals {
+                ritual.parameters.iter().for_each(|param| add(&param.var_type, &mut shapes));
+                add(&ritual.return_type, &mut shapes);
+                statements(&ritual.body, &mut shapes);
+            }
+            for extension in &being.extensions {
+                extension.parameters.iter().for_each(|param| add(&param.var_type, &mut shapes));
+                add(&extension.return_type, &mut shapes);
+            }
+        }
+    }
+    shapes
+}
+
+// Una struct deve essere definita prima delle essence che la contengono per valore, anche se
+// appartengono a un altro realm; l'analizzatore ha già escluso le essence ricorsive
+fn essences_in_dependency_order(program: &Program) -> Vec<(&Realm, &Essence)> {
+    fn visit<'p>(program: &'p Program, realm: &'p Realm, essence: &'p Essence, order: &mut Vec<(&'p Realm, &'p Essence)>) {
+        if order.iter().any(|(r, e)| std::ptr::eq(*r, realm) && std::ptr::eq(*e, essence)) {
+            return;
+        }
+        for field in &essence.fields {
+            if let Type::Custom(name) = &field.var_type {
+                if let Some((inner_realm, inner)) = find_essence_type(program, realm, name) {
+                    visit(program, inner_realm, inner, order);
+                }
+            }
+        }
+        order.push((realm, essence));
+    }
+
+    let mut order = Vec::new();
+    for realm in &program.realms {
+        for essence in &realm.essences {
+            visit(program, realm, essence, &mut order);
+        }
+    }
+    order
+}
+
+fn header(program: &Program) -> Result<String, CodegenError> {
+    let mut out = Emitter::default();
+    out.line("/* Generated by the Nervs compiler. Do not edit. */");
+    out.line("#ifndef NERVS_PROGRAM_H");
+    out.line("#define NERVS_PROGRAM_H");
+    out.line("");
+    out.line("#include <stdbool.h>");
+    out.line("#include <stdint.h>");
+
+    // Dichiarazioni anticipate: le variabili di un being possono riferirsi a being dichiarati dopo
+    out.line("");
+    for (realm, being) in beings(program) {
+        out.line(format!("typedef struct {0} {0};", being_type(realm, being)));
+    }
+
+    // Gli elementi di ogni hive sono `double`, come nel runtime, anche per le hive di interi
+    for dimensions in hive_shapes(program) {
+        let name = hive_type(&dimensions);
+        out.line("");
+        out.open("typedef struct {");
+        out.line(format!("double data[{}];", dimensions.iter().product::<usize>()));
+        out.close(format!("}} {};", name));
+    }
+
+    for (realm, essence) in essences_in_dependency_order(program) {
+        let name = essence_type(realm, essence);
+        out.line("");
+        out.line(format!("/* essence {} (realm {}) */", essence.name, realm.name));
+        out.open(format!("typedef struct {} {{", name));
+        for field in &essence.fields {
+            out.line(format!("{};", declaration(program, realm, &field.var_type, &identifier(&field.name))?));
+        }
+        if essence.fields.is_empty() {
+            out.line("char nervs_unused;");
+        }
+        out.close(format!("}} {};", name));
+    }
+
+    for (realm, being) in beings(program) {
+        let name = being_type(realm, being);
+        out.line("");
+        out.line(format!("/* being {} (realm {}) */", being.name, realm.name));
+        out.open(format!("struct {} {{", name));
+        for var in &being.variables {
+            out.line(format!("{};", declaration(program, realm, &var.variable.var_type, &identifier(&var.variable.name))?));
+        }
+        if being.variables.is_empty() {
+            // Il C99 non ammette struct senza membri
+            out.line("char nervs_unused;");
+        }
+        out.close("};");
+        out.line("");
+        out.line(format!("extern {} {};", name, instance(realm, being)));
+        out.line(format!("void {}({} *self);", init_function(realm, being), name));
+        for ritual in &being.rituals {
+            out.line(format!(
+                "{};",
+                signature(program, realm, being, &ritual.name, &ritual.parameters, &ritual.return_type)?
+            ));
+        }
+        // Le estensioni non vengono generate: le definisce il programma C che include l'header
+        for extension in &being.extensions {
+            out.line(format!(
+                "extern {};",
+                signature(program, realm, being, &extension.name, &extension.parameters, &extension.return_type)?
+            ));
+        }
+    }
+
+    out.line("");
+    out.line("#endif /* NERVS_PROGRAM_H */");
+    Ok(out.out)
+}
+
+// Funzioni di supporto: l'aritmetica intera segnala overflow e divisioni per zero come l'interprete
+const PRELUDE: &str = r#"static void nervs_panic(const char *message) {
+    fprintf(stderr, "nervs: %s\n", message);
+    exit(1);
+}
+
+static inline int64_t nervs_add(int64_t a, int64_t b) {
+    if ((b > 0 && a > INT64_MAX - b) || (b < 0 && a < INT64_MIN - b)) nervs_panic("integer overflow");
+    return a + b;
+}
+
+static inline int64_t nervs_sub(int64_t a, int64_t b) {
+    if ((b < 0 && a > INT64_MAX + b) || (b > 0 && a < INT64_MIN + b)) nervs_panic("integer overflow");
+    return a - b;
+}
+
+static inline int64_t nervs_mul(int64_t a, int64_t b) {
+    if (a > 0 ? (b > 0 ? a > INT64_MAX / b : b < INT64_MIN / a)
+              : (b > 0 ? a < INT64_MIN / b : (a != 0 && b < INT64_MAX / a))) nervs_panic("integer overflow");
+    return a * b;
+}
+
+static inline int64_t nervs_div(int64_t a, int64_t b) {
+    if (b == 0) nervs_panic("division by zero");
+    if (a == INT64_MIN && b == -1) nervs_panic("integer overflow");
+    return a / b;
+}
+
+static inline int64_t nervs_mod(int64_t a, int64_t b) {
+    if (b == 0) nervs_panic("division by zero");
+    if (a == INT64_MIN && b == -1) nervs_panic("integer overflow");
+    return a % b;
+}
+
+static inline int64_t nervs_neg(int64_t a) {
+    if (a == INT64_MIN) nervs_panic("integer overflow");
+    return -a;
+}
+
+static inline size_t nervs_hive_index(int64_t index, int64_t size, bool circular) {
+    if (circular) return (size_t)(((index % size) + size) % size);
+    if (index < 0 || index >= size) nervs_panic("hive index out of bounds");
+    return (size_t)index;
+}
+"#;
+
+fn source(program: &Program) -> Result<String, CodegenError> {
+    let mut out = Emitter::default();
+    out.line("/* Generated by the Nervs compiler. Do not edit. */");
+    out.line(format!("#include \"{}\"", HEADER_FILE));
+    out.line("");
+    out.line("#include <stdio.h>");
+    out.line("#include <stdlib.h>");
+    out.line("#include <string.h>");
+    out.line("");
+    out.out.push_str(PRELUDE);
+
+    for dimensions in hive_shapes(program) {
+        hive_equals(&dimensions, &mut out);
+    }
+
+    // Confronto tra essence, nello stesso ordine delle struct perché i campi annidati usano le funzioni precedenti
+    for (realm, essence) in essences_in_dependency_order(program) {
+        equals(program, realm, essence, &mut out);
+    }
+
+    out.line("");
+    for (realm, being) in beings(program) {
+        out.line(format!("{} {};", being_type(realm, being), instance(realm, being)));
+    }
+
+    for (realm, being) in beings(program) {
+        let mut writer = RitualWriter::new(program, realm, being, out);
+
+        // Inizializzazione delle variabili del being, nell'ordine di dichiarazione, seguita dal ritual `init`
+        writer.out.line("");
+        writer.out.open(format!("void {}({} *self) {{", init_function(realm, being), being_type(realm, being)));
+        for var in &being.variables {
+            let value = match &var.initializer {
+                Some(init) => writer.converted(init, &var.variable.var_type)?,
+                None => default_value(program, realm, &var.variable.var_type)?,
+            };
+            writer.out.line(format!("self->{} = {};", identifier(&var.variable.name), value));
+        }
+        if let Some(init) = being.init_ritual() {
+            writer.out.line(format!("{}(self);", ritual_function(realm, being, &init.name)));
+        } else if being.variables.is_empty() {
+            writer.out.line("(void)self;");
+        }
+        writer.out.close("}");
+
+        for ritual in &being.rituals {
+            writer.ritual(ritual)?;
+        }
+        out = writer.out;
+    }
+
+    Ok(out.out)
+}
+
+fn equals(program: &Program, realm: &Realm, essence: &Essence, out: &mut Emitter) {
+    let name = essence_type(realm, essence);
+    out.line("");
+    out.open(format!("static inline bool {}({} a, {} b) {{", equals_function(realm, essence), name, name));
+    let comparisons: Vec<String> = essence.fields.iter()
+        .map(|field| {
+            let field_name = identifier(&field.name);
+            let (a, b) = (format!("a.{}", field_name), format!("b.{}", field_name));
+            equality(program, realm, &field.var_type, &a, &b)
+        })
+        .collect();
+    if comparisons.is_empty() {
+        out.line("(void)a;");
+        out.line("(void)b;");
+        out.line("return true;");
+    } else {
+        out.line(format!("return {};", comparisons.join(" && ")));
+    }
+    out.close("}");
+}
+
+// Confronto elemento per elemento tra due hive con le stesse dimensioni
+fn hive_equals(dimensions: &[usize], out: &mut Emitter) {
+    let name = hive_type(dimensions);
+    out.line("");
+    out.open(format!("static inline bool {}({} a, {} b) {{", hive_equals_function(dimensions), name, name));
+    out.open(format!("for (size_t i = 0; i < {}; i++) {{", dimensions.iter().product::<usize>()));
+    out.line("if (a.data[i] != b.data[i]) return false;");
+    out.close("}");
+    out.line("return true;");
+    out.close("}");
+}
+
+// Espressione C che confronta due valori dello stesso tipo
+fn equality(program: &Program, realm: &Realm, t: &Type, a: &str, b: &str) -> String {
+    match t {
+        Type::String => format!("(strcmp({}, {}) == 0)", a, b),
+        Type::Custom(name) => match find_essence_type(program, realm, name) {
+            Some((realm, essence)) => format!("{}({}, {})", equals_function(realm, essence), a, b),
+            None => format!("({} == {})", a, b),
+        },
+        Type::Hive { dimensions, .. } => format!("{}({}, {})", hive_equals_function(dimensions), a, b),
+        _ => format!("({} == {})", a, b),
+    }
+}
+
+fn main_stub(program: &Program) -> String {
+    let mut out = Emitter::default();
+    out.line("/* Generated by the Nervs compiler. Do not edit. */");
+    out.line(format!("#include \"{}\"", HEADER_FILE));
+    out.line("");
+    out.open("int main(void) {");
+    for (realm, being) in beings(program) {
+        out.line(format!("{}(&{});", init_function(realm, being), instance(realm, being)));
+    }
+    out.line("return 0;");
+    out.close("}");
+    out.out
+}
+
+// Generatore del corpo dei ritual di un being. I tipi calcolati sono sempre qualificati con il
+// realm (`Realm::Nome`), perché i valori possono provenire da being di altri realm
+struct RitualWriter<'a> {
+    program: &'a Program,
+    realm: &'a Realm,
+    being: &'a Being,
+    // Tipi delle variabili locali visibili, dal blocco più esterno al più interno
+    scopes: Vec<HashMap<String, Type>>,
+    // Tipo di ritorno del ritual in corso di generazione
+    return_type: Type,
+    out: Emitter,
+}
+
+impl<'a> RitualWriter<'a> {
+    fn new(program: &'a Program, realm: &'a Realm, being: &'a Being, out: Emitter) -> Self {
+        RitualWriter { program, realm, being, scopes: Vec::new(), return_type: Type::Void, out }
+    }
+
+    fn ritual(&mut self, ritual: &Ritual) -> Result<(), CodegenError> {
+        self.out.line("");
+        self.out.open(format!("{} {{", signature(
+            self.program, self.realm, self.being, &ritual.name, &ritual.parameters, &ritual.return_type
+        )?));
+        self.out.line("(void)self;");
+        self.return_type = ritual.return_type.clone();
+        self.scopes.push(ritual.parameters.iter()
+            .map(|param| (param.name.clone(), qualified(self.realm, param.var_type.clone())))
+            .collect());
+        let result = self.statements(&ritual.body);
+        self.scopes.pop();
+        self.out.close("}");
+        result
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> Result<(), CodegenError> {
+        self.scopes.push(HashMap::new());
+        let result = statements.iter().try_for_each(|stmt| self.statement(stmt));
+        self.scopes.pop();
+        result
+    }
+
+    fn statement(&mut self, stmt: &Statement) -> Result<(), CodegenError> {
+        match stmt {
+            Statement::VariableDeclaration { variable, initializer, .. } => {
+                let value = match initializer {
+                    Some(init) => self.converted(init, &variable.var_type)?,
+                    None => default_value(self.program, self.realm, &variable.var_type)?,
+                };
+                let name = identifier(&variable.name);
+                self.out.line(format!("{} = {};", declaration(self.program, self.realm, &variable.var_type, &name)?, value));
+                self.scopes.last_mut()
+                    .expect("a block always has a scope")
+                    .insert(variable.name.clone(), qualified(self.realm, variable.var_type.clone()));
+            }
+            Statement::Assignment { name, value, span } => {
+                let target = self.variable(name);
+                let target_type = self.expression_type(&Expression::Variable(name.clone(), span.clone()))?;
+                let value = self.converted(value, &target_type)?;
+                self.out.line(format!("{} = {};", target, value));
+            }
+            Statement::FieldAssignment { object, field, value, .. } => {
+                let target = self.field(object, field)?;
+                let value = self.converted(value, &self.field_type(object, field)?)?;
+                self.out.line(format!("{} = {};", target, value));
+            }
+            Statement::IndexAssignment { object, indices, value, .. } => {
+                let target = self.element(object, indices)?;
+                let value = self.value(value)?;
+                self.out.line(format!("{} = {};", target, value));
+            }
+            Statement::RitualCall { name, arguments, .. } => {
+                let call = self.call(name, arguments)?;
+                self.out.line(format!("{};", call));
+            }
+            Statement::Conditional { condition, true_branch, false_branch, .. } => {
+                let condition = self.value(condition)?;
+                self.out.open(format!("if ({}) {{", condition));
+                self.statements(true_branch)?;
+                self.else_branch(false_branch.as_deref())?;
+            }
+            Statement::Cycle { condition, body, .. } => {
+                match condition {
+                    Some(condition) => {
+                        let condition = self.value(condition)?;
+                        self.out.open(format!("while ({}) {{", condition));
+                    }
+                    None => self.out.open("for (;;) {"),
+                }
+                self.statements(body)?;
+                self.out.close("}");
+            }
+            Statement::Return(value, _) => match value {
+                Some(value) => {
+                    let value = self.converted(value, &self.return_type.clone())?;
+                    self.out.line(format!("return {};", value));
+                }
+                None => self.out.line("return;"),
+            },
+            Statement::Expression(expr, _) => {
+                let value = self.value(expr)?;
+                self.out.line(format!("{};", value));
+            }
+            Statement::Error(_) => return Err(CodegenError::InvalidProgram),
+        }
+        Ok(())
+    }
+
+    // Chiude un `if`, trasformando un ramo else composto da un solo condizionale in `else if`
+    fn else_branch(&mut self, false_branch: Option<&[Statement]>) -> Result<(), CodegenError> {
+        match false_branch {
+            None => self.out.close("}"),
+            Some([Statement::Conditional { condition, true_branch, false_branch, .. }]) => {
+                let condition = self.value(condition)?;
+                self.out.indent -= 1;
+                self.out.open(format!("}} else if ({}) {{", condition));
+                self.statements(true_branch)?;
+                self.else_branch(false_branch.as_deref())?;
+            }
+            Some(statements) => {
+                self.out.indent -= 1;
+                self.out.open("} else {");
+                self.statements(statements)?;
+                self.out.close("}");
+            }
+        }
+        Ok(())
+    }
+
+    fn local(&self, name: &str) -> Option<&Type> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    // Being con il nome indicato, eventualmente qualificato con il realm
+    fn find_being(&self, name: &str) -> Option<(&'a Realm, &'a Being)> {
+        find_being_type(self.program, self.realm, name)
+    }
+
+    // Le variabili non locali sono campi del being; il nome di un being indica la sua istanza
+    fn variable(&self, name: &str) -> String {
+        if self.local(name).is_some() {
+            return identifier(name);
+        }
+        match self.find_being(name) {
+            Some((realm, being)) if variable_type(self.being, name).is_none() => format!("(&{})", instance(realm, being)),
+            _ => format!("self->{}", identifier(name)),
+        }
+    }
+
+    fn call(&mut self, name: &str, arguments: &[Expression]) -> Result<String, CodegenError> {
+        self.call_on((self.realm, self.being), "self".to_string(), name, arguments)
+    }
+
+    // Chiamata a un ritual di `being`, con `object` come istanza
+    fn call_on(
+        &mut self,
+        (realm, being): (&Realm, &Being),
+        object: String,
+        name: &str,
+        arguments: &[Expression],
+    ) -> Result<String, CodegenError> {
+        let mut values = vec![object];
+        for (arg, param) in arguments.iter().zip(parameters(being, name)?) {
+            values.push(self.converted(arg, &param.var_type)?);
+        }
+        Ok(format!("{}({})", ritual_function(realm, being, name), values.join(", ")))
+    }
+
+    // Being a cui appartiene il valore di un'espressione usata con `.`, con il suo realm
+    fn owner(&self, object: &Expression) -> Result<(&'a Realm, &'a Being), CodegenError> {
+        match self.expression_type(object)? {
+            Type::Custom(name) => self.find_being(&name).ok_or(CodegenError::InvalidProgram),
+            _ => Err(CodegenError::InvalidProgram),
+        }
+    }
+
+    fn find_essence(&self, t: &Type) -> Option<(&'a Realm, &'a Essence)> {
+        match t {
+            Type::Custom(name) => find_essence_type(self.program, self.realm, name),
+            _ => None,
+        }
+    }
+
+    // Accesso a un campo: diretto per le essence, tramite puntatore per i being
+    fn field(&mut self, object: &Expression, field: &str) -> Result<String, CodegenError> {
+        let essence = self.find_essence(&self.expression_type(object)?).is_some();
+        let object = self.expression(object)?;
+        let operator = if essence { "." } else { "->" };
+        Ok(format!("{}{}{}", object, operator, identifier(field)))
+    }
+
+    // Elemento di una hive: gli indici vengono verificati (o riportati nei limiti, per le hive
+    // circolari) e combinati in ordine row-major
+    fn element(&mut self, object: &Expression, indices: &[Expression]) -> Result<String, CodegenError> {
+        let Type::Hive { dimensions, circular, .. } = self.expression_type(object)? else {
+            return Err(CodegenError::InvalidProgram);
+        };
+        let mut offset = String::new();
+        for (index, size) in indices.iter().zip(&dimensions) {
+            let position = format!("nervs_hive_index({}, {}, {})", self.value(index)?, size, circular);
+            offset = if offset.is_empty() { position } else { format!("({}) * {} + {}", offset, size, position) };
+        }
+        Ok(format!("{}.data[{}]", self.expression(object)?, offset))
+    }
+
+    // Tipo di un campo di un'essence o di una variabile di un being
+    fn field_type(&self, object: &Expression, field: &str) -> Result<Type, CodegenError> {
+        let object_type = self.expression_type(object)?;
+        let Type::Custom(name) = &object_type else {
+            return Err(CodegenError::InvalidProgram);
+        };
+        let field_type = match self.find_essence(&object_type) {
+            Some((realm, essence)) => essence.fields.iter()
+                .find(|f| f.name == field)
+                .map(|f| qualified(realm, f.var_type.clone())),
+            None => self.find_being(name)
+                .and_then(|(realm, being)| Some(qualified(realm, variable_type(being, field)?))),
+        };
+        field_type.ok_or(CodegenError::InvalidProgram)
+    }
+
+    // Valore destinato a una posizione di tipo `target`: gli interi vengono promossi esplicitamente a `double`
+    fn converted(&mut self, expr: &Expression, target: &Type) -> Result<String, CodegenError> {
+        if *target == Type::Float && self.expression_type(expr)? == Type::Integer {
+            return Ok(format!("(double){}", self.expression(expr)?));
+        }
+        self.value(expr)
+    }
+
+    // Espressione in posizione autonoma (argomento, condizione, valore assegnato), senza parentesi esterne
+    fn value(&mut self, expr: &Expression) -> Result<String, CodegenError> {
+        let code = self.expression(expr)?;
+        // Le operazioni binarie e unarie sono le uniche espressioni racchiuse per intero tra parentesi
+        Ok(match code.strip_prefix('(').and_then(|c| c.strip_suffix(')')) {
+            Some(inner) if matches!(expr, Expression::BinaryOperation { .. } | Expression::UnaryOperation { .. }) => {
+                inner.to_string()
+            }
+            _ => code,
+        })
+    }
+
+    fn expression(&mut self, expr: &Expression) -> Result<String, CodegenError> {
+        Ok(match expr {
+            Expression::Literal(literal, _) => match literal {
+                Literal::Integer(n) => n.to_string(),
+                Literal::Float(x) if x.is_finite() => format!("{:?}", x),
+                Literal::Float(_) => return Err(CodegenError::Unsupported("non-finite float literal".to_string())),
+                Literal::String(s) => string_literal(s),
+                Literal::Boolean(b) => b.to_string(),
+            },
+            Expression::Variable(name, _) => self.variable(name),
+            Expression::BeingReference { realm, being, .. } => {
+                let qualified = format!("{}::{}", realm, being);
+                let (realm, being) = self.find_being(&qualified).ok_or(CodegenError::InvalidProgram)?;
+                format!("(&{})", instance(realm, being))
+            }
+            Expression::BinaryOperation { left, operator, right, .. } => {
+                let left_type = self.expression_type(left)?;
+                let integers = left_type == Type::Integer && self.expression_type(right)? == Type::Integer;
+                let strings = left_type == Type::String;
+                // Essence e hive sono struct, confrontate con le funzioni generate
+                let structured = self.find_essence(&left_type).is_some() || matches!(left_type, Type::Hive { .. });
+                let l = self.expression(left)?;
+                let r = self.expression(right)?;
+                match operator {
+                    BinaryOperator::Equal if structured => equality(self.program, self.realm, &left_type, &l, &r),
+                    BinaryOperator::NotEqual if structured => {
+                        format!("(!{})", equality(self.program, self.realm, &left_type, &l, &r))
+                    }
+                    BinaryOperator::Add if integers => format!("nervs_add({}, {})", l, r),
+                    BinaryOperator::Subtract if integers => format!("nervs_sub({}, {})", l, r),
+                    BinaryOperator::Multiply if integers => format!("nervs_mul({}, {})", l, r),
+                    BinaryOperator::Divide if integers => format!("nervs_div({}, {})", l, r),
+                    BinaryOperator::Modulo => format!("nervs_mod({}, {})", l, r),
+                    BinaryOperator::Equal if strings => format!("(strcmp({}, {}) == 0)", l, r),
+                    BinaryOperator::NotEqual if strings => format!("(strcmp({}, {}) != 0)", l, r),
+                    _ => format!("({} {} {})", l, crate::ast::printer::operator_symbol(operator), r),
+                }
+            }
+            Expression::UnaryOperation { operator, operand, .. } => {
+                let integer = self.expression_type(operand)? == Type::Integer;
+                let code = self.expression(operand)?;
+                match operator {
+                    UnaryOperator::Negate if integer => format!("nervs_neg({})", code),
+                    UnaryOperator::Negate => format!("(-{})", code),
+                    UnaryOperator::Not => format!("(!{})", code),
+                }
+            }
+            Expression::FunctionCall { name, arguments, .. } => self.call(name, arguments)?,
+            Expression::FieldAccess { object, field, .. } => self.field(object, field)?,
+            // Gli elementi sono `double`: quelli delle hive di interi vengono riconvertiti
+            Expression::Index { object, indices, .. } => {
+                let element = self.element(object, indices)?;
+                match self.expression_type(expr)? {
+                    Type::Integer => format!("((int64_t){})", element),
+                    _ => element,
+                }
+            }
+            Expression::MethodCall { object, name, arguments, .. } => {
+                let being = self.owner(object)?;
+                let object = self.value(object)?;
+                self.call_on(being, object, name, arguments)?
+            }
+            // Letterale composto del C99, con i campi nell'ordine in cui sono scritti
+            Expression::Construction { essence, fields, .. } => {
+                let (realm, essence) = find_essence_type(self.program, self.realm, essence).ok_or(CodegenError::InvalidProgram)?;
+                let mut values = Vec::with_capacity(fields.len());
+                for field in fields {
+                    let declared = essence.fields.iter()
+                        .find(|declared| declared.name == field.name)
+                        .ok_or(CodegenError::InvalidProgram)?;
+                    let value = self.converted(&field.value, &declared.var_type)?;
+                    values.push(format!(".{} = {}", identifier(&field.name), value));
+                }
+                if values.is_empty() {
+                    values.push("0".to_string());
+                }
+                format!("({}){{ {} }}", essence_type(realm, essence), values.join(", "))
+            }
+            Expression::Error(_) => return Err(CodegenError::InvalidProgram),
+        })
+    }
+
+    // Tipo statico di un'espressione; il programma è già stato verificato dall'analizzatore
+    fn expression_type(&self, expr: &Expression) -> Result<Type, CodegenError> {
+        Ok(match expr {
+            Expression::Literal(literal, _) => match literal {
+                Literal::Integer(_) => Type::Integer,
+                Literal::Float(_) => Type::Float,
+                Literal::String(_) => Type::String,
+                Literal::Boolean(_) => Type::Boolean,
+            },
+            Expression::Variable(name, _) => match self.local(name) {
+                Some(t) => t.clone(),
+                None => match variable_type(self.being, name) {
+                    Some(t) => qualified(self.realm, t),
+                    None => self.find_being(name)
+                        .map(|(realm, being)| Type::Custom(format!("{}::{}", realm.name, being.name)))
+                        .ok_or(CodegenError::InvalidProgram)?,
+                },
+            },
+            Expression::BeingReference { realm, being, .. } => Type::Custom(format!("{}::{}", realm, being)),
+            Expression::BinaryOperation { left, operator, right, .. } => match operator {
+                BinaryOperator::Modulo => Type::Integer,
+                BinaryOperator::Add | BinaryOperator::Subtract |
+                BinaryOperator::Multiply | BinaryOperator::Divide => {
+                    if self.expression_type(left)? == Type::Float || self.expression_type(right)? == Type::Float {
+                        Type::Float
+                    } else {
+                        Type::Integer
+                    }
+                }
+                _ => Type::Boolean,
+            },
+            Expression::UnaryOperation { operator, operand, .. } => match operator {
+                UnaryOperator::Negate => self.expression_type(operand)?,
+                UnaryOperator::Not => Type::Boolean,
+            },
+            Expression::FunctionCall { name, .. } => qualified(self.realm, return_type(self.being, name)?),
+            Expression::FieldAccess { object, field, .. } => self.field_type(object, field)?,
+            Expression::Construction { essence, .. } => qualified(self.realm, Type::Custom(essence.clone())),
+            Expression::Index { object, .. } => match self.expression_type(object)? {
+                Type::Hive { element, .. } => *element,
+                _ => return Err(CodegenError::InvalidProgram),
+            },
+            Expression::MethodCall { object, name, .. } => {
+                let (realm, being) = self.owner(object)?;
+                qualified(realm, return_type(being, name)?)
+            }
+            Expression::Error(_) => return Err(CodegenError::InvalidProgram),
+        })
+    }
+}
+
+fn variable_type(being: &Being, name: &str) -> Option<Type> {
+    being.variables.iter()
+        .find(|var| var.variable.name == name)
+        .map(|var| var.variable.var_type.clone())
+}
+
+fn parameters<'b>(being: &'b Being, ritual: &str) -> Result<&'b [Variable], CodegenError> {
+    being.rituals.iter()
+        .find(|r| r.name == ritual)
+        .map(|r| r.parameters.as_slice())
+        .or_else(|| being.extension(ritual).map(|extension| extension.parameters.as_slice()))
+        .ok_or(CodegenError::InvalidProgram)
+}
+
+fn return_type(being: &Being, ritual: &str) -> Result<Type, CodegenError> {
+    being.rituals.iter()
+        .find(|r| r.name == ritual)
+        .map(|r| r.return_type.clone())
+        .or_else(|| being.extension(ritual).map(|extension| extension.return_type.clone()))
+        .ok_or(CodegenError::InvalidProgram)
+}
+
+// Letterale stringa C a partire dal testo sorgente Nervs, che conserva le sequenze di escape;
+// i caratteri non stampabili diventano sequenze ottali
+fn string_literal(raw: &str) -> String {
+    let mut out = String::from("\"");
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some(other) => other,
+                None => '\\',
+            },
+            c => c,
+        };
+        let mut buffer = [0; 4];
+        for &byte in c.encode_utf8(&mut buffer).as_bytes() {
+            match byte {
+                b'"' => out.push_str("\\\""),
+                b'\\' => out.push_str("\\\\"),
+                b'\n' => out.push_str("\\n"),
+                b'\t' => out.push_str("\\t"),
+                // Anche `?` viene codificato, per non formare trigraph
+                b' '..=b'~' if byte != b'?' => out.push(byte as char),
+                _ => out.push_str(&format!("\\{:03o}", byte)),
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use crate::Session;
+
+    // Directory temporanea rimossa alla fine del test
+    struct Scratch(std::path::PathBuf);
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn generated_c_compiles_and_runs() {
+        let program = Session::new("main.nervs", "
+            realm Math {
+                being Calc {
+                    public int calls: 0;
+
+                    public ritual factorial(n: int) int {
+                        calls = calls + 1;
+                        if (n <= 1) {
+                            return 1;
+                        }
+                        return n * factorial(n - 1);
+                    }
+
+                    public ritual average(a: int, b: float) float {
+                        return (a + b) / 2;
+                    }
+
+                    public ritual parity(n: int) string {
+                        if (n % 2 == 0 && n != 0) {
+                            return \"even\";
+                        }
+                        return \"odd or zero\";
+                    }
+                }
+
+                being Report {
+                    public ritual total() int {
+                        int result: Calc.factorial(3);
+                        return result + Calc.calls;
+                    }
+                }
+            }
+        ").analyze().expect("the program is valid");
+
+        let scratch = Scratch(std::env::temp_dir().join(format!("nervs-codegen-{}", std::process::id())));
+        let directory = &scratch.0;
+        generate_code(&program, directory).unwrap();
+        // Sostituisce il main generato con uno che stampa i risultati
+        std::fs::write(directory.join(MAIN_FILE), format!("\
+#include <stdio.h>
+#include \"{}\"
+
+int main(void) {{
+    nervs_init__Math__Calc(&Math__Calc_instance);
+    nervs_init__Math__Report(&Math__Report_instance);
+    printf(\"%lld\\n\", (long long)Math__Calc__factorial(&Math__Calc_instance, 5));
+    printf(\"%g\\n\", Math__Calc__average(&Math__Calc_instance, 2, 3.5));
+    printf(\"%s %s\\n\", Math__Calc__parity(&Math__Calc_instance, 4), Math__Calc__parity(&Math__Calc_instance, 0));
+    printf(\"%lld\\n\", (long long)Math__Report__total(&Math__Report_instance));
+    return 0;
+}}
+", HEADER_FILE)).unwrap();
+
+        let binary = directory.join("program");
+        let compiled = match Command::new("cc")
+            .args(["-std=c99", "-Wall", "-Wextra", "-pedantic", "-Werror", "-o"])
+            .arg(&binary)
+            .arg(directory.join(SOURCE_FILE))
+            .arg(directory.join(MAIN_FILE))
+            .arg("-lm")
+            .output()
+        {
+            Ok(output) => output,
+            Err(_) => {
+                eprintln!("skipping: no C compiler available");
+                return;
+            }
+        };
+        assert!(compiled.status.success(), "{}", String::from_utf8_lossy(&compiled.stderr));
+
+        let run = Command::new(&binary).output().unwrap();
+        assert!(run.status.success());
+        assert_eq!(String::from_utf8_lossy(&run.stdout), "120\n2.75\neven odd or zero\n14\n");
+    }
+
+    #[test]
+    fn extension_signatures_declare_their_hive_types() {
+        let program = Session::new("main.nervs", "
+            realm Host {
+                being Filter {
+                    extensions {
+                        ritual smooth(h: hive<float>[5]) hive<float>[2, 3];
+                    }
+                }
+            }
+        ").analyze().expect("the program is valid");
+
+        let scratch = Scratch(std::env::temp_dir().join(format!("nervs-extensions-{}", std::process::id())));
+        let directory = &scratch.0;
+        generate_code(&program, directory).unwrap();
+        let header = std::fs::read_to_string(directory.join(HEADER_FILE)).unwrap();
+        assert!(header.contains("} nervs_hive_5;") && header.contains("} nervs_hive_2x3;"), "{}", header);
+
+        // Le estensioni sono definite dal programma ospite: basta compilare senza collegare
+        let compiled = match Command::new("cc")
+            .args(["-std=c99", "-Wall", "-Wextra", "-pedantic", "-Werror", "-c", "-o"])
+            .arg(directory.join("nervs.o"))
+            .arg(directory.join(SOURCE_FILE))
+            .output()
+        {
+            Ok(output) => output,
+            Err(_) => {
+                eprintln!("skipping: no C compiler available");
+                return;
+            }
+        };
+        assert!(compiled.status.success(), "{}", String::from_utf8_lossy(&compiled.stderr));
+    }
+}
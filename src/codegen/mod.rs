@@ -1,9 +1,19 @@
-pub mod generator;
-
-use crate::ast::nodes::Program;
-use std::path::Path;
-
-pub fn generate(_program: &Program, _output_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    // Da implementare
-    Ok(())
-}
\ No newline at end of file
+pub mod generator;
+
+use crate::ast::nodes::Program;
+use std::path::Path;
+
+/// Errors raised while lowering a program to C
+#[derive(Debug, thiserror::Error)]
+pub enum CodegenError {
+    #[error("{0} is not supported by the C backend")]
+    Unsupported(String),
+
+    #[error("the program contains errors and cannot be compiled")]
+    InvalidProgram,
+}
+
+/// Writes the C translation of a checked program into `output_dir`
+pub fn generate(program: &Program, output_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    generator::generate_code(program, output_dir)
+}
@@ -0,0 +1,341 @@
+// Rendering degli errori in stile rustc, condiviso da lexer, parser e analisi semantica
+
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write};
+use crate::ast::span::{SourceMap, Span};
+
+/// Severity of a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A secondary annotation attached to a region of the source
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// A compiler message pointing at the offending code
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    /// Testo mostrato accanto al sottolineato principale
+    pub primary_label: Option<String>,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Creates an error diagnostic at the given span
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic::new(Severity::Error, message.into(), span)
+    }
+
+    /// Creates a warning diagnostic at the given span
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Diagnostic::new(Severity::Warning, message.into(), span)
+    }
+
+    fn new(severity: Severity, message: String, span: Span) -> Self {
+        Diagnostic {
+            severity,
+            message,
+            span,
+            primary_label: None,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    /// Sets the text shown under the primary span
+    pub fn with_primary_label(mut self, message: impl Into<String>) -> Self {
+        self.primary_label = Some(message.into());
+        self
+    }
+
+    /// Adds a secondary label at another location
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into() });
+        self
+    }
+
+    /// Adds a `note:` line
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Adds a `help:` line
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Renders the diagnostic with the source snippet, optionally with ANSI colours
+    pub fn render(&self, source_map: &SourceMap, color: bool) -> String {
+        let style = Style { color };
+        let (title, accent) = match self.severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+        };
+
+        // Larghezza del margine: numero di cifre della riga più alta mostrata
+        let max_line = std::iter::once(&self.span)
+            .chain(self.labels.iter().map(|label| &label.span))
+            .map(|span| source_map.line_col(span.start).0)
+            .max()
+            .unwrap_or(1);
+        let gutter = max_line.to_string().len();
+        let pad = " ".repeat(gutter);
+
+        let mut out = String::new();
+        let _ = writeln!(out, "{}{}",
+            style.paint(&format!("{}:", title), accent),
+            style.paint(&format!(" {}", self.message), BOLD));
+
+        let file = source_map.file_name(self.span.start);
+        let (line, column) = source_map.line_col(self.span.start);
+        let _ = writeln!(out, "{}{} {}:{}:{}", pad, style.paint("-->", BLUE), file, line, column);
+        let _ = writeln!(out, "{} {}", pad, style.paint("|", BLUE));
+
+        // Le righe vengono mostrate nell'ordine del sorgente, anche se un'etichetta precede lo span principale;
+        // le etichette in altri file seguono quelle del file principale
+        let mut snippets = vec![(&self.span, '^', accent, self.primary_label.as_deref())];
+        snippets.extend(self.labels.iter().map(|label| (&label.span, '-', BLUE, Some(label.message.as_str()))));
+        snippets.sort_by_key(|(span, ..)| (source_map.file_name(span.start) != file, span.start));
+        let mut previous = (file, None);
+        for (span, marker, color, message) in snippets {
+            let span_file = source_map.file_name(span.start);
+            let line = source_map.line_col(span.start).0;
+            if span_file != previous.0 {
+                let column = source_map.line_col(span.start).1;
+                let _ = writeln!(out, "{}{} {}:{}:{}", pad, style.paint(":::", BLUE), span_file, line, column);
+                let _ = writeln!(out, "{} {}", pad, style.paint("|", BLUE));
+            }
+            // Più etichette sulla stessa riga condividono la riga di sorgente
+            let show_source = previous != (span_file, Some(line));
+            previous = (span_file, Some(line));
+            self.render_snippet(&mut out, source_map, &style, gutter, span, marker, color, message, show_source);
+        }
+
+        if !self.notes.is_empty() || !self.help.is_empty() {
+            let _ = writeln!(out, "{} {}", pad, style.paint("|", BLUE));
+        }
+        for note in &self.notes {
+            let _ = writeln!(out, "{} {} {} {}", pad, style.paint("=", BLUE), style.paint("note:", BOLD), note);
+        }
+        for help in &self.help {
+            let _ = writeln!(out, "{} {} {} {}", pad, style.paint("=", BLUE), style.paint("help:", BOLD), help);
+        }
+
+        out
+    }
+
+    // Mostra la riga del sorgente e sottolinea lo span (solo la prima riga se è su più righe)
+    #[allow(clippy::too_many_arguments)]
+    fn render_snippet(
+        &self,
+        out: &mut String,
+        source_map: &SourceMap,
+        style: &Style,
+        gutter: usize,
+        span: &Span,
+        marker: char,
+        accent: &str,
+        message: Option<&str>,
+        show_source: bool,
+    ) {
+        let (line, column) = source_map.line_col(span.start);
+        let text = source_map.line_text(span.start);
+        let (end_line, end_column) = source_map.line_col(span.end);
+        let line_len = text.chars().count();
+        let width = if end_line == line {
+            end_column.saturating_sub(column)
+        } else {
+            (line_len + 1).saturating_sub(column)
+        }
+        .max(1);
+
+        let underline = format!("{}{}",
+            marker.to_string().repeat(width),
+            message.map(|m| format!(" {}", m)).unwrap_or_default());
+
+        if show_source {
+            let _ = writeln!(out, "{} {} {}",
+                style.paint(&format!("{:>width$}", line, width = gutter), BLUE),
+                style.paint("|", BLUE),
+                text);
+        }
+        let _ = writeln!(out, "{} {} {}{}",
+            " ".repeat(gutter),
+            style.paint("|", BLUE),
+            " ".repeat(column - 1),
+            style.paint(&underline, accent));
+    }
+}
+
+impl Diagnostic {
+    /// Renders the diagnostic as a single-line JSON object, for tools and editors
+    pub fn to_json(&self, source_map: &SourceMap) -> String {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        // Gli offset sono relativi al file che contiene lo span
+        let location = |span: &Span| {
+            let (line, column) = source_map.line_col(span.start);
+            let (start, end) = (source_map.local_offset(span.start), source_map.local_offset(span.end));
+            format!("\"file\":{},\"start\":{},\"end\":{},\"line\":{},\"column\":{}",
+                json_string(source_map.file_name(span.start)), start, end, line, column)
+        };
+        let labels: Vec<String> = self.labels.iter()
+            .map(|label| format!("{{{},\"message\":{}}}", location(&label.span), json_string(&label.message)))
+            .collect();
+        let strings = |items: &[String]| items.iter().map(|s| json_string(s)).collect::<Vec<_>>().join(",");
+
+        format!(
+            "{{\"severity\":\"{}\",\"message\":{},{},\"label\":{},\"labels\":[{}],\"notes\":[{}],\"help\":[{}]}}",
+            severity,
+            json_string(&self.message),
+            location(&self.span),
+            self.primary_label.as_deref().map(json_string).unwrap_or_else(|| "null".to_string()),
+            labels.join(","),
+            strings(&self.notes),
+            strings(&self.help),
+        )
+    }
+}
+
+/// Quotes and escapes a string as a JSON string literal
+pub fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// How diagnostics and listings are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// rustc-style text with source snippets
+    Human,
+    /// One JSON object per line
+    Json,
+}
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(&self, text: &str, code: &str) -> String {
+        if self.color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Writes diagnostics to stderr, coloured when stderr is a terminal
+pub fn emit(diagnostics: &[Diagnostic], source_map: &SourceMap) {
+    emit_as(diagnostics, source_map, OutputFormat::Human);
+}
+
+/// Writes diagnostics to stderr in the requested format
+pub fn emit_as(diagnostics: &[Diagnostic], source_map: &SourceMap, format: OutputFormat) {
+    let stderr = std::io::stderr();
+    let color = stderr.is_terminal();
+    let mut handle = stderr.lock();
+    for diagnostic in diagnostics {
+        let _ = match format {
+            OutputFormat::Human => writeln!(handle, "{}", diagnostic.render(source_map, color)),
+            OutputFormat::Json => writeln!(handle, "{}", diagnostic.to_json(source_map)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn diagnostics_render_the_source_snippet() {
+        let map = SourceMap::new("main.nervs", "int x: 1;\nx = y + 2;\n");
+        let diagnostic = Diagnostic::error("undefined variable `y`", 14..15)
+            .with_primary_label("not found in this scope")
+            .with_label(4..5, "similar name declared here")
+            .with_help("did you mean `x`?");
+
+        assert_eq!(diagnostic.render(&map, false), "\
+error: undefined variable `y`
+ --> main.nervs:2:5
+  |
+1 | int x: 1;
+  |     - similar name declared here
+2 | x = y + 2;
+  |     ^ not found in this scope
+  |
+  = help: did you mean `x`?
+");
+    }
+
+    #[test]
+    fn diagnostics_serialize_to_json() {
+        let map = SourceMap::new("main.nervs", "int x: \"a\";");
+        let diagnostic = Diagnostic::warning("odd \"value\"", 7..10);
+        assert_eq!(
+            diagnostic.to_json(&map),
+            "{\"severity\":\"warning\",\"message\":\"odd \\\"value\\\"\",\"file\":\"main.nervs\",\"start\":7,\"end\":10,\
+             \"line\":1,\"column\":8,\"label\":null,\"labels\":[],\"notes\":[],\"help\":[]}"
+        );
+    }
+
+    #[test]
+    fn types_are_shown_with_their_source_names() {
+        let mut session = Session::new("main.nervs", "
+            realm R {
+                being B {
+                    ritual f() {
+                        hive<float>[2] h;
+                        int x: h;
+                    }
+                }
+            }
+        ");
+        assert!(session.analyze().is_err());
+        let rendered = session.diagnostics()[0].render(session.source_map(), false);
+        assert!(rendered.contains("expected int, found hive<float>[2]"), "{}", rendered);
+    }
+}
@@ -1,26 +1,75 @@
-mod token;
-
-pub use token::Token;
-use logos::Logos;
-
-#[derive(Debug, thiserror::Error)]
-pub enum LexerError {
-    #[error("Lexer error: {0}")]
-    Error(String),
-}
-
-/// Tokenizes the source code into a stream of tokens
-pub fn tokenize(source: &str) -> Result<Vec<Token>, LexerError> {
-    let lexer = Token::lexer(source);
-    
-    // Raccoglie i token, filtrando gli errori
-    let mut tokens = Vec::new();
-    for token_result in lexer {
-        match token_result {
-            Ok(token) => tokens.push(token),
-            Err(_) => return Err(LexerError::Error("Invalid token".to_string())),
-        }
-    }
-    
-    Ok(tokens)
-}
\ No newline at end of file
+mod token;
+
+pub use token::Token;
+use logos::Logos;
+use crate::ast::span::Span;
+use crate::diagnostics::Diagnostic;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LexerError {
+    #[error("Invalid token `{0}`")]
+    InvalidToken(String, Span),
+}
+
+impl LexerError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexerError::InvalidToken(_, span) => span.clone(),
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            LexerError::InvalidToken(text, span) => {
+                Diagnostic::error(format!("invalid token `{}`", text), span.clone())
+                    .with_primary_label("not recognised by the Nervs lexer")
+            }
+        }
+    }
+}
+
+/// Tokenizes the source code into a stream of tokens paired with their byte ranges
+pub fn tokenize(source: &str) -> Result<Vec<(Token, Span)>, LexerError> {
+    tokenize_from(source, 0)
+}
+
+/// Tokenizes a source file whose first byte is at `start` in the offset space of a
+/// [`SourceMap`](crate::ast::span::SourceMap)
+pub fn tokenize_from(source: &str, start: usize) -> Result<Vec<(Token, Span)>, LexerError> {
+    let lexer = Token::lexer(source).spanned();
+    
+    // Raccoglie i token, fermandosi al primo errore
+    let mut tokens = Vec::new();
+    for (token_result, span) in lexer {
+        let shifted = span.start + start..span.end + start;
+        match token_result {
+            Ok(token) => tokens.push((token, shifted)),
+            Err(_) => return Err(LexerError::InvalidToken(source[span].to_string(), shifted)),
+        }
+    }
+    
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_carry_their_byte_ranges() {
+        let tokens = tokenize("int x: 42;").unwrap();
+        let spans: Vec<Span> = tokens.iter().map(|(_, span)| span.clone()).collect();
+        assert_eq!(spans, [0..3, 4..5, 5..6, 7..9, 9..10]);
+        assert_eq!(tokens[3].0, Token::Number("42".to_string()));
+
+        let shifted = tokenize_from("x;", 100).unwrap();
+        assert_eq!(shifted[0], (Token::Identifier("x".to_string()), 100..101));
+    }
+
+    #[test]
+    fn invalid_tokens_report_their_position() {
+        let error = tokenize("int x: $;").unwrap_err();
+        assert_eq!(error.span(), 7..8);
+        assert_eq!(error.to_diagnostic().message, "invalid token `$`");
+    }
+}
@@ -1,86 +1,203 @@
-use logos::Logos;
-
-#[derive(Logos, Debug, PartialEq, Clone, Hash, Eq)]
-pub enum Token {
-    // Keywords
-    #[token("realm")]
-    Realm,
-    
-    #[token("essence")]
-    Essence,
-    
-    #[token("being")]
-    Being,
-    
-    #[token("ritual")]
-    Ritual,
-    
-    #[token("cycle")]
-    Cycle,
-    
-    #[token("seal")]
-    Seal,
-    
-    #[token("perceptions")]
-    Perceptions,
-    
-    #[token("extensions")]
-    Extensions,
-    
-    #[token("memory")]
-    Memory,
-    
-    #[token("hive")]
-    Hive,
-    
-    // Identifiers and literals
-    #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", priority = 1, callback = |lex| lex.slice().to_string())]
-    Identifier(String),
-    
-    #[regex(r#""([^"\\]|\\.)*""#, callback = |lex| lex.slice()[1..lex.slice().len()-1].to_string())]
-    String(String),
-    
-    #[regex(r"[0-9]+(\.[0-9]+)?", callback = |lex| lex.slice().to_string())]
-    Number(String),
-    
-    // Symbols
-    #[token("(")]
-    LParen,
-    
-    #[token(")")]
-    RParen,
-    
-    #[token("{")]
-    LBrace,
-    
-    #[token("}")]
-    RBrace,
-    
-    #[token("[")]
-    LBracket,
-    
-    #[token("]")]
-    RBracket,
-    
-    #[token(";")]
-    Semicolon,
-    
-    #[token(":")]
-    Colon,
-    
-    #[token(",")]
-    Comma,
-    
-    #[token(".")]
-    Dot,
-    
-    // Skip whitespace and comments
-    #[regex(r"[ \t\n\r]+", logos::skip)]
-    #[regex(r"//[^\n]*", logos::skip)]
-    #[regex(r"/\*([^*]|\*[^/])*\*/", logos::skip)]
-    Whitespace,
-    
-    // Error fallback
-
-    Error,
-}
\ No newline at end of file
+use logos::Logos;
+
+#[derive(Logos, Debug, PartialEq, Clone, Hash, Eq)]
+pub enum Token {
+    // Keywords
+    #[token("realm")]
+    Realm,
+    
+    #[token("essence")]
+    Essence,
+    
+    #[token("being")]
+    Being,
+    
+    #[token("ritual")]
+    Ritual,
+    
+    #[token("cycle")]
+    Cycle,
+    
+    #[token("seal")]
+    Seal,
+    
+    #[token("perceptions")]
+    Perceptions,
+    
+    #[token("extensions")]
+    Extensions,
+    
+    #[token("memory")]
+    Memory,
+    
+    #[token("hive")]
+    Hive,
+    
+    #[token("public")]
+    Public,
+    
+    #[token("private")]
+    Private,
+    
+    #[token("true")]
+    True,
+    
+    #[token("false")]
+    False,
+    
+    // Identifiers and literals
+    #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", priority = 1, callback = |lex| lex.slice().to_string())]
+    Identifier(String),
+    
+    #[regex(r#""([^"\\]|\\.)*""#, callback = |lex| lex.slice()[1..lex.slice().len()-1].to_string())]
+    String(String),
+    
+    #[regex(r"[0-9]+(\.[0-9]+)?", callback = |lex| lex.slice().to_string())]
+    Number(String),
+    
+    // Symbols
+    #[token("(")]
+    LParen,
+    
+    #[token(")")]
+    RParen,
+    
+    #[token("{")]
+    LBrace,
+    
+    #[token("}")]
+    RBrace,
+    
+    #[token("[")]
+    LBracket,
+    
+    #[token("]")]
+    RBracket,
+    
+    #[token(";")]
+    Semicolon,
+    
+    #[token(":")]
+    Colon,
+    
+    #[token("::")]
+    DoubleColon,
+    
+    #[token(",")]
+    Comma,
+    
+    #[token(".")]
+    Dot,
+    
+    #[token("@")]
+    At,
+    
+    // Operators
+    #[token("+")]
+    Plus,
+    
+    #[token("-")]
+    Minus,
+    
+    #[token("*")]
+    Star,
+    
+    #[token("/")]
+    Slash,
+    
+    #[token("%")]
+    Percent,
+    
+    #[token("&&")]
+    AndAnd,
+    
+    #[token("||")]
+    OrOr,
+    
+    #[token("!")]
+    Bang,
+    
+    #[token("==")]
+    EqualEqual,
+    
+    #[token("!=")]
+    NotEqual,
+    
+    #[token("<")]
+    Less,
+    
+    #[token(">")]
+    Greater,
+    
+    #[token("<=")]
+    LessEqual,
+    
+    #[token(">=")]
+    GreaterEqual,
+    
+    #[token("=")]
+    Assign,
+    
+    // Skip whitespace and comments
+    #[regex(r"[ \t\n\r]+", logos::skip)]
+    #[regex(r"//[^\n]*", logos::skip)]
+    #[regex(r"/\*([^*]|\*[^/])*\*/", logos::skip)]
+    Whitespace,
+    
+    // Error fallback
+
+    Error,
+}
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Token::Realm => "realm",
+            Token::Essence => "essence",
+            Token::Being => "being",
+            Token::Ritual => "ritual",
+            Token::Cycle => "cycle",
+            Token::Seal => "seal",
+            Token::Perceptions => "perceptions",
+            Token::Extensions => "extensions",
+            Token::Memory => "memory",
+            Token::Hive => "hive",
+            Token::Public => "public",
+            Token::Private => "private",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Identifier(name) => name,
+            Token::String(s) => return write!(f, "\"{}\"", s),
+            Token::Number(n) => n,
+            Token::At => "@",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::Semicolon => ";",
+            Token::Colon => ":",
+            Token::DoubleColon => "::",
+            Token::Comma => ",",
+            Token::Dot => ".",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::AndAnd => "&&",
+            Token::OrOr => "||",
+            Token::Bang => "!",
+            Token::EqualEqual => "==",
+            Token::NotEqual => "!=",
+            Token::Less => "<",
+            Token::Greater => ">",
+            Token::LessEqual => "<=",
+            Token::GreaterEqual => ">=",
+            Token::Assign => "=",
+            Token::Whitespace => "whitespace",
+            Token::Error => "error",
+        };
+        write!(f, "{}", text)
+    }
+}
@@ -0,0 +1,19 @@
+//! Compiler for the Nervs language.
+//!
+//! The individual stages are available as modules ([`lexer`], [`parser`],
+//! [`loader`], [`semantic`], [`codegen`], [`seal`], [`runtime`]); [`Session`]
+//! runs them in order over a source file and the files it imports, and
+//! collects the diagnostics.
+
+pub mod ast;
+pub mod codegen;
+pub mod diagnostics;
+pub mod lexer;
+pub mod loader;
+pub mod parser;
+pub mod runtime;
+pub mod seal;
+pub mod semantic;
+mod session;
+
+pub use session::{CompileError, CompileOptions, Session, Stage};
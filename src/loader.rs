@@ -0,0 +1,257 @@
+// Caricamento dei file importati: risolve gli `import`, rileva i cicli e unisce i realm in un solo Program
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use crate::ast::nodes::{Import, Program, Realm};
+use crate::ast::span::{SourceMap, Span};
+use crate::diagnostics::Diagnostic;
+use crate::{lexer, parser};
+
+/// Extension appended to imports written without one
+pub const EXTENSION: &str = "nervs";
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadError {
+    #[error("Cannot find imported file {path}")]
+    NotFound {
+        path: String,
+        span: Span,
+        /// Percorsi provati, nell'ordine
+        searched: Vec<PathBuf>,
+    },
+
+    #[error("Import cycle through {path}")]
+    Cycle {
+        path: String,
+        span: Span,
+        /// File coinvolti nel ciclo, dal primo importatore al file che chiude il ciclo
+        chain: Vec<String>,
+    },
+
+    #[error("Cannot read imported file {path}: {message}")]
+    Io {
+        path: String,
+        span: Span,
+        message: String,
+    },
+}
+
+impl LoadError {
+    pub fn span(&self) -> Span {
+        match self {
+            LoadError::NotFound { span, .. } |
+            LoadError::Cycle { span, .. } |
+            LoadError::Io { span, .. } => span.clone(),
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            LoadError::NotFound { path, span, searched } => {
+                let locations: Vec<String> = searched.iter().map(|p| p.display().to_string()).collect();
+                Diagnostic::error(format!("cannot find imported file `{}`", path), span.clone())
+                    .with_primary_label("file not found")
+                    .with_note(format!("searched: {}", locations.join(", ")))
+                    .with_help("add the directory containing the file with `--include`")
+            }
+            LoadError::Cycle { path, span, chain } => {
+                Diagnostic::error(format!("import cycle through `{}`", path), span.clone())
+                    .with_primary_label("this import closes the cycle")
+                    .with_note(format!("import chain: {}", chain.join(" -> ")))
+            }
+            LoadError::Io { path, span, message } => {
+                Diagnostic::error(format!("cannot read imported file `{}`", path), span.clone())
+                    .with_primary_label(message.clone())
+            }
+        }
+    }
+}
+
+/// Loads the files imported by a parsed main file and merges their realms into one program
+///
+/// Imports are resolved relative to the directory of the importing file first, then in each
+/// of the search paths in order. Every file is loaded once, however many times it is imported;
+/// the realms of an imported file come before those of the files importing it. The loaded
+/// sources are added to `source_map`, so the returned diagnostics point into the right file.
+pub fn load(
+    program: Program,
+    root: Option<&Path>,
+    source_map: &mut SourceMap,
+    search_paths: &[PathBuf],
+) -> (Program, Vec<Diagnostic>) {
+    let mut loader = Loader {
+        source_map,
+        search_paths,
+        loading: Vec::new(),
+        loaded: HashSet::new(),
+        realms: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    // Il file principale fa parte della catena: importarlo di nuovo è un ciclo
+    if let Some(path) = root {
+        loader.loading.push((canonical(path), display(path)));
+    }
+    let directory = root.and_then(Path::parent).unwrap_or(Path::new("")).to_path_buf();
+    loader.load_imports(&program.imports, &directory);
+
+    let Program { imports, realms } = program;
+    let mut merged = loader.realms;
+    merged.extend(realms);
+    (Program { imports, realms: merged }, loader.diagnostics)
+}
+
+struct Loader<'a> {
+    source_map: &'a mut SourceMap,
+    search_paths: &'a [PathBuf],
+    // File in corso di caricamento (percorso canonico, nome mostrato), dal principale al più interno
+    loading: Vec<(PathBuf, String)>,
+    // File già caricati, per non caricarli due volte
+    loaded: HashSet<PathBuf>,
+    realms: Vec<Realm>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Loader<'_> {
+    fn load_imports(&mut self, imports: &[Import], directory: &Path) {
+        for import in imports {
+            if let Err(error) = self.load_import(import, directory) {
+                self.diagnostics.push(error.to_diagnostic());
+            }
+        }
+    }
+
+    fn load_import(&mut self, import: &Import, directory: &Path) -> Result<(), LoadError> {
+        let path = self.resolve(import, directory)?;
+        let key = canonical(&path);
+        let name = display(&path);
+
+        if let Some(position) = self.loading.iter().position(|(loading, _)| *loading == key) {
+            let mut chain: Vec<String> = self.loading[position..].iter().map(|(_, name)| name.clone()).collect();
+            chain.push(name);
+            return Err(LoadError::Cycle { path: import.path.clone(), span: import.span.clone(), chain });
+        }
+        if !self.loaded.insert(key.clone()) {
+            return Ok(());
+        }
+
+        let source = std::fs::read_to_string(&path).map_err(|e| LoadError::Io {
+            path: import.path.clone(),
+            span: import.span.clone(),
+            message: e.to_string(),
+        })?;
+        let start = self.source_map.add_file(name.clone(), source.clone());
+
+        // Gli errori di un file importato non fermano il caricamento degli altri
+        let tokens = match lexer::tokenize_from(&source, start) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                self.diagnostics.push(e.to_diagnostic());
+                return Ok(());
+            }
+        };
+        let (program, errors) = parser::parse(tokens);
+        self.diagnostics.extend(errors.iter().map(parser::error::to_diagnostic));
+        let Some(program) = program else {
+            return Ok(());
+        };
+
+        // Prima le dipendenze del file, poi i suoi realm
+        self.loading.push((key, name));
+        let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        self.load_imports(&program.imports, &directory);
+        self.loading.pop();
+        self.realms.extend(program.realms);
+        Ok(())
+    }
+
+    // Percorso del file importato: prima accanto all'importatore, poi nei percorsi di ricerca
+    fn resolve(&self, import: &Import, directory: &Path) -> Result<PathBuf, LoadError> {
+        let mut relative = PathBuf::from(&import.path);
+        if relative.extension().is_none() {
+            relative.set_extension(EXTENSION);
+        }
+
+        let mut candidates: Vec<PathBuf> = Vec::new();
+        for base in std::iter::once(directory).chain(self.search_paths.iter().map(PathBuf::as_path)) {
+            let candidate = base.join(&relative);
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        match candidates.iter().find(|candidate| candidate.is_file()) {
+            Some(found) => Ok(found.clone()),
+            None => Err(LoadError::NotFound {
+                path: import.path.clone(),
+                span: import.span.clone(),
+                searched: candidates,
+            }),
+        }
+    }
+}
+
+// Percorso canonico, che identifica un file indipendentemente da come è stato raggiunto
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Nome mostrato nei diagnostic, senza il `./` iniziale
+fn display(path: &Path) -> String {
+    path.strip_prefix(".").unwrap_or(path).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CompileError, Session, Stage};
+
+    // Directory temporanea con i file di un test, rimossa alla fine
+    struct Scratch(std::path::PathBuf);
+
+    impl Scratch {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let directory = std::env::temp_dir().join(format!("nervs-loader-{}-{}", std::process::id(), name));
+            for (path, source) in files {
+                let path = directory.join(path);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(path, source).unwrap();
+            }
+            Scratch(directory)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn shared_imports_are_loaded_once() {
+        let scratch = Scratch::new("diamond", &[
+            ("main.nervs", "import \"left\"; import \"lib/right.nervs\"; realm Main { being App { public int x: 1; } }"),
+            ("left.nervs", "import \"common\"; realm Left { }"),
+            ("lib/right.nervs", "import \"../common.nervs\"; realm Right { }"),
+            ("common.nervs", "realm Common { }"),
+        ]);
+        let program = Session::from_file(&scratch.0.join("main.nervs")).unwrap().analyze().unwrap();
+        let realms: Vec<&str> = program.realms.iter().map(|realm| realm.name.as_str()).collect();
+        assert_eq!(realms, ["Common", "Left", "Right", "Main"]);
+    }
+
+    #[test]
+    fn import_cycles_are_reported() {
+        let scratch = Scratch::new("cycle", &[
+            ("a.nervs", "import \"b\"; realm A { }"),
+            ("b.nervs", "import \"c\"; realm B { }"),
+            ("c.nervs", "import \"a\"; realm C { }"),
+        ]);
+        let mut session = Session::from_file(&scratch.0.join("a.nervs")).unwrap();
+        assert!(matches!(session.analyze(), Err(CompileError::Diagnostics { stage: Stage::Import, count: 1 })));
+
+        let diagnostic = &session.diagnostics()[0];
+        assert!(diagnostic.message.starts_with("import cycle through"), "{}", diagnostic.message);
+        assert!(session.source_map().file_name(diagnostic.span.start).ends_with("c.nervs"));
+        assert_eq!(diagnostic.notes.len(), 1);
+        assert!(diagnostic.notes[0].contains("a.nervs -> ") && diagnostic.notes[0].contains("c.nervs"), "{}", diagnostic.notes[0]);
+    }
+}
@@ -1,143 +1,558 @@
-mod lexer;
-mod parser;
-mod ast;
-mod semantic;
-mod codegen;
-mod seal;
-mod runtime;
-
-use std::error::Error;
-use std::fs;
-use std::env;
-use std::path::{Path, PathBuf};
-
-fn main() -> Result<(), Box<dyn Error>> {
-    println!("Nervs Compiler - Starting");
-    
-    // Controlla se è stato fornito un file
-    let args: Vec<String> = env::args().collect();
-    
-    let source = if args.len() > 1 {
-        // Ottieni il percorso del file
-        let file_arg = &args[1];
-        
-        // Costruisci il percorso completo
-        let file_path = if Path::new(file_arg).is_absolute() {
-            PathBuf::from(file_arg)
-        } else {
-            // Se è un percorso relativo, prova prima nella directory corrente
-            let current_dir_path = PathBuf::from(file_arg);
-            if current_dir_path.exists() {
-                current_dir_path
-            } else {
-                // Altrimenti, prova nella directory parent (NERVS)
-                let parent_dir_path = Path::new("..").join(file_arg);
-                if parent_dir_path.exists() {
-                    parent_dir_path
-                } else {
-                    // Se non esiste neanche lì, usa il percorso originale e lascia
-                    // che il sistema di lettura file gestisca l'errore
-                    PathBuf::from(file_arg)
-                }
-            }
-        };
-        
-        println!("Reading source from file: {}", file_path.display());
-        fs::read_to_string(file_path)?
-    } else {
-        // Usa l'esempio incorporato
-        println!("No input file specified, using built-in example");
-        String::from(r#"
-realm MathRealm {
-    being Calculator {
-        // Variabili del being
-        int pi_approx: 314;
-        
-        // Ritual con parametri e tipo di ritorno esplicito
-        ritual add(a: int, b: int) int {
-            return a + b;
-        }
-        
-        // Ritual che chiama un altro ritual
-        ritual multiply(a: int, b: int) int {
-            int result: a * b;
-            return result;
-        }
-        
-        // Ritual con tipo di ritorno string
-        ritual greet() string {
-            return "Hello, Nervs!";
-        }
-        
-        // Ritual condizionale
-        ritual max(a: int, b: int) int {
-            if (a > b) {
-                return a;
-            } else {
-                return b;
-            }
-        }
-        
-        // Ritual con ciclo
-        ritual factorial(n: int) int {
-            int result: 1;
-            int i: 1;
-            
-            cycle (i <= n) {
-                result = result * i;
-                i = i + 1;
-            }
-            
-            return result;
-        }
-    }
-}
-"#)
-    };
-    
-    // Test del lexer
-    match lexer::tokenize(&source) {
-        Ok(tokens) => {
-            println!("Lexing successful! Found {} tokens", tokens.len());
-            
-            // Stampa i primi 20 token (o meno se ce ne sono meno)
-            println!("\nToken preview (first 20):");
-            for (i, token) in tokens.iter().enumerate().take(20) {
-                println!("  {}: {:?}", i, token);
-            }
-            
-            if tokens.len() > 20 {
-                println!("  ... and {} more", tokens.len() - 20);
-            }
-
-            // Test del parser
-            match parser::parse(tokens) {
-                Ok(program) => {
-                    println!("\nParsing successful!");
-                    println!("Parsed {} realm(s)", program.realms.len());
-                    
-                    // Esegui l'analisi semantica
-                    println!("\nStarting semantic analysis...");
-                    match semantic::analyze(&program) {
-                        Ok(_) => {
-                            println!("Semantic analysis successful!");
-                            
-                            // Qui si potrebbe aggiungere la generazione del codice
-                            // e l'applicazione dei sigilli se necessario
-                        },
-                        Err(e) => {
-                            println!("Semantic error: {}", e);
-                        }
-                    }
-                },
-                Err(errors) => {
-                    println!("Parsing errors: {:?}", errors);
-                }
-            }
-        },
-        Err(e) => println!("Lexing error: {}", e),
-    }
-    
-    println!("\nCompilation process complete");
-    Ok(())
-}
\ No newline at end of file
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use clap::{Parser, Subcommand, ValueEnum};
+use nervs_compiler::ast::{self, nodes::Program};
+use nervs_compiler::diagnostics::{self, OutputFormat};
+use nervs_compiler::runtime::{self, RuntimeError, RuntimeOptions, RuntimeValue};
+use nervs_compiler::semantic::lints::{Lint, LintConfig, LintLevel};
+use nervs_compiler::{CompileError, CompileOptions, Session};
+
+/// Codici di uscita del compilatore
+mod exit {
+    /// Il programma contiene errori (lessicali, sintattici, semantici o di esecuzione)
+    pub const COMPILE_ERROR: u8 = 1;
+    /// Argomenti non validi (usato anche da clap)
+    pub const USAGE: u8 = 2;
+    /// Impossibile leggere o scrivere un file
+    pub const IO_ERROR: u8 = 3;
+}
+
+/// Compiler and toolchain for the Nervs language
+#[derive(Parser)]
+#[command(name = "nervs", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Print progress information (repeat for more detail)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only print errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Format of diagnostics and listings
+    #[arg(long, value_enum, default_value_t = Format::Human, global = true)]
+    format: Format,
+
+    /// Silence a lint (can be repeated)
+    #[arg(short = 'A', long = "allow", value_name = "LINT", value_parser = parse_lint, global = true)]
+    allow: Vec<Lint>,
+
+    /// Report a lint as a warning (can be repeated)
+    #[arg(short = 'W', long = "warn", value_name = "LINT", value_parser = parse_lint, global = true)]
+    warn: Vec<Lint>,
+
+    /// Report a lint as an error (can be repeated); takes precedence over --allow and --warn
+    #[arg(short = 'D', long = "deny", value_name = "LINT", value_parser = parse_lint, global = true)]
+    deny: Vec<Lint>,
+
+    /// Directory searched for imported files (can be repeated)
+    #[arg(short = 'I', long = "include", value_name = "DIR", global = true)]
+    include: Vec<PathBuf>,
+}
+
+fn parse_lint(name: &str) -> Result<Lint, String> {
+    Lint::from_name(name).ok_or_else(|| {
+        let known: Vec<&str> = Lint::ALL.iter().map(|lint| lint.name()).collect();
+        format!("unknown lint `{}` (known lints: {})", name, known.join(", "))
+    })
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Human,
+    Json,
+}
+
+impl From<Format> for OutputFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Human => OutputFormat::Human,
+            Format::Json => OutputFormat::Json,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check a program for errors without generating code
+    Check {
+        /// Source file (defaults to the built-in example)
+        file: Option<PathBuf>,
+    },
+    /// Compile a program to C
+    Build {
+        /// Source file (defaults to the built-in example)
+        file: Option<PathBuf>,
+        /// Output directory for the generated code
+        #[arg(short, long, default_value = "build")]
+        output: PathBuf,
+    },
+    /// Execute a ritual with the interpreter
+    ///
+    /// Extensions are implemented by the program embedding the runtime, which binds them with
+    /// `NervsRuntime::with_extensions`: programs declaring an `extensions` block cannot be run here.
+    Run {
+        /// Source file (defaults to the built-in example)
+        file: Option<PathBuf>,
+        /// Ritual to execute, as `Realm.Being.ritual`
+        #[arg(short, long)]
+        entry: String,
+        /// Arguments passed to the ritual (integers, floats, `true`/`false` or strings)
+        #[arg(last = true)]
+        args: Vec<String>,
+        /// Directory of the files backing persistent hives
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        hive_dir: PathBuf,
+        /// Directory of the snapshots of `memory` blocks
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        memory_dir: PathBuf,
+    },
+    /// Print the token stream
+    Tokens {
+        /// Source file (defaults to the built-in example)
+        file: Option<PathBuf>,
+    },
+    /// Print the syntax tree
+    Ast {
+        /// Source file (defaults to the built-in example)
+        file: Option<PathBuf>,
+    },
+    /// Apply integrity seals to a program (not implemented yet)
+    Seal {
+        /// Source file (defaults to the built-in example)
+        file: Option<PathBuf>,
+    },
+    /// Verify the integrity seals of a program (not implemented yet)
+    Verify {
+        /// Source file (defaults to the built-in example)
+        file: Option<PathBuf>,
+    },
+    /// Reformat a program in the canonical style (comments are not preserved)
+    Fmt {
+        /// Source file (defaults to the built-in example)
+        file: Option<PathBuf>,
+        /// Write the formatted source here instead of stdout (never over the source file)
+        #[arg(short, long, conflicts_with = "check")]
+        output: Option<PathBuf>,
+        /// Exit with an error if the file is not already formatted
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+/// Esito di un comando fallito: il codice di uscita, dopo aver già stampato i diagnostic
+struct Failure(u8);
+
+/// Opzioni di output comuni a tutti i comandi
+struct Output {
+    verbose: u8,
+    quiet: bool,
+    format: OutputFormat,
+    lints: LintConfig,
+    search_paths: Vec<PathBuf>,
+}
+
+impl Output {
+    // Messaggi di avanzamento, solo con --verbose
+    fn progress(&self, message: &str) {
+        if self.verbose > 0 {
+            eprintln!("{}", message);
+        }
+    }
+
+    // Messaggi di esito, soppressi da --quiet
+    fn status(&self, message: &str) {
+        if !self.quiet {
+            eprintln!("{}", message);
+        }
+    }
+
+    // Stampa i diagnostic della sessione; i warning non vengono mostrati con --quiet
+    fn diagnostics(&self, session: &Session) {
+        let shown: Vec<_> = session.diagnostics().iter()
+            .filter(|d| d.is_error() || !self.quiet)
+            .cloned()
+            .collect();
+        diagnostics::emit_as(&shown, session.source_map(), self.format);
+    }
+
+    // Opzioni di compilazione derivate dalla riga di comando
+    fn compile_options(&self) -> CompileOptions {
+        CompileOptions {
+            lints: self.lints.clone(),
+            search_paths: self.search_paths.clone(),
+            ..CompileOptions::default()
+        }
+    }
+
+    // Riporta l'esito di uno stage della pipeline, stampando i diagnostic raccolti
+    fn finish<T>(&self, session: &Session, result: Result<T, CompileError>) -> Result<T, Failure> {
+        self.diagnostics(session);
+        result.map_err(|e| {
+            match e {
+                CompileError::Diagnostics { .. } => self.status(&format!(
+                    "error: could not compile {} due to {} previous error(s)",
+                    session.source_map().name(),
+                    session.error_count()
+                )),
+                CompileError::Backend { .. } => eprintln!("error: {}", e),
+            }
+            Failure(exit::COMPILE_ERROR)
+        })
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    // I livelli vengono applicati in ordine di severità crescente
+    let mut lints = LintConfig::default();
+    for (names, level) in [(&cli.allow, LintLevel::Allow), (&cli.warn, LintLevel::Warn), (&cli.deny, LintLevel::Deny)] {
+        for &lint in names {
+            lints.set(lint, level);
+        }
+    }
+
+    let output = Output {
+        verbose: cli.verbose,
+        quiet: cli.quiet,
+        format: cli.format.into(),
+        lints,
+        search_paths: cli.include,
+    };
+
+    match run(cli.command, &output) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure(code)) => ExitCode::from(code),
+    }
+}
+
+fn run(command: Command, output: &Output) -> Result<(), Failure> {
+    match command {
+        Command::Check { file } => {
+            let mut session = load_source(file.as_deref(), output)?;
+            let result = session.analyze();
+            output.finish(&session, result)?;
+            output.status("No errors found");
+            Ok(())
+        }
+        Command::Build { file, output: out_dir } => {
+            output.progress(&format!("Generating code into {}", out_dir.display()));
+            let mut session = load_source(file.as_deref(), output)?
+                .with_options(CompileOptions {
+                    output_dir: Some(out_dir.clone()),
+                    ..output.compile_options()
+                });
+            let result = session.compile();
+            output.finish(&session, result)?;
+            output.status(&format!("Build complete: {}", out_dir.display()));
+            Ok(())
+        }
+        Command::Run { file, entry, args, hive_dir, memory_dir } => {
+            let parts: Vec<&str> = entry.split('.').collect();
+            let [realm, being, ritual] = parts[..] else {
+                eprintln!("error: entry point must be written as `Realm.Being.ritual`, found `{}`", entry);
+                return Err(Failure(exit::USAGE));
+            };
+
+            let mut session = load_source(file.as_deref(), output)?;
+            let result = session.analyze();
+            let program = output.finish(&session, result)?;
+            output.progress(&format!("Executing {}", entry));
+            let arguments = args.iter().map(|arg| parse_argument(arg)).collect();
+            let options = RuntimeOptions { hive_directory: hive_dir, memory_directory: memory_dir };
+            let result = runtime::initialize_with(&program, &options).and_then(|mut runtime| {
+                let value = runtime.execute_ritual(realm, being, ritual, arguments)?;
+                runtime.flush()?;
+                Ok(value)
+            });
+            match result {
+                Ok(value) => {
+                    println!("{}", value);
+                    Ok(())
+                }
+                Err(RuntimeError::UnboundExtensions(missing)) => {
+                    eprintln!("error: `nervs run` cannot provide the extensions {}", missing.join(", "));
+                    eprintln!("note: extensions are implemented in Rust by the program embedding the runtime, which binds them with `NervsRuntime::with_extensions`");
+                    Err(Failure(exit::COMPILE_ERROR))
+                }
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    Err(Failure(exit::COMPILE_ERROR))
+                }
+            }
+        }
+        Command::Tokens { file } => {
+            let mut session = load_source(file.as_deref(), output)?;
+            let result = session.tokenize();
+            let tokens = output.finish(&session, result)?;
+            let source_map = session.source_map();
+            for (token, span) in &tokens {
+                let (line, column) = source_map.line_col(span.start);
+                match output.format {
+                    OutputFormat::Human => println!("{}:{}\t{:?}", line, column, token),
+                    OutputFormat::Json => println!(
+                        "{{\"token\":{},\"text\":{},\"line\":{},\"column\":{},\"start\":{},\"end\":{}}}",
+                        diagnostics::json_string(&format!("{:?}", token)),
+                        diagnostics::json_string(&source_map.source()[span.clone()]),
+                        line, column, span.start, span.end
+                    ),
+                }
+            }
+            Ok(())
+        }
+        Command::Ast { file } => {
+            let mut session = load_source(file.as_deref(), output)?;
+            let result = session.parse();
+            let program = output.finish(&session, result)?;
+            print_ast(&program, output.format)
+        }
+        // I sigilli non sono ancora implementati: meglio fallire che dichiarare un sigillo inesistente
+        Command::Seal { .. } | Command::Verify { .. } => {
+            eprintln!("error: sealing is not implemented yet");
+            Err(Failure(exit::COMPILE_ERROR))
+        }
+        Command::Fmt { file, output: destination, check } => {
+            let mut session = load_source(file.as_deref(), output)?;
+            let result = session.parse();
+            let program = output.finish(&session, result)?;
+            let formatted = ast::printer::format_program(&program);
+
+            if check {
+                if formatted != session.source_map().source() {
+                    output.status(&format!("{} is not formatted", session.source_map().name()));
+                    return Err(Failure(exit::COMPILE_ERROR));
+                }
+                return Ok(());
+            }
+
+            match destination {
+                // La stampa non conserva i commenti: sovrascrivere il sorgente li perderebbe
+                Some(path) if file.as_deref().is_some_and(|input| same_file(input, &path)) => {
+                    eprintln!("error: refusing to overwrite {}: comments are not preserved by fmt", path.display());
+                    Err(Failure(exit::USAGE))
+                }
+                Some(path) => fs::write(&path, formatted).map_err(|e| {
+                    eprintln!("error: cannot write {}: {}", path.display(), e);
+                    Failure(exit::IO_ERROR)
+                }),
+                None => {
+                    print!("{}", formatted);
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serialization")]
+fn print_ast(program: &Program, format: OutputFormat) -> Result<(), Failure> {
+    match format {
+        OutputFormat::Human => println!("{:#?}", program),
+        OutputFormat::Json => match serde_json::to_string_pretty(program) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("error: cannot serialize the syntax tree: {}", e);
+                return Err(Failure(exit::IO_ERROR));
+            }
+        },
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "serialization"))]
+fn print_ast(program: &Program, format: OutputFormat) -> Result<(), Failure> {
+    match format {
+        OutputFormat::Human => {
+            println!("{:#?}", program);
+            Ok(())
+        }
+        OutputFormat::Json => {
+            eprintln!("error: JSON output of the syntax tree requires the `serialization` feature");
+            Err(Failure(exit::USAGE))
+        }
+    }
+}
+
+// Indica se due percorsi indicano lo stesso file esistente
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Interpreta un argomento della riga di comando come il letterale Nervs più specifico possibile
+fn parse_argument(arg: &str) -> RuntimeValue {
+    if let Ok(n) = arg.parse() {
+        RuntimeValue::Integer(n)
+    } else if let Ok(x) = arg.parse() {
+        RuntimeValue::Float(x)
+    } else if let Ok(b) = arg.parse() {
+        RuntimeValue::Boolean(b)
+    } else {
+        RuntimeValue::String(arg.to_string())
+    }
+}
+
+// Apre una sessione sul file sorgente, oppure sull'esempio incorporato se non è stato indicato
+fn load_source(file: Option<&Path>, output: &Output) -> Result<Session, Failure> {
+    let session = match file {
+        Some(path) => {
+            output.progress(&format!("Reading source from file: {}", path.display()));
+            Session::from_file(path).map_err(|e| {
+                eprintln!("error: cannot read {}: {}", path.display(), e);
+                Failure(exit::IO_ERROR)
+            })
+        }
+        None => {
+            output.progress("No input file specified, using built-in example");
+            Ok(Session::new("<built-in>", BUILTIN_EXAMPLE))
+        }
+    };
+    session.map(|session| session.with_options(output.compile_options()))
+}
+
+const BUILTIN_EXAMPLE: &str = r#"
+realm MathRealm {
+    being Calculator {
+        // Variabili del being; quelle pubbliche sono leggibili dagli altri being
+        public int pi_approx: 314;
+
+        // Ritual con parametri e tipo di ritorno esplicito
+        public ritual add(a: int, b: int) int {
+            return a + b;
+        }
+
+        // Ritual che chiama un altro ritual
+        public ritual multiply(a: int, b: int) int {
+            int result: a * b;
+            return result;
+        }
+
+        // Ritual che legge una variabile del being
+        public ritual circle_area(radius: int) int {
+            return pi_approx * radius * radius / 100;
+        }
+
+        // Ritual con tipo di ritorno string
+        public ritual greet() string {
+            return "Hello, Nervs!";
+        }
+
+        // Ritual condizionale
+        public ritual max(a: int, b: int) int {
+            if (a > b) {
+                return a;
+            } else {
+                return b;
+            }
+        }
+
+        // Ritual con ciclo
+        public ritual factorial(n: int) int {
+            int result: 1;
+            int i: 1;
+
+            cycle (i <= n) {
+                result = result * i;
+                i = i + 1;
+            }
+
+            return result;
+        }
+    }
+
+    // Being che collabora con Calculator, chiamandone i ritual pubblici
+    being Geometry {
+        public ritual square_area(side: int) int {
+            return Calculator.multiply(side, side);
+        }
+
+        public ritual circumference(radius: int) int {
+            return MathRealm::Calculator.multiply(2, Calculator.pi_approx) * radius / 100;
+        }
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn command_line_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn run_takes_the_ritual_arguments_after_the_separator() {
+        let cli = Cli::try_parse_from(["nervs", "-q", "run", "app.nervs", "-e", "R.B.go", "--", "-3", "x"]).unwrap();
+        assert!(cli.quiet);
+        let Command::Run { file, entry, args, .. } = cli.command else {
+            panic!("expected the run command");
+        };
+        assert_eq!(file, Some(PathBuf::from("app.nervs")));
+        assert_eq!(entry, "R.B.go");
+        assert_eq!(args, ["-3", "x"]);
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        assert!(Cli::try_parse_from(["nervs", "check", "-A", "no_such_lint"]).is_err());
+        assert!(Cli::try_parse_from(["nervs", "check", "-q", "-v"]).is_err());
+        assert!(Cli::try_parse_from(["nervs", "run", "app.nervs"]).is_err());
+    }
+
+    #[test]
+    fn built_in_example_compiles() {
+        let mut session = Session::new("<built-in>", BUILTIN_EXAMPLE);
+        assert!(session.analyze().is_ok());
+        assert!(session.diagnostics().is_empty());
+    }
+
+    fn quiet_output() -> Output {
+        Output {
+            verbose: 0,
+            quiet: true,
+            format: OutputFormat::Human,
+            lints: LintConfig::default(),
+            search_paths: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn sealing_commands_fail_until_implemented() {
+        let output = quiet_output();
+        assert!(matches!(run(Command::Seal { file: None }, &output), Err(Failure(exit::COMPILE_ERROR))));
+        assert!(matches!(run(Command::Verify { file: None }, &output), Err(Failure(exit::COMPILE_ERROR))));
+    }
+
+    #[test]
+    fn fmt_does_not_overwrite_its_input() {
+        let output = quiet_output();
+        let directory = std::env::temp_dir().join(format!("nervs-fmt-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let source = "realm R { // commento\n}\n";
+        let path = directory.join("input.nervs");
+        fs::write(&path, source).unwrap();
+
+        let fmt = |destination: PathBuf| run(Command::Fmt { file: Some(path.clone()), output: Some(destination), check: false }, &output);
+        assert!(matches!(fmt(directory.join(".").join("input.nervs")), Err(Failure(exit::USAGE))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), source);
+        assert!(fmt(directory.join("formatted.nervs")).is_ok());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn arguments_become_the_most_specific_literal() {
+        assert_eq!(parse_argument("-3"), RuntimeValue::Integer(-3));
+        assert_eq!(parse_argument("2.5"), RuntimeValue::Float(2.5));
+        assert_eq!(parse_argument("true"), RuntimeValue::Boolean(true));
+        assert_eq!(parse_argument("hello"), RuntimeValue::String("hello".to_string()));
+    }
+}
@@ -1,11 +1,56 @@
-#[derive(Debug, thiserror::Error)]
-pub enum ParseError {
-    #[error("Syntax error: {0}")]
-    Syntax(String),
-    
-    #[error("Unexpected token")]
-    UnexpectedToken,
-    
-    #[error("Generic error: {0}")]
-    Generic(String),
-}
\ No newline at end of file
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    #[error("Syntax error: {0}")]
+    Syntax(String),
+    
+    #[error("Unexpected token")]
+    UnexpectedToken,
+    
+    #[error("Generic error: {0}")]
+    Generic(String),
+}
+
+use chumsky::error::{Simple, SimpleReason};
+use crate::diagnostics::Diagnostic;
+use crate::lexer::Token;
+
+/// Converts a chumsky parse error into a diagnostic with the expected-token list
+pub fn to_diagnostic(error: &Simple<Token>) -> Diagnostic {
+    let found = match error.found() {
+        Some(token) => format!("`{}`", token),
+        None => "end of input".to_string(),
+    };
+
+    match error.reason() {
+        SimpleReason::Unclosed { span, delimiter } => {
+            Diagnostic::error(format!("unclosed delimiter `{}`", delimiter), error.span())
+                .with_primary_label(format!("expected closing delimiter, found {}", found))
+                .with_label(span.clone(), "unclosed delimiter opened here")
+        }
+        SimpleReason::Custom(message) => {
+            Diagnostic::error(message.clone(), error.span())
+        }
+        SimpleReason::Unexpected => {
+            let mut expected: Vec<String> = error.expected()
+                .map(|token| match token {
+                    Some(token) => format!("`{}`", token),
+                    None => "end of input".to_string(),
+                })
+                .collect();
+            expected.sort();
+            expected.dedup();
+
+            // L'etichetta del costrutto atteso (es. "expression") è più chiara dell'elenco dei token
+            let message = match (error.label(), expected.len()) {
+                (Some(label), _) => format!("expected {}, found {}", label, found),
+                (None, 0) => format!("unexpected {}", found),
+                (None, 1) => format!("expected {}, found {}", expected[0], found),
+                (None, 2) => format!("expected {} or {}, found {}", expected[0], expected[1], found),
+                (None, _) => format!("expected one of {}, found {}", expected.join(", "), found),
+            };
+
+            Diagnostic::error(message, error.span())
+                .with_primary_label(format!("unexpected {}", found))
+        }
+    }
+}
@@ -1,122 +1,938 @@
-use chumsky::prelude::*;
-use chumsky::Parser;
-use crate::lexer::Token;
-use crate::ast::nodes::*;
-
-fn expression_parser() -> impl Parser<Token, Expression, Error = Simple<Token>> {
-    select! { 
-        Token::Number(num) => Expression::Literal(
-            if num.contains('.') {
-                Literal::Float(num.parse().unwrap())
-            } else {
-                Literal::Integer(num.parse().unwrap())
-            }
-        ),
-        Token::String(s) => Expression::Literal(Literal::String(s)),
-        Token::Identifier(name) => Expression::Variable(name)
-    }.boxed()
-}
-
-fn statement_parser() -> impl Parser<Token, Statement, Error = Simple<Token>> {
-    choice([
-        // Return statement with optional expression
-        just(Token::Identifier(String::from("return")))
-            .ignore_then(expression_parser().or_not())
-            .then_ignore(just(Token::Semicolon))
-            .map(Statement::Return)
-            .boxed(),
-        
-        // Empty statement (semicolon)
-        just(Token::Semicolon)
-            .map(|_| Statement::Return(None))
-            .boxed()
-    ])
-}
-
-// Add the parse function
-pub fn parse(tokens: Vec<Token>) -> Result<Program, Vec<Simple<Token>>> {
-    let program_parser = program_parser();
-    
-    program_parser.parse(tokens)
-}
-
-fn program_parser() -> impl Parser<Token, Program, Error = Simple<Token>> {
-    realm_parser()
-        .repeated()
-        .map(|realms| Program { realms })
-        .then_ignore(end())
-}
-
-fn realm_parser() -> impl Parser<Token, Realm, Error = Simple<Token>> {
-    just(Token::Realm)
-        .ignore_then(select! { Token::Identifier(name) => name })
-        .then_ignore(just(Token::LBrace))
-        .then(being_parser().repeated())
-        .then_ignore(just(Token::RBrace))
-        .map(|(name, beings)| Realm { name, beings })
-}
-
-fn being_parser() -> impl Parser<Token, Being, Error = Simple<Token>> {
-    just(Token::Being)
-        .ignore_then(select! { Token::Identifier(name) => name })
-        .then_ignore(just(Token::LBrace))
-        .then(variable_parser().repeated().or(empty().to(vec![])))
-        .then(ritual_parser().repeated())
-        .then_ignore(just(Token::RBrace))
-        .map(|((name, variables), rituals)| Being { 
-            name, 
-            variables, 
-            rituals 
-        })
-}
-
-fn ritual_parser() -> impl Parser<Token, Ritual, Error = Simple<Token>> {
-    just(Token::Ritual)
-        .ignore_then(select! { Token::Identifier(name) => name })
-        .then_ignore(just(Token::LParen))
-        .then(parameter_parser().separated_by(just(Token::Comma)).or(empty().to(vec![])))
-        .then_ignore(just(Token::RParen))
-        // Make the return type optional with a default of Type::Void
-        .then(
-            type_parser()
-                .or_not()
-                .map(|t| t.unwrap_or(Type::Void))
-        )
-        .then_ignore(just(Token::LBrace))
-        .then(statement_parser().repeated().or(empty().to(vec![])))
-        .then_ignore(just(Token::RBrace))
-        .map(|(((name, parameters), return_type), body)| Ritual {
-            name, 
-            parameters, 
-            return_type, 
-            body 
-        })
-}
-
-// Add variable parser
-fn variable_parser() -> impl Parser<Token, Variable, Error = Simple<Token>> {
-    select! { Token::Identifier(name) => name }
-        .then_ignore(just(Token::Colon))
-        .then(type_parser())
-        .map(|(name, var_type)| Variable { name, var_type })
-}
-
-fn parameter_parser() -> impl Parser<Token, Variable, Error = Simple<Token>> {
-    select! { Token::Identifier(name) => name }
-        .then_ignore(just(Token::Colon))
-        .then(type_parser())
-        .map(|(name, var_type)| Variable { name, var_type })
-}
-
-fn type_parser() -> impl Parser<Token, Type, Error = Simple<Token>> {
-    select! {
-        Token::Identifier(name) => match name.as_str() {
-            "int" => Type::Integer,
-            "float" => Type::Float,
-            "string" => Type::String,
-            "bool" => Type::Boolean,
-            "void" => Type::Void,
-            _ => Type::Custom(name)
-        }
-    }
-}
\ No newline at end of file
+#![allow(clippy::result_large_err)]
+
+pub mod error;
+
+use chumsky::prelude::*;
+use chumsky::Parser;
+use crate::lexer::Token;
+use crate::ast::nodes::*;
+
+fn expression_parser() -> impl Parser<Token, Expression, Error = Simple<Token>> + Clone {
+    recursive(|expr| {
+        // Un intero che non sta in 64 bit è un errore, non un panic
+        let number = select! { Token::Number(num) => num }
+            .try_map(|num, span: Span| {
+                let literal = if num.contains('.') {
+                    num.parse().map(Literal::Float).map_err(|_| "invalid float literal")
+                } else {
+                    num.parse().map(Literal::Integer).map_err(|_| "integer literal out of range")
+                };
+                literal
+                    .map(|literal| Expression::Literal(literal, span.clone()))
+                    .map_err(|message| Simple::custom(span, message))
+            });
+        let literal = select! { |span|
+            Token::String(s) => Expression::Literal(Literal::String(s), span),
+            Token::True => Expression::Literal(Literal::Boolean(true), span),
+            Token::False => Expression::Literal(Literal::Boolean(false), span),
+        }
+        .or(number);
+
+        // Chiamata a ritual: nome(argomenti)
+        let call = select! { Token::Identifier(name) => name }
+            .then(
+                expr.clone()
+                    .separated_by(just(Token::Comma))
+                    .delimited_by(just(Token::LParen), just(Token::RParen))
+            )
+            .map_with_span(|(name, arguments), span| Expression::FunctionCall { name, arguments, span });
+
+        let variable = select! { |span| Token::Identifier(name) => Expression::Variable(name, span) };
+
+        // Being di un altro realm: Realm::Being
+        let being_reference = select! { Token::Identifier(realm) => realm }
+            .then_ignore(just(Token::DoubleColon))
+            .then(select! { Token::Identifier(being) => being })
+            .map_with_span(|(realm, being), span| Expression::BeingReference { realm, being, span });
+
+        // Costruzione di un'essence: Nome { campo: valore, ... }
+        let field_init = select! { |span| Token::Identifier(name) => (name, span) }
+            .then_ignore(just(Token::Colon))
+            .then(expr.clone())
+            .map(|((name, span), value)| FieldInit { name, value, span });
+        let type_name = select! { Token::Identifier(name) => name }
+            .then(just(Token::DoubleColon).ignore_then(select! { Token::Identifier(name) => name }).or_not())
+            .map(|(first, second)| match second {
+                Some(name) => format!("{}::{}", first, name),
+                None => first,
+            });
+        let construction = type_name
+            .then(
+                field_init
+                    .separated_by(just(Token::Comma))
+                    .allow_trailing()
+                    .delimited_by(just(Token::LBrace), just(Token::RBrace))
+            )
+            .map_with_span(|(essence, fields), span| Expression::Construction { essence, fields, span });
+
+        // Un'espressione tra parentesi malformata diventa un nodo di errore
+        let parenthesized = expr.clone()
+            .delimited_by(just(Token::LParen), just(Token::RParen))
+            .recover_with(nested_delimiters(
+                Token::LParen,
+                Token::RParen,
+                [(Token::LBrace, Token::RBrace), (Token::LBracket, Token::RBracket)],
+                Expression::Error,
+            ));
+
+        let atom = literal
+            .or(construction)
+            .or(being_reference)
+            .or(call)
+            .or(variable)
+            .or(parenthesized)
+            .labelled("expression")
+            .boxed();
+
+        // Accesso ai membri, il livello più stretto: oggetto.campo e oggetto.ritual(argomenti)
+        let member = just(Token::Dot)
+            .ignore_then(select! { Token::Identifier(name) => name })
+            .then(
+                expr.clone()
+                    .separated_by(just(Token::Comma))
+                    .delimited_by(just(Token::LParen), just(Token::RParen))
+                    .or_not()
+            )
+            .map_with_span(|member, span: Span| (member, span.end));
+
+        // Indicizzazione di una hive: oggetto[indice, ...]
+        let index = expr.clone()
+            .separated_by(just(Token::Comma))
+            .at_least(1)
+            .delimited_by(just(Token::LBracket), just(Token::RBracket))
+            .map_with_span(|indices, span: Span| (indices, span.end));
+
+        let postfix = atom
+            .then(member.map(Postfix::Member).or(index.map(Postfix::Index)).repeated())
+            .foldl(|object, postfix| {
+                let object = Box::new(object);
+                match postfix {
+                    Postfix::Member(((name, arguments), end)) => {
+                        let span = object.span().start..end;
+                        match arguments {
+                            Some(arguments) => Expression::MethodCall { object, name, arguments, span },
+                            None => Expression::FieldAccess { object, field: name, span },
+                        }
+                    }
+                    Postfix::Index((indices, end)) => {
+                        let span = object.span().start..end;
+                        Expression::Index { object, indices, span }
+                    }
+                }
+            })
+            .boxed();
+
+        // Operatori prefissi, associativi a destra: `--x` e `!!b` sono ammessi
+        let unary = select! { |span|
+            Token::Minus => (UnaryOperator::Negate, span),
+            Token::Bang => (UnaryOperator::Not, span),
+        }
+            .repeated()
+            .then(postfix)
+            .foldr(|(operator, span): (UnaryOperator, Span), operand| {
+                let span = span.start..operand.span().end;
+                Expression::UnaryOperation { operator, operand: Box::new(operand), span }
+            })
+            .boxed();
+
+        // Livelli di precedenza, dal più stretto al più largo; tutti associativi a sinistra
+        let product = binary_level(unary, select! {
+            Token::Star => BinaryOperator::Multiply,
+            Token::Slash => BinaryOperator::Divide,
+            Token::Percent => BinaryOperator::Modulo,
+        });
+
+        let sum = binary_level(product, select! {
+            Token::Plus => BinaryOperator::Add,
+            Token::Minus => BinaryOperator::Subtract,
+        });
+
+        let comparison = binary_level(sum, select! {
+            Token::Less => BinaryOperator::LessThan,
+            Token::Greater => BinaryOperator::GreaterThan,
+            Token::LessEqual => BinaryOperator::LessEqual,
+            Token::GreaterEqual => BinaryOperator::GreaterEqual,
+        });
+
+        let equality = binary_level(comparison, select! {
+            Token::EqualEqual => BinaryOperator::Equal,
+            Token::NotEqual => BinaryOperator::NotEqual,
+        });
+
+        let conjunction = binary_level(equality, select! {
+            Token::AndAnd => BinaryOperator::And,
+        });
+
+        binary_level(conjunction, select! {
+            Token::OrOr => BinaryOperator::Or,
+        })
+    })
+}
+
+// Operazione applicata dopo un'espressione: `.membro`, `.ritual(argomenti)` oppure `[indici]`
+enum Postfix {
+    Member(((String, Option<Vec<Expression>>), usize)),
+    Index((Vec<Expression>, usize)),
+}
+
+// Costruisce un livello di precedenza associativo a sinistra: operand (op operand)*
+fn binary_level(
+    operand: impl Parser<Token, Expression, Error = Simple<Token>> + Clone + 'static,
+    operator: impl Parser<Token, BinaryOperator, Error = Simple<Token>> + Clone + 'static,
+) -> BoxedParser<'static, Token, Expression, Simple<Token>> {
+    operand.clone()
+        .then(operator.then(operand).repeated())
+        .foldl(|left, (operator, right)| {
+            let span = left.span().start..right.span().end;
+            Expression::BinaryOperation {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+                span,
+            }
+        })
+        .boxed()
+}
+
+// Le parole chiave del linguaggio non riservate dal lexer sono identificatori
+fn keyword(word: &'static str) -> impl Parser<Token, Token, Error = Simple<Token>> + Clone {
+    just(Token::Identifier(word.to_string()))
+}
+
+// Token che aprono una dichiarazione di livello superiore: il recupero dagli errori
+// non li scavalca mai, così un ritual o un being successivo viene comunque analizzato
+const BOUNDARY_TOKENS: [Token; 10] = [
+    Token::Ritual, Token::Being, Token::Essence, Token::Realm, Token::At, Token::Public, Token::Private,
+    Token::Memory, Token::Perceptions, Token::Extensions,
+];
+
+// Salta i token di un costrutto malformato fino al prossimo `;` (consumato),
+// oppure fino a un blocco `{ ... }` bilanciato (consumato), senza superare `}` o un confine
+fn skip_construct() -> impl Parser<Token, (), Error = Simple<Token>> + Clone {
+    let balanced = recursive(|balanced| {
+        balanced
+            .or(none_of([Token::LBrace, Token::RBrace]).ignored())
+            .repeated()
+            .delimited_by(just(Token::LBrace), just(Token::RBrace))
+            .ignored()
+    });
+
+    let mut stop = vec![Token::Semicolon, Token::LBrace, Token::RBrace];
+    stop.extend(BOUNDARY_TOKENS);
+
+    none_of(stop)
+        .ignored()
+        .repeated()
+        .then(just(Token::Semicolon).ignored().or(balanced).or_not())
+        .try_map(|(skipped, terminator), span| {
+            // Deve consumare almeno un token, altrimenti il recupero non fa progressi
+            if skipped.is_empty() && terminator.is_none() {
+                Err(Simple::custom(span, "nothing to skip"))
+            } else {
+                Ok(())
+            }
+        })
+}
+
+// Graffa di chiusura di una dichiarazione; se manca ma segue un'altra dichiarazione
+// (o la fine del file) l'errore viene segnalato e il parsing prosegue come se ci fosse
+fn closing_brace(expected: &'static str) -> impl Parser<Token, (), Error = Simple<Token>> + Clone {
+    let boundary = choice(BOUNDARY_TOKENS.map(just))
+        .map(Some)
+        .or(end().to(None));
+
+    just(Token::RBrace).ignored().or(
+        boundary
+            .rewind()
+            .validate(move |found, span, emit| {
+                let found = match found {
+                    Some(token) => format!("`{}`", token),
+                    None => "end of input".to_string(),
+                };
+                emit(Simple::custom(span, format!("expected {}, found {}", expected, found)));
+            })
+            .ignored()
+    )
+}
+
+// Blocco di statement tra graffe; i punti e virgola isolati vengono ignorati
+fn block_parser(
+    statement: impl Parser<Token, Statement, Error = Simple<Token>> + Clone,
+    close: impl Parser<Token, (), Error = Simple<Token>> + Clone,
+) -> impl Parser<Token, Vec<Statement>, Error = Simple<Token>> + Clone {
+    statement
+        .map(Some)
+        .or(just(Token::Semicolon).to(None))
+        .repeated()
+        .delimited_by(just(Token::LBrace), close)
+        .map(|statements| statements.into_iter().flatten().collect())
+}
+
+fn statement_parser() -> impl Parser<Token, Statement, Error = Simple<Token>> + Clone {
+    recursive(|statement| {
+        let block = block_parser(statement, just(Token::RBrace).ignored());
+        let condition = expression_parser()
+            .delimited_by(just(Token::LParen), just(Token::RParen));
+
+        // Return statement with optional expression
+        let return_statement = keyword("return")
+            .ignore_then(expression_parser().or_not())
+            .then_ignore(just(Token::Semicolon))
+            .map_with_span(Statement::Return);
+
+        // if (cond) { ... } else if (cond) { ... } else { ... }
+        let conditional = recursive(|conditional| {
+            keyword("if")
+                .ignore_then(condition.clone())
+                .then(block.clone())
+                .then(
+                    keyword("else")
+                        .ignore_then(conditional.map(|stmt| vec![stmt]).or(block.clone()))
+                        .or_not()
+                )
+                .map_with_span(|((condition, true_branch), false_branch), span| Statement::Conditional {
+                    condition,
+                    true_branch,
+                    false_branch,
+                    span,
+                })
+        });
+
+        // cycle (cond) { ... } oppure cycle { ... } senza condizione
+        let cycle = just(Token::Cycle)
+            .ignore_then(condition.or_not())
+            .then(block)
+            .map_with_span(|(condition, body), span| Statement::Cycle { condition, body, span });
+
+        let declaration = declaration_parser()
+            .map_with_span(|(variable, initializer), span| Statement::VariableDeclaration { variable, initializer, span });
+
+        let assignment = select! { Token::Identifier(name) => name }
+            .then_ignore(just(Token::Assign))
+            .then(expression_parser())
+            .then_ignore(just(Token::Semicolon))
+            .map_with_span(|(name, value), span| Statement::Assignment { name, value, span });
+
+        let ritual_call = select! { Token::Identifier(name) => name }
+            .then(
+                expression_parser()
+                    .separated_by(just(Token::Comma))
+                    .delimited_by(just(Token::LParen), just(Token::RParen))
+            )
+            .then_ignore(just(Token::Semicolon))
+            .map_with_span(|(name, arguments), span| Statement::RitualCall { name, arguments, span });
+
+        // Solo una chiamata su un oggetto può essere usata come statement; un accesso a un campo
+        // o un elemento di una hive può essere la destinazione di un'assegnazione
+        let expression_statement = expression_parser()
+            .then(just(Token::Assign).ignore_then(expression_parser()).or_not())
+            .then_ignore(just(Token::Semicolon))
+            .try_map(|(expr, value), span| match (expr, value) {
+                (expr @ Expression::MethodCall { .. }, None) => Ok(Statement::Expression(expr, span)),
+                (Expression::FieldAccess { object, field, .. }, Some(value)) => {
+                    Ok(Statement::FieldAssignment { object: *object, field, value, span })
+                }
+                (Expression::Index { object, indices, .. }, Some(value)) => {
+                    Ok(Statement::IndexAssignment { object: *object, indices, value, span })
+                }
+                (expr, Some(_)) => Err(Simple::custom(expr.span(), "invalid assignment target")),
+                (expr, None) => Err(Simple::custom(expr.span(), "expected a statement, found an expression")),
+            });
+
+        choice((
+            return_statement,
+            conditional,
+            cycle,
+            declaration,
+            assignment,
+            ritual_call,
+            expression_statement,
+        ))
+        // Uno statement malformato diventa un nodo di errore e il parsing riprende dopo il `;`
+        .recover_with(skip_parser(broken_statement()))
+        .boxed()
+    })
+}
+
+// Recupero di uno statement malformato. Di una dichiarazione o di un'assegnazione con un valore
+// malformato resta la variabile, con un'espressione di errore come valore: l'analisi semantica
+// non segnala così la variabile come sconosciuta o non assegnata negli statement successivi
+fn broken_statement() -> impl Parser<Token, Statement, Error = Simple<Token>> + Clone {
+    let value = skip_construct().map_with_span(|_, span| Expression::Error(span));
+
+    let declaration = type_parser()
+        .map_with_span(|var_type, span| (var_type, span))
+        .then(select! { |span| Token::Identifier(name) => (name, span) })
+        .then_ignore(just(Token::Colon))
+        .then(value.clone())
+        .map_with_span(|(((var_type, type_span), (name, span)), value), statement_span| Statement::VariableDeclaration {
+            variable: Variable { name, var_type, span, type_span },
+            initializer: Some(value),
+            span: statement_span,
+        });
+
+    let assignment = select! { Token::Identifier(name) => name }
+        .then_ignore(just(Token::Assign))
+        .then(value)
+        .map_with_span(|(name, value), span| Statement::Assignment { name, value, span });
+
+    declaration
+        .or(assignment)
+        .or(skip_construct().map_with_span(|_, span| Statement::Error(span)))
+}
+
+// Dichiarazione tipizzata: `int nome;` oppure `int nome: espressione;`
+fn declaration_parser() -> impl Parser<Token, (Variable, Option<Expression>), Error = Simple<Token>> + Clone {
+    type_parser()
+        .map_with_span(|var_type, span| (var_type, span))
+        .then(select! { |span| Token::Identifier(name) => (name, span) })
+        .then(just(Token::Colon).ignore_then(expression_parser()).or_not())
+        .then_ignore(just(Token::Semicolon))
+        .map(|(((var_type, type_span), (name, span)), initializer)| {
+            (Variable { name, var_type, span, type_span }, initializer)
+        })
+}
+
+// Add the parse function
+//
+// Il parser recupera dagli errori: restituisce sempre tutti gli errori trovati e,
+// quando possibile, un AST parziale in cui i costrutti malformati sono nodi di errore
+pub fn parse(tokens: Vec<(Token, Span)>) -> (Option<Program>, Vec<Simple<Token>>) {
+    let program_parser = program_parser();
+    
+    // La fine dell'input punta subito dopo l'ultimo token
+    let eoi = tokens.last().map(|(_, span)| span.end).unwrap_or(0);
+    program_parser.parse_recovery(chumsky::Stream::from_iter(eoi..eoi, tokens.into_iter()))
+}
+
+fn program_parser() -> impl Parser<Token, Program, Error = Simple<Token>> {
+    import_parser()
+        .repeated()
+        .then(realm_parser().repeated())
+        .map(|(imports, realms)| Program { imports, realms })
+        .then_ignore(end())
+}
+
+// Import di un altro file: `import "percorso.nervs";`
+fn import_parser() -> impl Parser<Token, Import, Error = Simple<Token>> {
+    keyword("import")
+        .ignore_then(select! { Token::String(path) => path })
+        .then_ignore(just(Token::Semicolon))
+        .map_with_span(|path, span| Import { path, span })
+}
+
+// Dichiarazione contenuta in un realm
+enum RealmItem {
+    Essence(Essence),
+    Being(Being),
+}
+
+fn realm_parser() -> impl Parser<Token, Realm, Error = Simple<Token>> {
+    let item = recoverable(essence_parser(), Token::Essence).map(|essence| essence.map(RealmItem::Essence))
+        .or(recoverable(being_parser(), Token::Being).map(|being| being.map(RealmItem::Being)));
+
+    attribute_parser()
+        .repeated()
+        .then(
+            just(Token::Realm)
+                .ignore_then(select! { Token::Identifier(name) => name })
+                .then_ignore(just(Token::LBrace))
+                .then(item.repeated().flatten())
+                .then_ignore(closing_brace("`}` to close realm"))
+                .map_with_span(|(name, items), span| (name, items, span))
+        )
+        .map(|(attributes, (name, items, span))| {
+            let mut realm = Realm { attributes, name, essences: Vec::new(), beings: Vec::new(), span };
+            for item in items {
+                match item {
+                    RealmItem::Essence(essence) => realm.essences.push(essence),
+                    RealmItem::Being(being) => realm.beings.push(being),
+                }
+            }
+            realm
+        })
+}
+
+fn essence_parser() -> impl Parser<Token, Essence, Error = Simple<Token>> {
+    // Campo: `nome: tipo;`
+    let field = parameter_parser().then_ignore(just(Token::Semicolon));
+
+    attribute_parser()
+        .repeated()
+        .then(
+            just(Token::Essence)
+                .ignore_then(select! { Token::Identifier(name) => name })
+                .then_ignore(just(Token::LBrace))
+                .then(
+                    field
+                        .map(Some)
+                        .recover_with(skip_parser(skip_construct().to(None)))
+                        .repeated()
+                        .flatten()
+                )
+                .then_ignore(closing_brace("`}` to close essence"))
+                .map_with_span(|(name, fields), span| (name, fields, span))
+        )
+        .map(|(attributes, (name, fields, span))| Essence { attributes, name, fields, span })
+}
+
+// Sezione di un being; le sezioni possono comparire in qualsiasi ordine
+enum BeingItem {
+    Variables(Vec<BeingVariable>),
+    Perceptions(Vec<Perception>),
+    Extensions(Vec<Extension>),
+    Ritual(Ritual),
+}
+
+fn being_parser() -> impl Parser<Token, Being, Error = Simple<Token>> {
+    let variables = memory_parser()
+        .or(variable_parser().map(|var| vec![var]))
+        .map(|variables| Some(BeingItem::Variables(variables)))
+        // La visibilità di un ritual non va scambiata per l'inizio di una variabile malformata
+        .recover_with(skip_parser(visibility_parser().ignore_then(skip_construct()).map(|_| None)));
+    let item = recoverable(perceptions_parser(), Token::Perceptions).map(|block| block.map(BeingItem::Perceptions))
+        .or(recoverable(extensions_parser(), Token::Extensions).map(|block| block.map(BeingItem::Extensions)))
+        .or(recoverable(ritual_parser(), Token::Ritual).map(|ritual| ritual.map(BeingItem::Ritual)))
+        .or(variables);
+
+    attribute_parser()
+        .repeated()
+        .then(
+            just(Token::Being)
+                .ignore_then(select! { Token::Identifier(name) => name })
+                .then_ignore(just(Token::LBrace))
+                .then(item.repeated().flatten())
+                .then_ignore(closing_brace("`}` to close being"))
+                .map_with_span(|(name, items), span| (name, items, span))
+        )
+        .map(|(attributes, (name, items, span))| {
+            let mut being = Being {
+                attributes,
+                name,
+                variables: Vec::new(),
+                perceptions: Vec::new(),
+                extensions: Vec::new(),
+                rituals: Vec::new(),
+                span,
+            };
+            for item in items {
+                match item {
+                    BeingItem::Variables(variables) => being.variables.extend(variables),
+                    BeingItem::Perceptions(perceptions) => being.perceptions.extend(perceptions),
+                    BeingItem::Extensions(extensions) => being.extensions.extend(extensions),
+                    BeingItem::Ritual(ritual) => being.rituals.push(ritual),
+                }
+            }
+            being
+        })
+}
+
+// Attributo prima di una dichiarazione: `@nome` oppure `@nome(argomento, ...)`
+fn attribute_parser() -> impl Parser<Token, Attribute, Error = Simple<Token>> + Clone {
+    let identifier = select! { Token::Identifier(name) => name };
+    just(Token::At)
+        .ignore_then(identifier)
+        .then(
+            identifier
+                .separated_by(just(Token::Comma))
+                .delimited_by(just(Token::LParen), just(Token::RParen))
+                .or_not()
+        )
+        .map_with_span(|(name, arguments), span| Attribute {
+            name,
+            arguments: arguments.unwrap_or_default(),
+            span,
+        })
+}
+
+// Una dichiarazione malformata (ad esempio con un'intestazione errata) viene saltata
+// fino alla fine del suo blocco, e il parsing riprende dalla dichiarazione successiva
+fn recoverable<O>(
+    parser: impl Parser<Token, O, Error = Simple<Token>>,
+    keyword: Token,
+) -> impl Parser<Token, Option<O>, Error = Simple<Token>> {
+    // Gli attributi e la visibilità fanno parte della dichiarazione e vengono saltati con essa
+    let header = attribute_parser().repeated().then(visibility_parser()).ignore_then(just(keyword));
+    parser
+        .map(Some)
+        .recover_with(skip_parser(header.ignore_then(skip_construct().or_not()).map(|_| None)))
+}
+
+// Intestazione `ritual nome(parametri) tipo`, condivisa da ritual ed estensioni
+fn signature_parser() -> impl Parser<Token, ((String, Vec<Variable>), (Type, Span)), Error = Simple<Token>> {
+    just(Token::Ritual)
+        .ignore_then(select! { Token::Identifier(name) => name })
+        .then_ignore(just(Token::LParen))
+        .then(parameter_parser().separated_by(just(Token::Comma)).or(empty().to(vec![])))
+        .then(just(Token::RParen).map_with_span(|_, span: Span| span.end))
+        // Make the return type optional with a default of Type::Void
+        .then(type_parser().map_with_span(|t, span| (t, span)).or_not())
+        .map(|((name_and_parameters, parameters_end), return_type)| {
+            (name_and_parameters, return_type.unwrap_or((Type::Void, parameters_end..parameters_end)))
+        })
+}
+
+fn ritual_parser() -> impl Parser<Token, Ritual, Error = Simple<Token>> {
+    attribute_parser()
+        .repeated()
+        .then(visibility_parser())
+        .then(
+            signature_parser()
+                .then(block_parser(statement_parser(), closing_brace("`;` or `}` after statement")))
+                .map_with_span(|(((name, parameters), return_type), body), span| (name, parameters, return_type, body, span))
+        )
+        .map(|((attributes, visibility), (name, parameters, (return_type, return_type_span), body, span))| Ritual {
+            attributes,
+            visibility,
+            name, 
+            parameters, 
+            return_type, 
+            return_type_span,
+            body,
+            span
+        })
+}
+
+// Variabile del being: stessa forma di una dichiarazione locale, preceduta dalla visibilità
+fn variable_parser() -> impl Parser<Token, BeingVariable, Error = Simple<Token>> {
+    visibility_parser()
+        .then(declaration_parser().map_with_span(|declaration, span| (declaration, span)))
+        .map(|(visibility, ((variable, initializer), span))| BeingVariable {
+            visibility,
+            variable,
+            initializer,
+            memory: false,
+            span,
+        })
+}
+
+// Blocco `memory { ... }`: variabili del being che il runtime conserva tra le esecuzioni
+fn memory_parser() -> impl Parser<Token, Vec<BeingVariable>, Error = Simple<Token>> {
+    just(Token::Memory)
+        .ignore_then(just(Token::LBrace))
+        .ignore_then(variable_parser().repeated())
+        .then_ignore(closing_brace("`}` to close memory block"))
+        .map(|variables| variables.into_iter().map(|var| BeingVariable { memory: true, ..var }).collect())
+}
+
+// Blocco `perceptions { evento(parametri); ... }`: gli eventi che il being può ricevere
+fn perceptions_parser() -> impl Parser<Token, Vec<Perception>, Error = Simple<Token>> {
+    let perception = select! { Token::Identifier(name) => name }
+        .then(
+            parameter_parser()
+                .separated_by(just(Token::Comma))
+                .delimited_by(just(Token::LParen), just(Token::RParen))
+        )
+        .then_ignore(just(Token::Semicolon))
+        .map_with_span(|(name, parameters), span| Perception { name, parameters, span });
+
+    just(Token::Perceptions)
+        .ignore_then(just(Token::LBrace))
+        .ignore_then(perception.repeated())
+        .then_ignore(closing_brace("`}` to close perceptions block"))
+}
+
+// Blocco `extensions { ritual nome(parametri) tipo; ... }`: ritual nativi forniti dall'host
+fn extensions_parser() -> impl Parser<Token, Vec<Extension>, Error = Simple<Token>> {
+    let extension = visibility_parser()
+        .then(signature_parser())
+        .then_ignore(just(Token::Semicolon))
+        .map_with_span(|(visibility, ((name, parameters), (return_type, return_type_span))), span| Extension {
+            visibility,
+            name,
+            parameters,
+            return_type,
+            return_type_span,
+            span,
+        });
+
+    just(Token::Extensions)
+        .ignore_then(just(Token::LBrace))
+        .ignore_then(extension.repeated())
+        .then_ignore(closing_brace("`}` to close extensions block"))
+}
+
+// `public` o `private`; in mancanza, privato
+fn visibility_parser() -> impl Parser<Token, Visibility, Error = Simple<Token>> + Clone {
+    just(Token::Public).to(Visibility::Public)
+        .or(just(Token::Private).to(Visibility::Private))
+        .or_not()
+        .map(Option::unwrap_or_default)
+}
+
+fn parameter_parser() -> impl Parser<Token, Variable, Error = Simple<Token>> {
+    select! { |span| Token::Identifier(name) => (name, span) }
+        .then_ignore(just(Token::Colon))
+        .then(type_parser().map_with_span(|var_type, span| (var_type, span)))
+        .map(|((name, span), (var_type, type_span))| Variable { name, var_type, span, type_span })
+}
+
+#[derive(Clone, PartialEq)]
+enum HiveModifier {
+    Circular,
+    Persistent,
+}
+
+fn type_parser() -> impl Parser<Token, Type, Error = Simple<Token>> + Clone {
+    // Tipo di un altro realm: Realm::Nome
+    let qualified = select! { Token::Identifier(realm) => realm }
+        .then_ignore(just(Token::DoubleColon))
+        .then(select! { Token::Identifier(name) => name })
+        .map(|(realm, name)| Type::Custom(format!("{}::{}", realm, name)));
+
+    let named = select! {
+        Token::Identifier(name) => match name.as_str() {
+            "int" => Type::Integer,
+            "float" => Type::Float,
+            "string" => Type::String,
+            "bool" => Type::Boolean,
+            "void" => Type::Void,
+            _ => Type::Custom(name)
+        }
+    };
+
+    // Dimensione di una hive: un intero positivo
+    let dimension = select! { Token::Number(n) => n }
+        .try_map(|n, span| match n.parse::<usize>() {
+            Ok(size) if size > 0 => Ok(size),
+            _ => Err(Simple::custom(span, "hive dimensions must be positive integers")),
+        });
+
+    // Hive: `circular persistent hive<tipo>[d1, d2, ...]`, con i modificatori in qualsiasi ordine
+    let hive = keyword("circular").to(HiveModifier::Circular)
+        .or(keyword("persistent").to(HiveModifier::Persistent))
+        .repeated()
+        .then_ignore(just(Token::Hive))
+        .then(named.delimited_by(just(Token::Less), just(Token::Greater)))
+        .then(
+            dimension
+                .separated_by(just(Token::Comma))
+                .at_least(1)
+                .delimited_by(just(Token::LBracket), just(Token::RBracket))
+        )
+        .map(|((modifiers, element), dimensions)| Type::Hive {
+            element: Box::new(element),
+            dimensions,
+            circular: modifiers.contains(&HiveModifier::Circular),
+            persistent: modifiers.contains(&HiveModifier::Persistent),
+        });
+
+    hive.or(qualified).or(named).labelled("type")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chumsky::error::SimpleReason;
+    use crate::lexer;
+
+    fn parse_source(source: &str) -> (Option<Program>, Vec<Simple<Token>>) {
+        parse(lexer::tokenize(source).expect("the source is lexically valid"))
+    }
+
+    fn parse_expression(source: &str) -> Expression {
+        let tokens = lexer::tokenize(source).expect("the source is lexically valid");
+        let eoi = source.len();
+        expression_parser()
+            .then_ignore(end())
+            .parse(chumsky::Stream::from_iter(eoi..eoi, tokens.into_iter()))
+            .expect("the expression is valid")
+    }
+
+    // Struttura di un'espressione con le parentesi esplicite: `(Add 1 (Multiply 2 3))`
+    fn shape(expr: &Expression) -> String {
+        match expr {
+            Expression::Literal(Literal::Integer(n), _) => n.to_string(),
+            Expression::Literal(literal, _) => format!("{:?}", literal),
+            Expression::Variable(name, _) => name.clone(),
+            Expression::BinaryOperation { left, operator, right, .. } => {
+                format!("({:?} {} {})", operator, shape(left), shape(right))
+            }
+            Expression::UnaryOperation { operator, operand, .. } => format!("({:?} {})", operator, shape(operand)),
+            Expression::FunctionCall { name, arguments, .. } => {
+                let arguments: Vec<String> = arguments.iter().map(shape).collect();
+                format!("{}({})", name, arguments.join(" "))
+            }
+            other => panic!("unexpected expression {:?}", other),
+        }
+    }
+
+    #[test]
+    fn operators_follow_precedence() {
+        assert_eq!(shape(&parse_expression("1 + 2 * 3 - 4 / 2")), "(Subtract (Add 1 (Multiply 2 3)) (Divide 4 2))");
+        assert_eq!(shape(&parse_expression("a < b == c > d")), "(Equal (LessThan a b) (GreaterThan c d))");
+        assert_eq!(shape(&parse_expression("(1 + 2) * f(3, x)")), "(Multiply (Add 1 2) f(3 x))");
+    }
+
+    #[test]
+    fn logical_and_unary_operators_bind_as_expected() {
+        assert_eq!(shape(&parse_expression("!a && b || c")), "(Or (And (Not a) b) c)");
+        assert_eq!(shape(&parse_expression("a || b && c == d")), "(Or a (And b (Equal c d)))");
+        assert_eq!(shape(&parse_expression("-x * --y % 3")), "(Modulo (Multiply (Negate x) (Negate (Negate y))) 3)");
+        assert_eq!(shape(&parse_expression("!true")), "(Not Boolean(true))");
+    }
+
+    #[test]
+    fn binary_operators_are_left_associative() {
+        assert_eq!(shape(&parse_expression("10 - 3 - 2")), "(Subtract (Subtract 10 3) 2)");
+        assert_eq!(shape(&parse_expression("8 / 4 / 2")), "(Divide (Divide 8 4) 2)");
+    }
+
+    #[test]
+    fn nodes_span_their_source_text() {
+        let expr = parse_expression("a + b * f(c)");
+        assert_eq!(expr.span(), 0..12);
+        let Expression::BinaryOperation { right, .. } = expr else {
+            panic!("expected a binary operation");
+        };
+        assert_eq!(right.span(), 4..12);
+
+        let (program, _) = parse_source("realm R { being B { ritual go() { return; } } }");
+        let being = &program.unwrap().realms[0].beings[0];
+        assert_eq!(being.span, 10..45);
+        assert_eq!(being.rituals[0].span, 20..43);
+        assert_eq!(being.rituals[0].body[0].span(), 34..41);
+    }
+
+    #[test]
+    fn every_statement_form_is_parsed() {
+        let (program, errors) = parse_source("
+            realm R {
+                being B {
+                    ritual run(n: int) int {
+                        int total: 0;
+                        float ratio;
+                        total = n * 2;
+                        log(total);
+                        if (n > 1) {
+                            ratio = 0.5;
+                        } else if (n > 0) {
+                            ratio = 1.0;
+                        } else {
+                            return 0;
+                        }
+                        cycle (total > 0) {
+                            total = total - 1;
+                        }
+                        cycle {
+                            return total;
+                        }
+                    }
+                }
+            }
+        ");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let program = program.unwrap();
+        let body = &program.realms[0].beings[0].rituals[0].body;
+
+        let forms: Vec<&str> = body.iter().map(|statement| match statement {
+            Statement::VariableDeclaration { initializer: Some(_), .. } => "declaration with initializer",
+            Statement::VariableDeclaration { initializer: None, .. } => "declaration",
+            Statement::Assignment { .. } => "assignment",
+            Statement::RitualCall { .. } => "call",
+            Statement::Conditional { .. } => "conditional",
+            Statement::Cycle { condition: Some(_), .. } => "cycle",
+            Statement::Cycle { condition: None, .. } => "endless cycle",
+            other => panic!("unexpected statement {:?}", other),
+        }).collect();
+        assert_eq!(forms, [
+            "declaration with initializer", "declaration", "assignment", "call", "conditional", "cycle", "endless cycle",
+        ]);
+
+        // `else if` è un conditional annidato nel ramo else
+        let Statement::Conditional { false_branch: Some(otherwise), .. } = &body[4] else {
+            panic!("expected a conditional with an else branch");
+        };
+        assert!(matches!(
+            otherwise.as_slice(),
+            [Statement::Conditional { false_branch: Some(last), .. }] if matches!(last.as_slice(), [Statement::Return(Some(_), _)])
+        ));
+    }
+
+    #[test]
+    fn recovery_reports_every_syntax_error() {
+        let (program, errors) = parse_source("
+            realm R {
+                being A {
+                    ritual f() {
+                        int x: 1 +;
+                        x = 2;
+                    }
+
+                    ritual g() {
+                        if (x > ) {
+                        }
+                    }
+
+                being C {
+                    ritual h() {
+                        return;
+                    }
+                }
+            }
+        ");
+        let messages: Vec<String> = errors.iter().map(|error| error::to_diagnostic(error).message).collect();
+        assert_eq!(messages, [
+            "expected expression, found `;`",
+            "expected expression, found `)`",
+            "expected `}` to close being, found `being`",
+        ]);
+
+        // Il resto del programma è comunque nell'albero
+        let realm = &program.expect("the parser recovers a program").realms[0];
+        let names: Vec<&str> = realm.beings.iter()
+            .flat_map(|being| being.rituals.iter().map(|ritual| ritual.name.as_str()))
+            .collect();
+        assert_eq!(names, ["f", "g", "h"]);
+        assert!(matches!(realm.beings[0].rituals[0].body[1], Statement::Assignment { .. }));
+    }
+
+    #[test]
+    fn being_sections_can_appear_in_any_order() {
+        let (program, errors) = parse_source("
+            realm R {
+                being B {
+                    public ritual first() int {
+                        return count;
+                    }
+
+                    perceptions {
+                        tick(n: int);
+                    }
+
+                    int count: 0;
+
+                    ritual tick(n: int) {
+                        count = count + n;
+                    }
+
+                    memory {
+                        int runs: 0;
+                    }
+
+                    extensions {
+                        ritual now() int;
+                    }
+                }
+            }
+        ");
+        assert!(errors.is_empty(), "{:?}", errors);
+        let being = &program.unwrap().realms[0].beings[0];
+        let variables: Vec<(&str, bool)> = being.variables.iter()
+            .map(|var| (var.variable.name.as_str(), var.memory))
+            .collect();
+        assert_eq!(variables, [("count", false), ("runs", true)]);
+        let rituals: Vec<&str> = being.rituals.iter().map(|ritual| ritual.name.as_str()).collect();
+        assert_eq!(rituals, ["first", "tick"]);
+        assert_eq!(being.perceptions[0].name, "tick");
+        assert_eq!(being.extensions[0].name, "now");
+    }
+
+    #[test]
+    fn oversized_integers_are_reported() {
+        let (_, errors) = parse_source("realm R { being B { int x: 99999999999999999999; } }");
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].reason(), SimpleReason::Custom(message) if message == "integer literal out of range"));
+        assert_eq!(errors[0].span(), 27..47);
+    }
+}
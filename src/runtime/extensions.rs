@@ -0,0 +1,270 @@
+// Estensioni: ritual dichiarati nel blocco `extensions` di un being e implementati in Rust
+//
+// Le funzioni native vengono collegate per nome prima di creare il runtime, che rifiuta di
+// partire se un'estensione dichiarata dal programma è rimasta senza implementazione. Una funzione
+// collegata al nome qualificato, come `Host::Clock.now`, serve solo quel being; una collegata al
+// nome semplice serve tutti i being che dichiarano un'estensione con quel nome e non ne hanno una
+// propria.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use crate::ast::nodes::{Extension, Program, Type};
+use crate::ast::printer;
+use super::interpreter;
+use super::{NervsRuntime, RuntimeError, RuntimeValue};
+
+/// Funzione Rust che implementa un'estensione. Riceve gli argomenti già convertiti nei tipi
+/// dichiarati; un errore interrompe il ritual chiamante con il messaggio indicato
+pub type NativeRitual = Rc<dyn Fn(&[RuntimeValue]) -> Result<RuntimeValue, String>>;
+
+/// Funzioni native da collegare alle estensioni dichiarate dal programma
+#[derive(Clone, Default)]
+pub struct Extensions {
+    natives: HashMap<String, NativeRitual>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collega una funzione alle estensioni con questo nome, sostituendo quella collegata in
+    /// precedenza. Il nome può essere qualificato con il being, come `Realm::Being.nome`
+    pub fn bind<F>(&mut self, name: &str, native: F) -> &mut Self
+    where
+        F: Fn(&[RuntimeValue]) -> Result<RuntimeValue, String> + 'static,
+    {
+        self.natives.insert(name.to_string(), Rc::new(native));
+        self
+    }
+
+    /// Indica se una funzione è collegata a questo nome, semplice o qualificato
+    pub fn is_bound(&self, name: &str) -> bool {
+        self.natives.contains_key(name)
+    }
+
+    // Funzione dell'estensione di un being: quella collegata al nome qualificato, altrimenti
+    // quella collegata al nome semplice
+    fn native(&self, realm: &str, being: &str, name: &str) -> Option<&NativeRitual> {
+        self.natives.get(&qualified_name(realm, being, name)).or_else(|| self.natives.get(name))
+    }
+
+    // Estensioni dichiarate dal programma ma senza funzione, come `Realm::Being.nome`
+    pub(super) fn unbound(&self, program: &Program) -> Vec<String> {
+        let mut missing = Vec::new();
+        for realm in &program.realms {
+            for being in &realm.beings {
+                for extension in &being.extensions {
+                    if self.native(&realm.name, &being.name, &extension.name).is_none() {
+                        missing.push(qualified_name(&realm.name, &being.name, &extension.name));
+                    }
+                }
+            }
+        }
+        missing
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&String> = self.natives.keys().collect();
+        names.sort();
+        f.debug_struct("Extensions").field("natives", &names).finish()
+    }
+}
+
+// Nome di un'estensione qualificato con il suo being: `Realm::Being.nome`
+fn qualified_name(realm: &str, being: &str, name: &str) -> String {
+    format!("{}::{}.{}", realm, being, name)
+}
+
+impl NervsRuntime {
+    /// Collega una funzione alle estensioni con questo nome, semplice o qualificato, sostituendo
+    /// quella collegata in precedenza. Le estensioni chiamate durante l'avvio, dagli
+    /// inizializzatori o da `init`, vanno collegate prima, con `with_extensions`
+    pub fn bind<F>(&mut self, name: &str, native: F) -> &mut Self
+    where
+        F: Fn(&[RuntimeValue]) -> Result<RuntimeValue, String> + 'static,
+    {
+        self.extensions.bind(name, native);
+        self
+    }
+
+    // Estensione dichiarata da un being con questo nome
+    pub(super) fn lookup_extension(&self, realm: &str, being: &str, name: &str) -> Option<Rc<Extension>> {
+        self.realms.get(realm)?.beings.get(being)?.extensions.get(name).cloned()
+    }
+
+    // Chiama la funzione nativa di un'estensione, controllando argomenti e risultato rispetto
+    // alla firma dichiarata
+    pub(super) fn call_extension(
+        &self,
+        realm: &str,
+        being: &str,
+        extension: &Extension,
+        arguments: Vec<RuntimeValue>,
+    ) -> Result<RuntimeValue, RuntimeError> {
+        if arguments.len() != extension.parameters.len() {
+            return Err(RuntimeError::ArgumentCount {
+                ritual: extension.name.clone(),
+                expected: extension.parameters.len(),
+                found: arguments.len(),
+            });
+        }
+        let mut converted = Vec::with_capacity(arguments.len());
+        for (param, value) in extension.parameters.iter().zip(arguments) {
+            converted.push(interpreter::coerce(value, &param.var_type).ok_or_else(|| RuntimeError::TypeError(
+                format!("argument '{}' of extension '{}' expects {}", param.name, extension.name, printer::type_name(&param.var_type))
+            ))?);
+        }
+
+        // Il collegamento è verificato all'avvio
+        let native = self.extensions.native(realm, being, &extension.name)
+            .ok_or_else(|| RuntimeError::UnboundExtensions(vec![qualified_name(realm, being, &extension.name)]))?;
+        let result = native(&converted).map_err(|message| RuntimeError::Extension {
+            extension: extension.name.clone(),
+            message,
+        })?;
+
+        let returned = result.type_name();
+        let value = match (&extension.return_type, result) {
+            (Type::Void, RuntimeValue::Void) => Some(RuntimeValue::Void),
+            (Type::Void, _) => None,
+            (return_type, result) => interpreter::coerce(result, return_type),
+        };
+        value.ok_or_else(|| RuntimeError::TypeError(format!(
+            "extension '{}' returned {}, expected {}", extension.name, returned, printer::type_name(&extension.return_type)
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use crate::{lexer, parser};
+    use crate::runtime::RuntimeOptions;
+
+    const PROGRAM: &str = "
+        realm Host {
+            being Clock {
+                int started: 0;
+
+                extensions {
+                    public ritual now() int;
+                    ritual log(msg: string);
+                }
+
+                ritual init() {
+                    started = now();
+                    log(\"started\");
+                }
+
+                public ritual elapsed(scale: float) float {
+                    log(\"elapsed\");
+                    return scale * (now() - started);
+                }
+            }
+        }
+    ";
+
+    fn program() -> Program {
+        let tokens = lexer::tokenize(PROGRAM).expect("the program is valid");
+        let (program, errors) = parser::parse(tokens);
+        assert!(errors.is_empty());
+        program.expect("the program is valid")
+    }
+
+    #[test]
+    fn extensions_call_the_bound_closures() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut extensions = Extensions::new();
+        let clock = RefCell::new(10);
+        extensions.bind("now", move |_| {
+            *clock.borrow_mut() += 5;
+            Ok(RuntimeValue::Integer(*clock.borrow()))
+        });
+        let messages = log.clone();
+        extensions.bind("log", move |arguments| {
+            messages.borrow_mut().push(arguments[0].to_string());
+            Ok(RuntimeValue::Void)
+        });
+
+        let mut runtime = NervsRuntime::with_extensions(&program(), &RuntimeOptions::default(), extensions).unwrap();
+        assert_eq!(runtime.variable("Host", "Clock", "started"), Some(&RuntimeValue::Integer(15)));
+        let elapsed = runtime.execute_ritual("Host", "Clock", "elapsed", vec![RuntimeValue::Float(0.5)]).unwrap();
+        assert_eq!(elapsed, RuntimeValue::Float(2.5));
+        assert_eq!(*log.borrow(), ["started", "elapsed"]);
+    }
+
+    #[test]
+    fn unbound_extensions_are_reported_at_startup() {
+        let mut extensions = Extensions::new();
+        extensions.bind("log", |_| Ok(RuntimeValue::Void));
+        match NervsRuntime::with_extensions(&program(), &RuntimeOptions::default(), extensions) {
+            Err(RuntimeError::UnboundExtensions(missing)) => assert_eq!(missing, ["Host::Clock.now"]),
+            other => panic!("expected an unbound extension error, found {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn native_results_are_checked() {
+        let mut extensions = Extensions::new();
+        extensions.bind("now", |_| Ok(RuntimeValue::String("noon".to_string())));
+        extensions.bind("log", |_| Err("disk full".to_string()));
+        assert!(matches!(
+            NervsRuntime::with_extensions(&program(), &RuntimeOptions::default(), extensions.clone()),
+            Err(RuntimeError::TypeError(_))
+        ));
+
+        extensions.bind("now", |_| Ok(RuntimeValue::Integer(0)));
+        assert!(matches!(
+            NervsRuntime::with_extensions(&program(), &RuntimeOptions::default(), extensions),
+            Err(RuntimeError::Extension { message, .. }) if message == "disk full"
+        ));
+    }
+
+    #[test]
+    fn qualified_bindings_take_precedence() {
+        let tokens = lexer::tokenize("
+            realm Host {
+                being Console {
+                    extensions {
+                        ritual log(msg: string) int;
+                    }
+
+                    public ritual write() int {
+                        return log(\"ready\");
+                    }
+                }
+
+                being Journal {
+                    extensions {
+                        ritual log(level: int, msg: string) int;
+                    }
+
+                    public ritual write() int {
+                        return log(2, \"ready\");
+                    }
+                }
+            }
+        ").unwrap();
+        let program = parser::parse(tokens).0.unwrap();
+
+        let mut extensions = Extensions::new();
+        extensions.bind("log", |arguments| Ok(RuntimeValue::Integer(arguments.len() as i64)));
+        extensions.bind("Host::Journal.log", |arguments| match arguments[0] {
+            RuntimeValue::Integer(level) => Ok(RuntimeValue::Integer(10 * level)),
+            _ => Err("the level must be an integer".to_string()),
+        });
+        let mut runtime = NervsRuntime::with_extensions(&program, &RuntimeOptions::default(), extensions).unwrap();
+        let console = runtime.execute_ritual("Host", "Console", "write", Vec::new()).unwrap();
+        let journal = runtime.execute_ritual("Host", "Journal", "write", Vec::new()).unwrap();
+        assert_eq!((console, journal), (RuntimeValue::Integer(1), RuntimeValue::Integer(20)));
+
+        // Un collegamento fatto dopo l'avvio sostituisce quello precedente
+        runtime.bind("Host::Console.log", |_| Ok(RuntimeValue::Integer(7)));
+        assert_eq!(runtime.execute_ritual("Host", "Console", "write", Vec::new()).unwrap(), RuntimeValue::Integer(7));
+    }
+}
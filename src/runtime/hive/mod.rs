@@ -0,0 +1,904 @@
+// Implementation of the Hive multidimensional data structure
+// Hives produced by an operation keep the circular flag of their source but are never persistent
+
+pub mod storage;
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use storage::{HiveFile, CHUNK_ELEMENTS};
+
+/// Persistent hives with more elements than this are not loaded in memory by `open`: their
+/// elements are read and written one chunk at a time, straight from the file
+pub const CHUNKED_THRESHOLD: usize = 16 * 1024 * 1024;
+
+/// Errors raised when accessing or combining the elements of a Hive
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum HiveError {
+    #[error("Hive with {expected} dimension(s) indexed with {found} index(es)")]
+    IndexCount {
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("Index {index} is out of bounds for dimension {axis} of size {size}")]
+    OutOfBounds {
+        index: i64,
+        axis: usize,
+        size: usize,
+    },
+
+    #[error("Axis {axis} does not exist in a Hive with {count} dimension(s)")]
+    InvalidAxis {
+        axis: usize,
+        count: usize,
+    },
+
+    #[error("Slice {start}..{end} is out of bounds for dimension {axis} of size {size}")]
+    SliceOutOfBounds {
+        start: usize,
+        end: usize,
+        axis: usize,
+        size: usize,
+    },
+
+    #[error("Hives of shape {left:?} and {right:?} cannot be combined")]
+    ShapeMismatch {
+        left: Vec<usize>,
+        right: Vec<usize>,
+    },
+
+    #[error("{size} element(s) do not fit a Hive of shape {dimensions:?}")]
+    SizeMismatch {
+        size: usize,
+        dimensions: Vec<usize>,
+    },
+
+    #[error("Dimension {axis} is empty and cannot be reduced")]
+    EmptyAxis {
+        axis: usize,
+    },
+
+    #[error("Elements {start}..{end} are out of bounds for a Hive of {size} element(s)")]
+    ElementRange {
+        start: usize,
+        end: usize,
+        size: usize,
+    },
+
+    #[error("Cannot access hive file {path}: {message}")]
+    Io {
+        path: String,
+        message: String,
+    },
+
+    #[error("{path} is not a valid hive file: {reason}")]
+    InvalidFile {
+        path: String,
+        reason: String,
+    },
+
+    #[error("Hive file {path} uses format version {version}, which is not supported")]
+    UnsupportedVersion {
+        path: String,
+        version: u16,
+    },
+
+    #[error("Hive file {path} is corrupted: its checksum does not match its contents")]
+    ChecksumMismatch {
+        path: String,
+    },
+
+    #[error("Hive file {path} is read-only and cannot be modified")]
+    ReadOnly {
+        path: String,
+    },
+
+    #[error("Hive file {path} has shape {found:?}, but the Hive is declared with shape {expected:?}")]
+    DimensionMismatch {
+        path: String,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+}
+
+/// Represents a multidimensional Hive data structure
+///
+/// A Hive opened with `open` is backed by a file. Small hives keep their elements in memory
+/// and write them back by `flush`, or when the Hive is dropped; hives above `CHUNKED_THRESHOLD`
+/// elements keep only one chunk in memory and write it back in place. Copies made by `try_clone`
+/// are never backed by the file. Hive does not implement `Clone` or `PartialEq`: copying or
+/// comparing a chunked Hive reads the whole file, which can fail.
+#[derive(Debug)]
+pub struct Hive {
+    dimensions: Vec<usize>,
+    elements: Elements,
+    is_circular: bool,
+    is_persistent: bool,
+    storage: Option<Storage>,
+}
+
+// Where the elements of a Hive live
+#[derive(Debug)]
+enum Elements {
+    Memory(Vec<f64>),
+    // Elements of a large persistent Hive, read from its file on demand
+    Chunked(Box<RefCell<Chunks>>),
+}
+
+// File backing a persistent Hive
+#[derive(Debug)]
+struct Storage {
+    path: PathBuf,
+    // Elements changed since the last flush
+    modified: bool,
+    // The file cannot be written, so neither can the elements
+    read_only: bool,
+}
+
+// The chunk of a file-backed Hive most recently accessed, written back before moving to another
+#[derive(Debug)]
+struct Chunks {
+    file: HiveFile,
+    // Position of the first element of the chunk; the chunk is empty until the first access
+    start: usize,
+    chunk: Vec<f64>,
+    dirty: bool,
+}
+
+impl Chunks {
+    // Loads the chunk holding element `offset` and returns the position of the element in it
+    fn load(&mut self, offset: usize) -> Result<usize, HiveError> {
+        let start = offset - offset % CHUNK_ELEMENTS;
+        if self.chunk.is_empty() || start != self.start {
+            self.write_back()?;
+            let length = CHUNK_ELEMENTS.min(self.file.size() - start);
+            self.chunk.resize(length, 0.0);
+            self.file.read(start, &mut self.chunk)?;
+            self.start = start;
+        }
+        Ok(offset - start)
+    }
+
+    fn get(&mut self, offset: usize) -> Result<f64, HiveError> {
+        let position = self.load(offset)?;
+        Ok(self.chunk[position])
+    }
+
+    fn set(&mut self, offset: usize, value: f64) -> Result<(), HiveError> {
+        if !self.file.is_writable() {
+            return Err(HiveError::ReadOnly { path: storage::display(self.file.path()) });
+        }
+        let position = self.load(offset)?;
+        self.chunk[position] = value;
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn read_all(&mut self) -> Result<Vec<f64>, HiveError> {
+        self.write_back()?;
+        let mut data = vec![0.0; self.file.size()];
+        self.file.read(0, &mut data)?;
+        Ok(data)
+    }
+
+    fn write_all(&mut self, data: &[f64]) -> Result<(), HiveError> {
+        self.chunk.clear();
+        self.dirty = false;
+        self.file.write(0, data)
+    }
+
+    fn write_back(&mut self) -> Result<(), HiveError> {
+        if self.dirty {
+            self.file.write(self.start, &self.chunk)?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), HiveError> {
+        self.write_back()?;
+        self.file.sync()
+    }
+}
+
+impl Hive {
+    /// Creates a new Hive with the specified dimensions
+    pub fn new(dimensions: Vec<usize>, is_circular: bool, is_persistent: bool) -> Self {
+        let size = dimensions.iter().product();
+        let data = vec![0.0; size];
+
+        Hive {
+            dimensions,
+            elements: Elements::Memory(data),
+            is_circular,
+            is_persistent,
+            storage: None,
+        }
+    }
+
+    /// Opens the persistent Hive stored at `path`, creating the file if it does not exist
+    ///
+    /// An existing file must have the given dimensions; its circular flag is replaced by
+    /// `is_circular` the next time the file is written. A read-only file can be read but not
+    /// modified. Hives with more than `CHUNKED_THRESHOLD` elements are opened as with `open_chunked`.
+    pub fn open(path: &Path, dimensions: Vec<usize>, is_circular: bool) -> Result<Self, HiveError> {
+        if dimensions.iter().product::<usize>() > CHUNKED_THRESHOLD {
+            return Self::open_chunked(path, dimensions, is_circular);
+        }
+
+        let exists = path.exists();
+        let read_only = exists && is_read_only(path);
+        let mut hive = Hive::new(dimensions, is_circular, true);
+        if exists {
+            // The file is replaced as a whole by `flush`, so it is only read here
+            let mut file = HiveFile::open_read_only(path)?;
+            check_dimensions(&file, &hive.dimensions)?;
+            if let Elements::Memory(data) = &mut hive.elements {
+                file.read(0, data)?;
+            }
+        }
+
+        hive.storage = Some(Storage { path: path.to_path_buf(), modified: !exists, read_only });
+        hive.flush()?;
+        Ok(hive)
+    }
+
+    /// Opens the persistent Hive stored at `path` without loading its elements: they are read
+    /// and written in chunks of `storage::CHUNK_ELEMENTS`, and `flush` writes the changed chunk
+    /// in place. A read-only file can be read but not modified.
+    pub fn open_chunked(path: &Path, dimensions: Vec<usize>, is_circular: bool) -> Result<Self, HiveError> {
+        let read_only = is_read_only(path);
+        let file = if !path.exists() {
+            HiveFile::create(path, dimensions.clone(), is_circular)?
+        } else if read_only {
+            HiveFile::open_read_only(path)?
+        } else {
+            HiveFile::open(path)?
+        };
+        check_dimensions(&file, &dimensions)?;
+
+        let chunks = Chunks { file, start: 0, chunk: Vec::new(), dirty: false };
+        let mut hive = Hive {
+            dimensions,
+            elements: Elements::Chunked(Box::new(RefCell::new(chunks))),
+            is_circular,
+            is_persistent: true,
+            storage: Some(Storage { path: path.to_path_buf(), modified: false, read_only }),
+        };
+        hive.flush()?;
+        Ok(hive)
+    }
+
+    /// Creates a Hive from its elements in row-major order
+    pub fn from_data(
+        dimensions: Vec<usize>,
+        data: Vec<f64>,
+        is_circular: bool,
+        is_persistent: bool,
+    ) -> Result<Self, HiveError> {
+        if data.len() != dimensions.iter().product::<usize>() {
+            return Err(HiveError::SizeMismatch { size: data.len(), dimensions });
+        }
+
+        Ok(Hive {
+            dimensions,
+            elements: Elements::Memory(data),
+            is_circular,
+            is_persistent,
+            storage: None,
+        })
+    }
+
+    /// Gets the total number of elements in the Hive
+    pub fn size(&self) -> usize {
+        self.dimensions.iter().product()
+    }
+
+    /// Gets the number of dimensions in the Hive
+    pub fn dimension_count(&self) -> usize {
+        self.dimensions.len()
+    }
+
+    /// Gets the size of each dimension
+    pub fn dimensions(&self) -> &[usize] {
+        &self.dimensions
+    }
+
+    /// Gets the elements in row-major order; a chunked Hive reads them all from its file
+    pub fn data(&self) -> Result<Cow<'_, [f64]>, HiveError> {
+        match &self.elements {
+            Elements::Memory(data) => Ok(Cow::Borrowed(data)),
+            Elements::Chunked(chunks) => Ok(Cow::Owned(chunks.borrow_mut().read_all()?)),
+        }
+    }
+
+    /// Checks whether indices wrap around the dimensions
+    pub fn is_circular(&self) -> bool {
+        self.is_circular
+    }
+
+    /// Makes indices wrap around the dimensions, or be checked against them
+    pub fn set_circular(&mut self, is_circular: bool) {
+        self.is_circular = is_circular;
+    }
+
+    /// Checks whether the Hive is backed by persistent storage
+    pub fn is_persistent(&self) -> bool {
+        self.is_persistent
+    }
+
+    /// Checks whether the elements are read from the backing file on demand
+    pub fn is_chunked(&self) -> bool {
+        matches!(self.elements, Elements::Chunked(_))
+    }
+
+    /// Gets the path of the file backing the Hive, if it was opened from one
+    pub fn path(&self) -> Option<&Path> {
+        self.storage.as_ref().map(|storage| storage.path.as_path())
+    }
+
+    /// Writes the elements back to the backing file, if they changed since the last flush
+    ///
+    /// An in-memory Hive replaces the file atomically: a new file is written next to it and
+    /// renamed over it. A chunked Hive writes the changed chunk in place and updates the checksum.
+    pub fn flush(&mut self) -> Result<(), HiveError> {
+        if let Elements::Chunked(chunks) = &self.elements {
+            return chunks.borrow_mut().sync();
+        }
+        let Some(storage) = self.storage.as_mut().filter(|storage| storage.modified) else {
+            return Ok(());
+        };
+        let Elements::Memory(data) = &self.elements else {
+            unreachable!("chunked hives are flushed in place");
+        };
+
+        let mut temporary = storage.path.clone().into_os_string();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        {
+            let mut file = HiveFile::create(&temporary, self.dimensions.clone(), self.is_circular)?;
+            file.write(0, data)?;
+            file.sync()?;
+        }
+        std::fs::rename(&temporary, &storage.path).map_err(|e| HiveError::Io {
+            path: storage::display(&storage.path),
+            message: e.to_string(),
+        })?;
+        storage.modified = false;
+        Ok(())
+    }
+
+    /// Gets the element at the given indices, one per dimension
+    pub fn get(&self, indices: &[i64]) -> Result<f64, HiveError> {
+        self.view().get(indices)
+    }
+
+    /// Sets the element at the given indices, one per dimension
+    pub fn set(&mut self, indices: &[i64], value: f64) -> Result<(), HiveError> {
+        self.check_writable()?;
+        let offset = self.view().offset(indices)?;
+        match &mut self.elements {
+            Elements::Memory(data) => data[offset] = value,
+            Elements::Chunked(chunks) => chunks.get_mut().set(offset, value)?,
+        }
+        self.mark_modified();
+        Ok(())
+    }
+
+    /// Replaces all the elements with those of a Hive of the same shape
+    pub fn copy_from(&mut self, other: &Hive) -> Result<(), HiveError> {
+        if self.dimensions != other.dimensions {
+            return Err(HiveError::ShapeMismatch {
+                left: self.dimensions.clone(),
+                right: other.dimensions.clone(),
+            });
+        }
+        self.check_writable()?;
+
+        let data = other.data()?;
+        match &mut self.elements {
+            Elements::Memory(elements) => elements.copy_from_slice(&data),
+            Elements::Chunked(chunks) => chunks.get_mut().write_all(&data)?,
+        }
+        self.mark_modified();
+        Ok(())
+    }
+
+    /// Copies the Hive into memory; a chunked Hive is read as a whole from its file
+    pub fn try_clone(&self) -> Result<Hive, HiveError> {
+        Ok(Hive {
+            dimensions: self.dimensions.clone(),
+            elements: Elements::Memory(self.data()?.into_owned()),
+            is_circular: self.is_circular,
+            is_persistent: self.is_persistent,
+            storage: None,
+        })
+    }
+
+    /// Compares shape and elements, whether the hives are circular or persistent or not; a
+    /// chunked Hive is read as a whole from its file
+    pub fn equals(&self, other: &Hive) -> Result<bool, HiveError> {
+        Ok(self.dimensions == other.dimensions && self.data()? == other.data()?)
+    }
+
+    /// Borrows the whole Hive as a view
+    pub fn view(&self) -> HiveView<'_> {
+        HiveView {
+            hive: self,
+            offset: 0,
+            dimensions: self.dimensions.clone(),
+            strides: strides(&self.dimensions),
+        }
+    }
+
+    /// Restricts one dimension to `range`, without copying the elements
+    pub fn slice(&self, axis: usize, range: Range<usize>) -> Result<HiveView<'_>, HiveError> {
+        self.view().slice(axis, range)
+    }
+
+    /// Adds two hives of the same shape element by element
+    pub fn add(&self, other: &Hive) -> Result<Hive, HiveError> {
+        self.zip_with(other, |a, b| a + b)
+    }
+
+    /// Subtracts two hives of the same shape element by element
+    pub fn subtract(&self, other: &Hive) -> Result<Hive, HiveError> {
+        self.zip_with(other, |a, b| a - b)
+    }
+
+    /// Multiplies two hives of the same shape element by element
+    pub fn multiply(&self, other: &Hive) -> Result<Hive, HiveError> {
+        self.zip_with(other, |a, b| a * b)
+    }
+
+    /// Divides two hives of the same shape element by element
+    pub fn divide(&self, other: &Hive) -> Result<Hive, HiveError> {
+        self.zip_with(other, |a, b| a / b)
+    }
+
+    /// Sum of all the elements
+    pub fn sum(&self) -> Result<f64, HiveError> {
+        Ok(self.data()?.iter().sum())
+    }
+
+    /// Smallest element, `None` if the Hive is empty
+    pub fn min(&self) -> Result<Option<f64>, HiveError> {
+        Ok(self.data()?.iter().copied().reduce(f64::min))
+    }
+
+    /// Largest element, `None` if the Hive is empty
+    pub fn max(&self) -> Result<Option<f64>, HiveError> {
+        Ok(self.data()?.iter().copied().reduce(f64::max))
+    }
+
+    /// Average of all the elements, `None` if the Hive is empty
+    pub fn mean(&self) -> Result<Option<f64>, HiveError> {
+        let data = self.data()?;
+        Ok((!data.is_empty()).then(|| data.iter().sum::<f64>() / data.len() as f64))
+    }
+
+    /// Sums along one dimension, which is removed from the result
+    pub fn sum_axis(&self, axis: usize) -> Result<Hive, HiveError> {
+        self.reduce_axis(axis, |lane| lane.iter().sum())
+    }
+
+    /// Smallest element along one dimension, which is removed from the result
+    pub fn min_axis(&self, axis: usize) -> Result<Hive, HiveError> {
+        self.reduce_axis(axis, |lane| lane.iter().copied().fold(f64::INFINITY, f64::min))
+    }
+
+    /// Largest element along one dimension, which is removed from the result
+    pub fn max_axis(&self, axis: usize) -> Result<Hive, HiveError> {
+        self.reduce_axis(axis, |lane| lane.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+    }
+
+    /// Average along one dimension, which is removed from the result
+    pub fn mean_axis(&self, axis: usize) -> Result<Hive, HiveError> {
+        self.reduce_axis(axis, |lane| lane.iter().sum::<f64>() / lane.len() as f64)
+    }
+
+    /// Copies the elements, in row-major order, into a Hive of a different shape
+    pub fn reshape(&self, dimensions: Vec<usize>) -> Result<Hive, HiveError> {
+        Hive::from_data(dimensions, self.data()?.into_owned(), self.is_circular, false)
+    }
+
+    /// Reverses the order of the dimensions: element `[i, j]` moves to `[j, i]`
+    pub fn transpose(&self) -> Result<Hive, HiveError> {
+        let mut view = self.view();
+        view.dimensions.reverse();
+        view.strides.reverse();
+        view.to_hive()
+    }
+
+    // Element at a row-major position, already checked against the size
+    fn element(&self, offset: usize) -> Result<f64, HiveError> {
+        match &self.elements {
+            Elements::Memory(data) => Ok(data[offset]),
+            Elements::Chunked(chunks) => chunks.borrow_mut().get(offset),
+        }
+    }
+
+    fn check_writable(&self) -> Result<(), HiveError> {
+        match &self.storage {
+            Some(storage) if storage.read_only => Err(HiveError::ReadOnly { path: storage::display(&storage.path) }),
+            _ => Ok(()),
+        }
+    }
+
+    fn mark_modified(&mut self) {
+        if let Some(storage) = &mut self.storage {
+            storage.modified = true;
+        }
+    }
+
+    fn zip_with(&self, other: &Hive, operation: impl Fn(f64, f64) -> f64) -> Result<Hive, HiveError> {
+        if self.dimensions != other.dimensions {
+            return Err(HiveError::ShapeMismatch {
+                left: self.dimensions.clone(),
+                right: other.dimensions.clone(),
+            });
+        }
+
+        let data = self.data()?.iter().zip(other.data()?.iter()).map(|(&a, &b)| operation(a, b)).collect();
+        Hive::from_data(self.dimensions.clone(), data, self.is_circular, false)
+    }
+
+    // The elements along `axis` form a lane for every position of the other dimensions
+    fn reduce_axis(&self, axis: usize, reduce: impl Fn(&[f64]) -> f64) -> Result<Hive, HiveError> {
+        let Some(&length) = self.dimensions.get(axis) else {
+            return Err(HiveError::InvalidAxis { axis, count: self.dimensions.len() });
+        };
+        let outer: usize = self.dimensions[..axis].iter().product();
+        let inner: usize = self.dimensions[axis + 1..].iter().product();
+        // An empty lane has no min, max or mean
+        if length == 0 && outer * inner > 0 {
+            return Err(HiveError::EmptyAxis { axis });
+        }
+
+        let elements = self.data()?;
+        let mut data = Vec::with_capacity(outer * inner);
+        let mut lane = Vec::with_capacity(length);
+        for o in 0..outer {
+            for i in 0..inner {
+                lane.clear();
+                lane.extend((0..length).map(|k| elements[(o * length + k) * inner + i]));
+                data.push(reduce(&lane));
+            }
+        }
+
+        let mut dimensions = self.dimensions.clone();
+        dimensions.remove(axis);
+        Hive::from_data(dimensions, data, self.is_circular, false)
+    }
+}
+
+impl Drop for Hive {
+    fn drop(&mut self) {
+        // Drop cannot report errors: call `flush` explicitly to see them
+        let _ = self.flush();
+    }
+}
+
+/// A rectangular part of a Hive, borrowed without copying its elements
+///
+/// Views index with the same rules as their Hive: a view of a circular Hive wraps
+/// indices around its own dimensions.
+#[derive(Debug, Clone)]
+pub struct HiveView<'a> {
+    hive: &'a Hive,
+    // Position of the first element of the view in the data of the Hive
+    offset: usize,
+    dimensions: Vec<usize>,
+    // Distance in the data of the Hive between consecutive indices of each dimension
+    strides: Vec<usize>,
+}
+
+impl HiveView<'_> {
+    /// Gets the size of each dimension of the view
+    pub fn dimensions(&self) -> &[usize] {
+        &self.dimensions
+    }
+
+    /// Gets the number of elements in the view
+    pub fn size(&self) -> usize {
+        self.dimensions.iter().product()
+    }
+
+    /// Gets the element at the given indices, relative to the start of the view
+    pub fn get(&self, indices: &[i64]) -> Result<f64, HiveError> {
+        self.hive.element(self.offset(indices)?)
+    }
+
+    /// Restricts one dimension of the view to `range`
+    pub fn slice(&self, axis: usize, range: Range<usize>) -> Result<Self, HiveError> {
+        let Some(&size) = self.dimensions.get(axis) else {
+            return Err(HiveError::InvalidAxis { axis, count: self.dimensions.len() });
+        };
+        if range.start > range.end || range.end > size {
+            return Err(HiveError::SliceOutOfBounds { start: range.start, end: range.end, axis, size });
+        }
+
+        let mut view = self.clone();
+        view.offset += range.start * self.strides[axis];
+        view.dimensions[axis] = range.len();
+        Ok(view)
+    }
+
+    /// Gets the elements of the view in row-major order
+    pub fn elements(&self) -> Result<Vec<f64>, HiveError> {
+        let mut elements = Vec::with_capacity(self.size());
+        if self.size() == 0 {
+            return Ok(elements);
+        }
+
+        // Step through the indices of the view in row-major order
+        let mut indices = vec![0; self.dimensions.len()];
+        loop {
+            let offset: usize = indices.iter().zip(&self.strides).map(|(index, stride)| index * stride).sum();
+            elements.push(self.hive.element(self.offset + offset)?);
+
+            let mut axis = indices.len();
+            loop {
+                if axis == 0 {
+                    return Ok(elements);
+                }
+                axis -= 1;
+                indices[axis] += 1;
+                if indices[axis] < self.dimensions[axis] {
+                    break;
+                }
+                indices[axis] = 0;
+            }
+        }
+    }
+
+    /// Copies the elements of the view into a new Hive
+    pub fn to_hive(&self) -> Result<Hive, HiveError> {
+        Ok(Hive {
+            dimensions: self.dimensions.clone(),
+            elements: Elements::Memory(self.elements()?),
+            is_circular: self.hive.is_circular,
+            is_persistent: false,
+            storage: None,
+        })
+    }
+
+    // Position of an element in the data of the Hive; circular hives wrap every index
+    fn offset(&self, indices: &[i64]) -> Result<usize, HiveError> {
+        if indices.len() != self.dimensions.len() {
+            return Err(HiveError::IndexCount { expected: self.dimensions.len(), found: indices.len() });
+        }
+
+        let mut offset = self.offset;
+        for (axis, (&index, &size)) in indices.iter().zip(&self.dimensions).enumerate() {
+            offset += position(index, axis, size, self.hive.is_circular)? * self.strides[axis];
+        }
+        Ok(offset)
+    }
+}
+
+// An existing file must have the dimensions the Hive is declared with
+fn check_dimensions(file: &HiveFile, dimensions: &[usize]) -> Result<(), HiveError> {
+    if file.dimensions() != dimensions {
+        return Err(HiveError::DimensionMismatch {
+            path: storage::display(file.path()),
+            expected: dimensions.to_vec(),
+            found: file.dimensions().to_vec(),
+        });
+    }
+    Ok(())
+}
+
+// Whether the permissions of an existing file forbid writing it
+fn is_read_only(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().readonly())
+}
+
+// Position along one dimension; circular hives wrap the index around the dimension
+fn position(index: i64, axis: usize, size: usize, is_circular: bool) -> Result<usize, HiveError> {
+    if is_circular && size > 0 {
+        Ok(index.rem_euclid(size as i64) as usize)
+    } else if (0..size as i64).contains(&index) {
+        Ok(index as usize)
+    } else {
+        Err(HiveError::OutOfBounds { index, axis, size })
+    }
+}
+
+// Row-major strides: the last dimension is contiguous
+fn strides(dimensions: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; dimensions.len()];
+    for axis in (0..dimensions.len().saturating_sub(1)).rev() {
+        strides[axis] = strides[axis + 1] * dimensions[axis + 1];
+    }
+    strides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // [[1, 2, 3],
+    //  [4, 5, 6]]
+    fn matrix() -> Hive {
+        Hive::from_data(vec![2, 3], vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], false, false).unwrap()
+    }
+
+    #[test]
+    fn get_and_set_are_row_major() {
+        let mut hive = matrix();
+        assert_eq!(hive.get(&[0, 2]), Ok(3.0));
+        assert_eq!(hive.get(&[1, 0]), Ok(4.0));
+
+        hive.set(&[1, 1], 50.0).unwrap();
+        assert_eq!(*hive.data().unwrap(), [1.0, 2.0, 3.0, 4.0, 50.0, 6.0]);
+    }
+
+    #[test]
+    fn indexing_checks_bounds_and_index_count() {
+        let mut hive = matrix();
+        assert_eq!(hive.get(&[2, 0]), Err(HiveError::OutOfBounds { index: 2, axis: 0, size: 2 }));
+        assert_eq!(hive.get(&[0, -1]), Err(HiveError::OutOfBounds { index: -1, axis: 1, size: 3 }));
+        assert_eq!(hive.set(&[0], 1.0), Err(HiveError::IndexCount { expected: 2, found: 1 }));
+    }
+
+    #[test]
+    fn circular_hives_wrap_indices() {
+        let hive = Hive::from_data(vec![2, 3], matrix().data().unwrap().to_vec(), true, false).unwrap();
+        assert_eq!(hive.get(&[2, 3]), Ok(1.0));
+        assert_eq!(hive.get(&[-1, -1]), Ok(6.0));
+        assert_eq!(hive.get(&[3, 7]), Ok(5.0));
+    }
+
+    #[test]
+    fn from_data_checks_the_size() {
+        assert_eq!(
+            Hive::from_data(vec![2, 2], vec![1.0; 3], false, false).unwrap_err(),
+            HiveError::SizeMismatch { size: 3, dimensions: vec![2, 2] }
+        );
+    }
+
+    #[test]
+    fn slices_are_views_along_any_axis() {
+        let hive = matrix();
+
+        let row = hive.slice(0, 1..2).unwrap();
+        assert_eq!(row.dimensions(), &[1, 3]);
+        assert_eq!(row.elements(), Ok(vec![4.0, 5.0, 6.0]));
+
+        let columns = hive.slice(1, 1..3).unwrap();
+        assert_eq!(columns.dimensions(), &[2, 2]);
+        assert_eq!(columns.get(&[1, 0]), Ok(5.0));
+        assert_eq!(columns.elements(), Ok(vec![2.0, 3.0, 5.0, 6.0]));
+
+        let corner = columns.slice(0, 0..1).unwrap();
+        assert_eq!(*corner.to_hive().unwrap().data().unwrap(), [2.0, 3.0]);
+        assert_eq!(corner.get(&[1, 0]), Err(HiveError::OutOfBounds { index: 1, axis: 0, size: 1 }));
+    }
+
+    #[test]
+    fn slices_check_axis_and_range() {
+        let hive = matrix();
+        assert_eq!(hive.slice(2, 0..1).unwrap_err(), HiveError::InvalidAxis { axis: 2, count: 2 });
+        assert_eq!(
+            hive.slice(1, 2..4).unwrap_err(),
+            HiveError::SliceOutOfBounds { start: 2, end: 4, axis: 1, size: 3 }
+        );
+        assert_eq!(hive.slice(1, 1..1).unwrap().elements(), Ok(Vec::<f64>::new()));
+    }
+
+    #[test]
+    fn element_wise_arithmetic() {
+        let a = matrix();
+        let b = Hive::from_data(vec![2, 3], vec![6.0, 5.0, 4.0, 3.0, 2.0, 1.0], false, false).unwrap();
+
+        assert_eq!(*a.add(&b).unwrap().data().unwrap(), [7.0; 6]);
+        assert_eq!(*a.subtract(&b).unwrap().data().unwrap(), [-5.0, -3.0, -1.0, 1.0, 3.0, 5.0]);
+        assert_eq!(*a.multiply(&b).unwrap().data().unwrap(), [6.0, 10.0, 12.0, 12.0, 10.0, 6.0]);
+        assert_eq!(*a.divide(&b).unwrap().data().unwrap(), [1.0 / 6.0, 0.4, 0.75, 4.0 / 3.0, 2.5, 6.0]);
+    }
+
+    #[test]
+    fn arithmetic_checks_shapes() {
+        let a = matrix();
+        let b = a.reshape(vec![3, 2]).unwrap();
+        assert_eq!(a.add(&b).unwrap_err(), HiveError::ShapeMismatch { left: vec![2, 3], right: vec![3, 2] });
+    }
+
+    #[test]
+    fn reductions_over_all_elements() {
+        let hive = matrix();
+        assert_eq!(hive.sum(), Ok(21.0));
+        assert_eq!(hive.min(), Ok(Some(1.0)));
+        assert_eq!(hive.max(), Ok(Some(6.0)));
+        assert_eq!(hive.mean(), Ok(Some(3.5)));
+
+        let empty = Hive::new(vec![0, 3], false, false);
+        assert_eq!(empty.sum(), Ok(0.0));
+        assert_eq!(empty.min(), Ok(None));
+        assert_eq!(empty.mean(), Ok(None));
+    }
+
+    #[test]
+    fn reductions_per_axis() {
+        let hive = matrix();
+
+        let columns = hive.sum_axis(0).unwrap();
+        assert_eq!(columns.dimensions(), &[3]);
+        assert_eq!(*columns.data().unwrap(), [5.0, 7.0, 9.0]);
+
+        let rows = hive.sum_axis(1).unwrap();
+        assert_eq!(rows.dimensions(), &[2]);
+        assert_eq!(*rows.data().unwrap(), [6.0, 15.0]);
+
+        assert_eq!(*hive.min_axis(0).unwrap().data().unwrap(), [1.0, 2.0, 3.0]);
+        assert_eq!(*hive.max_axis(1).unwrap().data().unwrap(), [3.0, 6.0]);
+        assert_eq!(*hive.mean_axis(0).unwrap().data().unwrap(), [2.5, 3.5, 4.5]);
+        assert_eq!(*hive.mean_axis(1).unwrap().data().unwrap(), [2.0, 5.0]);
+
+        let total = rows.sum_axis(0).unwrap();
+        assert_eq!(total.dimensions(), &[] as &[usize]);
+        assert_eq!(total.get(&[]), Ok(21.0));
+    }
+
+    #[test]
+    fn reductions_on_a_middle_axis() {
+        // [[[0, 1], [2, 3], [4, 5]], [[6, 7], [8, 9], [10, 11]]]
+        let data = (0..12).map(f64::from).collect();
+        let hive = Hive::from_data(vec![2, 3, 2], data, false, false).unwrap();
+
+        let reduced = hive.sum_axis(1).unwrap();
+        assert_eq!(reduced.dimensions(), &[2, 2]);
+        assert_eq!(*reduced.data().unwrap(), [6.0, 9.0, 24.0, 27.0]);
+    }
+
+    #[test]
+    fn reductions_check_the_axis() {
+        let hive = matrix();
+        assert_eq!(hive.max_axis(2).unwrap_err(), HiveError::InvalidAxis { axis: 2, count: 2 });
+
+        let empty = Hive::new(vec![2, 0], false, false);
+        assert_eq!(empty.min_axis(1).unwrap_err(), HiveError::EmptyAxis { axis: 1 });
+        assert!(empty.sum_axis(0).unwrap().data().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reshape_keeps_row_major_order() {
+        let reshaped = matrix().reshape(vec![3, 2]).unwrap();
+        assert_eq!(reshaped.dimensions(), &[3, 2]);
+        assert_eq!(reshaped.get(&[1, 0]), Ok(3.0));
+        assert_eq!(reshaped.get(&[2, 1]), Ok(6.0));
+
+        assert_eq!(
+            matrix().reshape(vec![4, 2]).unwrap_err(),
+            HiveError::SizeMismatch { size: 6, dimensions: vec![4, 2] }
+        );
+    }
+
+    #[test]
+    fn transpose_swaps_indices() {
+        let transposed = matrix().transpose().unwrap();
+        assert_eq!(transposed.dimensions(), &[3, 2]);
+        assert_eq!(*transposed.data().unwrap(), [1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+        assert!(transposed.transpose().unwrap().equals(&matrix()).unwrap());
+
+        // In three dimensions element [i, j, k] moves to [k, j, i]
+        let data = (0..24).map(f64::from).collect();
+        let cube = Hive::from_data(vec![2, 3, 4], data, false, false).unwrap();
+        let transposed = cube.transpose().unwrap();
+        assert_eq!(transposed.dimensions(), &[4, 3, 2]);
+        assert_eq!(transposed.get(&[3, 1, 0]), cube.get(&[0, 1, 3]));
+        assert_eq!(transposed.get(&[2, 2, 1]), Ok(22.0));
+    }
+
+    #[test]
+    fn derived_hives_are_not_persistent() {
+        let hive = Hive::new(vec![2, 2], true, true);
+        let sum = hive.add(&hive).unwrap();
+        assert!(sum.is_circular());
+        assert!(!sum.is_persistent());
+        assert!(!hive.transpose().unwrap().is_persistent());
+    }
+}
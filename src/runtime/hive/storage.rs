@@ -0,0 +1,464 @@
+// On-disk format of persistent hives
+//
+// A hive file is a header followed by the elements in row-major order, all little-endian:
+//
+//   magic       4 bytes   `NHIV`
+//   version     u16       FORMAT_VERSION
+//   flags       u16       bit 0: circular
+//   rank        u32       number of dimensions
+//   dimensions  u64 each
+//   checksum    32 bytes  SHA-256 of the version, flags, rank, dimensions and elements
+//   elements    f64 each
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use super::HiveError;
+
+/// First bytes of every hive file
+pub const MAGIC: &[u8; 4] = b"NHIV";
+
+/// Version of the format written by this compiler
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Extension of the files backing persistent hives
+pub const EXTENSION: &str = "hive";
+
+/// Number of elements read or written at a time when streaming a file
+pub const CHUNK_ELEMENTS: usize = 64 * 1024;
+
+const CIRCULAR_FLAG: u16 = 1;
+const CHECKSUM_SIZE: usize = 32;
+const ELEMENT_SIZE: usize = std::mem::size_of::<f64>();
+
+/// A hive file opened for chunked access, without loading its elements in memory
+///
+/// Writes go straight to the file; the checksum is brought up to date by `sync`, which
+/// also runs when the file is dropped. A file opened with `open_read_only` rejects writes.
+#[derive(Debug)]
+pub struct HiveFile {
+    file: File,
+    path: PathBuf,
+    dimensions: Vec<usize>,
+    is_circular: bool,
+    writable: bool,
+    // Byte position of the checksum; the elements follow it
+    checksum_start: u64,
+    // Elements written since the checksum was last computed
+    modified: bool,
+}
+
+impl HiveFile {
+    /// Creates a zero-filled hive file, replacing any existing file at `path`
+    pub fn create(path: &Path, dimensions: Vec<usize>, is_circular: bool) -> Result<Self, HiveError> {
+        let io = |e: std::io::Error| io_error(path, e);
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path).map_err(io)?;
+
+        let mut header = MAGIC.to_vec();
+        header.extend(describe(&dimensions, is_circular));
+        let checksum_start = header.len() as u64;
+        header.extend([0; CHECKSUM_SIZE]);
+        file.write_all(&header).map_err(io)?;
+
+        let size: usize = dimensions.iter().product();
+        file.set_len(checksum_start + (CHECKSUM_SIZE + size * ELEMENT_SIZE) as u64).map_err(io)?;
+
+        Ok(HiveFile {
+            file,
+            path: path.to_path_buf(),
+            dimensions,
+            is_circular,
+            writable: true,
+            checksum_start,
+            modified: true,
+        })
+    }
+
+    /// Opens an existing hive file for reading and writing, checking its header, its length
+    /// and its checksum
+    pub fn open(path: &Path) -> Result<Self, HiveError> {
+        Self::open_with(path, true)
+    }
+
+    /// Opens an existing hive file like `open`, without asking for write access
+    pub fn open_read_only(path: &Path) -> Result<Self, HiveError> {
+        Self::open_with(path, false)
+    }
+
+    fn open_with(path: &Path, writable: bool) -> Result<Self, HiveError> {
+        let io = |e: std::io::Error| io_error(path, e);
+        let invalid = |reason: &str| HiveError::InvalidFile { path: display(path), reason: reason.to_string() };
+        let mut file = OpenOptions::new().read(true).write(writable).open(path).map_err(io)?;
+
+        let mut fixed = [0; 12];
+        file.read_exact(&mut fixed).map_err(|_| invalid("the header is truncated"))?;
+        if &fixed[..4] != MAGIC {
+            return Err(invalid("the file does not start with the hive signature"));
+        }
+        let version = u16::from_le_bytes([fixed[4], fixed[5]]);
+        if version != FORMAT_VERSION {
+            return Err(HiveError::UnsupportedVersion { path: display(path), version });
+        }
+        let flags = u16::from_le_bytes([fixed[6], fixed[7]]);
+        let rank = u32::from_le_bytes([fixed[8], fixed[9], fixed[10], fixed[11]]) as usize;
+
+        let length = file.metadata().map_err(io)?.len();
+        let checksum_start = 12 + 8 * rank as u64;
+        if checksum_start + CHECKSUM_SIZE as u64 > length {
+            return Err(invalid("the header is truncated"));
+        }
+        let mut bytes = vec![0; 8 * rank];
+        file.read_exact(&mut bytes).map_err(io)?;
+        let dimensions: Vec<usize> = bytes.chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().expect("chunks of 8 bytes")) as usize)
+            .collect();
+
+        let expected = dimensions.iter()
+            .try_fold(1usize, |size, &dimension| size.checked_mul(dimension))
+            .and_then(|size| size.checked_mul(ELEMENT_SIZE))
+            .map(|bytes| checksum_start + (CHECKSUM_SIZE + bytes) as u64);
+        if expected != Some(length) {
+            return Err(invalid("the length does not match the dimensions in the header"));
+        }
+
+        let mut hive_file = HiveFile {
+            file,
+            path: path.to_path_buf(),
+            dimensions,
+            is_circular: flags & CIRCULAR_FLAG != 0,
+            writable,
+            checksum_start,
+            modified: false,
+        };
+        let mut stored = [0; CHECKSUM_SIZE];
+        hive_file.file.seek(SeekFrom::Start(checksum_start)).map_err(io)?;
+        hive_file.file.read_exact(&mut stored).map_err(io)?;
+        if hive_file.checksum()? != stored {
+            return Err(HiveError::ChecksumMismatch { path: display(path) });
+        }
+        Ok(hive_file)
+    }
+
+    /// Gets the path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Gets the size of each dimension
+    pub fn dimensions(&self) -> &[usize] {
+        &self.dimensions
+    }
+
+    /// Gets the total number of elements in the file
+    pub fn size(&self) -> usize {
+        self.dimensions.iter().product()
+    }
+
+    /// Checks whether indices wrap around the dimensions
+    pub fn is_circular(&self) -> bool {
+        self.is_circular
+    }
+
+    /// Checks whether the file was opened for writing
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Gets the element at the given indices, one per dimension
+    pub fn get(&mut self, indices: &[i64]) -> Result<f64, HiveError> {
+        let mut value = [0.0];
+        self.read(self.offset(indices)?, &mut value)?;
+        Ok(value[0])
+    }
+
+    /// Sets the element at the given indices, one per dimension
+    pub fn set(&mut self, indices: &[i64], value: f64) -> Result<(), HiveError> {
+        self.write(self.offset(indices)?, &[value])
+    }
+
+    /// Reads consecutive elements, in row-major order, starting from element `start`
+    pub fn read(&mut self, start: usize, buffer: &mut [f64]) -> Result<(), HiveError> {
+        self.seek_element(start, buffer.len())?;
+        let mut bytes = vec![0; buffer.len().min(CHUNK_ELEMENTS) * ELEMENT_SIZE];
+        for chunk in buffer.chunks_mut(CHUNK_ELEMENTS) {
+            let bytes = &mut bytes[..chunk.len() * ELEMENT_SIZE];
+            self.file.read_exact(bytes).map_err(|e| io_error(&self.path, e))?;
+            for (value, element) in chunk.iter_mut().zip(bytes.chunks_exact(ELEMENT_SIZE)) {
+                *value = f64::from_le_bytes(element.try_into().expect("chunks of 8 bytes"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes consecutive elements, in row-major order, starting from element `start`
+    pub fn write(&mut self, start: usize, values: &[f64]) -> Result<(), HiveError> {
+        if !self.writable {
+            return Err(HiveError::ReadOnly { path: display(&self.path) });
+        }
+        self.seek_element(start, values.len())?;
+        self.modified = true;
+        for chunk in values.chunks(CHUNK_ELEMENTS) {
+            let bytes: Vec<u8> = chunk.iter().flat_map(|value| value.to_le_bytes()).collect();
+            self.file.write_all(&bytes).map_err(|e| io_error(&self.path, e))?;
+        }
+        Ok(())
+    }
+
+    /// Updates the checksum after the last writes and flushes the file to disk
+    pub fn sync(&mut self) -> Result<(), HiveError> {
+        if !self.modified {
+            return Ok(());
+        }
+
+        let checksum = self.checksum()?;
+        let io = |e: std::io::Error| io_error(&self.path, e);
+        self.file.seek(SeekFrom::Start(self.checksum_start)).map_err(io)?;
+        self.file.write_all(&checksum).map_err(io)?;
+        self.file.sync_data().map_err(io)?;
+        self.modified = false;
+        Ok(())
+    }
+
+    // Row-major position of an element
+    fn offset(&self, indices: &[i64]) -> Result<usize, HiveError> {
+        if indices.len() != self.dimensions.len() {
+            return Err(HiveError::IndexCount { expected: self.dimensions.len(), found: indices.len() });
+        }
+
+        let mut offset = 0;
+        for (axis, (&index, &size)) in indices.iter().zip(&self.dimensions).enumerate() {
+            offset = offset * size + super::position(index, axis, size, self.is_circular)?;
+        }
+        Ok(offset)
+    }
+
+    // Moves to element `start`, after checking that `count` elements follow it
+    fn seek_element(&mut self, start: usize, count: usize) -> Result<(), HiveError> {
+        let size = self.size();
+        if start.checked_add(count).is_none_or(|end| end > size) {
+            return Err(HiveError::ElementRange { start, end: start.saturating_add(count), size });
+        }
+
+        let position = self.checksum_start + (CHECKSUM_SIZE + start * ELEMENT_SIZE) as u64;
+        self.file.seek(SeekFrom::Start(position)).map_err(|e| io_error(&self.path, e))?;
+        Ok(())
+    }
+
+    // Hash of the header fields and of the elements, read back from the file in chunks
+    fn checksum(&mut self) -> Result<[u8; CHECKSUM_SIZE], HiveError> {
+        let mut hasher = Sha256::new();
+        hasher.update(describe(&self.dimensions, self.is_circular));
+
+        let io = |e: std::io::Error| io_error(&self.path, e);
+        self.file.seek(SeekFrom::Start(self.checksum_start + CHECKSUM_SIZE as u64)).map_err(io)?;
+        let mut remaining = self.size() * ELEMENT_SIZE;
+        let mut buffer = vec![0; remaining.min(CHUNK_ELEMENTS * ELEMENT_SIZE)];
+        while remaining > 0 {
+            let chunk = &mut buffer[..remaining.min(CHUNK_ELEMENTS * ELEMENT_SIZE)];
+            self.file.read_exact(chunk).map_err(io)?;
+            hasher.update(&*chunk);
+            remaining -= chunk.len();
+        }
+        Ok(hasher.finalize().into())
+    }
+}
+
+impl Drop for HiveFile {
+    fn drop(&mut self) {
+        // Drop cannot report errors: call `sync` explicitly to see them
+        let _ = self.sync();
+    }
+}
+
+// Header fields between the magic and the checksum
+fn describe(dimensions: &[usize], is_circular: bool) -> Vec<u8> {
+    let flags = if is_circular { CIRCULAR_FLAG } else { 0 };
+    let mut bytes = Vec::with_capacity(8 + 8 * dimensions.len());
+    bytes.extend(FORMAT_VERSION.to_le_bytes());
+    bytes.extend(flags.to_le_bytes());
+    bytes.extend((dimensions.len() as u32).to_le_bytes());
+    for &dimension in dimensions {
+        bytes.extend((dimension as u64).to_le_bytes());
+    }
+    bytes
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> HiveError {
+    HiveError::Io { path: display(path), message: error.to_string() }
+}
+
+pub(super) fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::hive::Hive;
+
+    // File in the temporary directory, removed when the test ends
+    struct Scratch(PathBuf);
+
+    impl std::ops::Deref for Scratch {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn scratch(name: &str) -> Scratch {
+        let path = std::env::temp_dir().join(format!("nervs-{}-{}.{}", std::process::id(), name, EXTENSION));
+        let _ = std::fs::remove_file(&path);
+        Scratch(path)
+    }
+
+    #[test]
+    fn elements_survive_reopening() {
+        let path = scratch("reopen");
+        {
+            let mut file = HiveFile::create(&path, vec![2, 3], true).unwrap();
+            file.write(1, &[1.5, 2.5]).unwrap();
+            file.set(&[1, 2], -4.0).unwrap();
+        }
+
+        let mut file = HiveFile::open(&path).unwrap();
+        assert_eq!(file.dimensions(), &[2, 3]);
+        assert!(file.is_circular());
+        let mut data = [0.0; 6];
+        file.read(0, &mut data).unwrap();
+        assert_eq!(data, [0.0, 1.5, 2.5, 0.0, 0.0, -4.0]);
+        assert_eq!(file.get(&[-1, -1]), Ok(-4.0));
+        assert_eq!(file.read(5, &mut [0.0; 2]), Err(HiveError::ElementRange { start: 5, end: 7, size: 6 }));
+    }
+
+    #[test]
+    fn chunked_access_crosses_chunk_boundaries() {
+        let path = scratch("chunks");
+        let size = CHUNK_ELEMENTS * 2 + 3;
+        let values: Vec<f64> = (0..size).map(|i| i as f64).collect();
+        {
+            let mut file = HiveFile::create(&path, vec![size], false).unwrap();
+            file.write(0, &values).unwrap();
+            file.sync().unwrap();
+        }
+
+        let mut file = HiveFile::open(&path).unwrap();
+        let mut data = vec![0.0; size - 10];
+        file.read(10, &mut data).unwrap();
+        assert_eq!(data, values[10..]);
+    }
+
+    #[test]
+    fn corrupted_files_are_rejected() {
+        let path = scratch("corrupted");
+        HiveFile::create(&path, vec![4], false).unwrap().write(0, &[1.0, 2.0, 3.0, 4.0]).unwrap();
+
+        let mut bytes = std::fs::read(&*path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&*path, &bytes).unwrap();
+        assert_eq!(HiveFile::open(&path).unwrap_err(), HiveError::ChecksumMismatch { path: display(&path) });
+
+        bytes[4] = 9;
+        std::fs::write(&*path, &bytes).unwrap();
+        assert_eq!(HiveFile::open(&path).unwrap_err(), HiveError::UnsupportedVersion { path: display(&path), version: 9 });
+
+        bytes.truncate(bytes.len() - 8);
+        bytes[4] = FORMAT_VERSION as u8;
+        std::fs::write(&*path, &bytes).unwrap();
+        assert!(matches!(HiveFile::open(&path), Err(HiveError::InvalidFile { .. })));
+    }
+
+    #[test]
+    fn persistent_hives_flush_on_drop() {
+        let path = scratch("persistent");
+        {
+            let mut hive = Hive::open(&path, vec![2, 2], false).unwrap();
+            hive.set(&[0, 1], 7.0).unwrap();
+            hive.set(&[1, 0], 3.0).unwrap();
+        }
+
+        let hive = Hive::open(&path, vec![2, 2], false).unwrap();
+        assert!(hive.is_persistent());
+        assert_eq!(*hive.data().unwrap(), [0.0, 7.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn clones_are_not_backed_by_the_file() {
+        let path = scratch("clone");
+        let mut hive = Hive::open(&path, vec![3], false).unwrap();
+        hive.set(&[0], 1.0).unwrap();
+        hive.flush().unwrap();
+
+        let mut copy = hive.try_clone().unwrap();
+        copy.set(&[0], 2.0).unwrap();
+        drop(copy);
+        drop(hive);
+        assert_eq!(*Hive::open(&path, vec![3], false).unwrap().data().unwrap(), [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn chunked_hives_write_in_place() {
+        let path = scratch("chunked");
+        let size = CHUNK_ELEMENTS + 2;
+        {
+            let mut hive = Hive::open_chunked(&path, vec![2, size], false).unwrap();
+            assert!(hive.is_chunked());
+            hive.set(&[0, (size - 1) as i64], 1.5).unwrap();
+            hive.set(&[1, 0], 2.5).unwrap();
+            hive.set(&[0, 0], 3.5).unwrap();
+            assert_eq!(hive.get(&[0, (size - 1) as i64]), Ok(1.5));
+        }
+
+        let mut file = HiveFile::open(&path).unwrap();
+        assert_eq!(file.get(&[0, (size - 1) as i64]), Ok(1.5));
+        assert_eq!(file.get(&[1, 0]), Ok(2.5));
+        drop(file);
+
+        let hive = Hive::open_chunked(&path, vec![2, size], false).unwrap();
+        assert_eq!(hive.sum(), Ok(7.5));
+        assert_eq!(hive.get(&[0, 0]), Ok(3.5));
+        assert!(hive.try_clone().unwrap().equals(&Hive::open(&path, vec![2, size], false).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn read_only_files_can_be_opened() {
+        let path = scratch("read-only");
+        HiveFile::create(&path, vec![3], false).unwrap().write(0, &[1.0, 2.0, 3.0]).unwrap();
+        let mut permissions = std::fs::metadata(&*path).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&*path, permissions).unwrap();
+
+        let mut file = HiveFile::open_read_only(&path).unwrap();
+        assert_eq!(file.get(&[2]), Ok(3.0));
+        assert_eq!(file.set(&[2], 0.0), Err(HiveError::ReadOnly { path: display(&path) }));
+
+        // In-memory and chunked hives refuse the same changes
+        let copy = Hive::from_data(vec![3], vec![0.0; 3], false, false).unwrap();
+        for mut hive in [Hive::open(&path, vec![3], false).unwrap(), Hive::open_chunked(&path, vec![3], false).unwrap()] {
+            assert_eq!(*hive.data().unwrap(), [1.0, 2.0, 3.0]);
+            assert_eq!(hive.set(&[1], 0.0), Err(HiveError::ReadOnly { path: display(&path) }));
+            assert_eq!(hive.copy_from(&copy), Err(HiveError::ReadOnly { path: display(&path) }));
+            hive.flush().unwrap();
+        }
+        assert_eq!(*Hive::open(&path, vec![3], false).unwrap().data().unwrap(), [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn reopening_with_other_dimensions_fails() {
+        let path = scratch("dimensions");
+        drop(Hive::open(&path, vec![2, 3], false).unwrap());
+        assert_eq!(
+            Hive::open(&path, vec![3, 2], false).unwrap_err(),
+            HiveError::DimensionMismatch { path: display(&path), expected: vec![3, 2], found: vec![2, 3] }
+        );
+    }
+}
@@ -0,0 +1,999 @@
+// Interprete ad albero per i ritual: valuta direttamente statement ed espressioni dell'AST
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::ast::nodes::{BinaryOperator, Being, Essence, Expression, FieldInit, Literal, Ritual, Statement, Type, UnaryOperator};
+use crate::ast::printer;
+use super::hive::Hive;
+use super::{NervsRuntime, RuntimeError, RuntimeValue};
+
+/// Profondità massima delle chiamate annidate
+const MAX_CALL_DEPTH: usize = 512;
+
+/// Stack minimo da avere a disposizione prima di eseguire un ritual; se ne resta meno,
+/// l'esecuzione prosegue su un nuovo segmento di `STACK_SEGMENT` byte
+const STACK_RED_ZONE: usize = 256 * 1024;
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+/// Esito dell'esecuzione di uno statement
+enum Flow {
+    Normal,
+    Return(RuntimeValue),
+}
+
+/// Record di attivazione di un ritual
+struct Frame {
+    realm: String,
+    being: String,
+    /// Scope locali, dal più esterno (parametri) al più interno
+    scopes: Vec<HashMap<String, RuntimeValue>>,
+}
+
+impl Frame {
+    fn lookup(&self, name: &str) -> Option<&RuntimeValue> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn lookup_mut(&mut self, name: &str) -> Option<&mut RuntimeValue> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name))
+    }
+}
+
+impl NervsRuntime {
+    // Assegna i valori iniziali alle variabili di un being, poi esegue il suo ritual `init`
+    pub(super) fn initialize_being(&mut self, realm: &str, being: &Being) -> Result<(), RuntimeError> {
+        for var in &being.variables {
+            let Some(init) = &var.initializer else { continue };
+            // Le variabili di memoria ripristinate conservano il valore salvato
+            if self.realms[realm].beings[&being.name].restored.contains(&var.variable.name) {
+                continue;
+            }
+            // Gli inizializzatori leggono solo variabili del being già inizializzate
+            let mut frame = Frame {
+                realm: realm.to_string(),
+                being: being.name.clone(),
+                scopes: vec![HashMap::new()],
+            };
+            let value = self.evaluate(&mut frame, init, 0)?;
+            let value = coerce(value, &var.variable.var_type).ok_or_else(|| RuntimeError::TypeError(
+                format!("cannot initialise '{}' of type {}", var.variable.name, printer::type_name(&var.variable.var_type))
+            ))?;
+            self.assign(&mut frame, &var.variable.name, value)?;
+        }
+
+        if being.init_ritual().is_some() {
+            self.call_ritual(realm, &being.name, Being::INIT_RITUAL, Vec::new(), 0)?;
+        }
+        Ok(())
+    }
+
+    // Chiama un ritual con argomenti già valutati
+    pub(super) fn call_ritual(
+        &mut self,
+        realm: &str,
+        being: &str,
+        ritual_name: &str,
+        arguments: Vec<RuntimeValue>,
+        depth: usize,
+    ) -> Result<RuntimeValue, RuntimeError> {
+        if depth >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::StackOverflow(ritual_name.to_string()));
+        }
+        // Senza ottimizzazioni ogni chiamata occupa decine di KB di stack: lo stack del thread
+        // chiamante si esaurirebbe prima di raggiungere il limite di profondità
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+            self.run_ritual(realm, being, ritual_name, arguments, depth)
+        })
+    }
+
+    fn run_ritual(
+        &mut self,
+        realm: &str,
+        being: &str,
+        ritual_name: &str,
+        arguments: Vec<RuntimeValue>,
+        depth: usize,
+    ) -> Result<RuntimeValue, RuntimeError> {
+
+        // Le estensioni non hanno un corpo: le esegue la funzione nativa collegata dall'host
+        if let Some(extension) = self.lookup_extension(realm, being, ritual_name) {
+            return self.call_extension(realm, being, &extension, arguments);
+        }
+
+        let ritual = self.lookup_ritual(realm, being, ritual_name)?;
+
+        if arguments.len() != ritual.parameters.len() {
+            return Err(RuntimeError::ArgumentCount {
+                ritual: ritual_name.to_string(),
+                expected: ritual.parameters.len(),
+                found: arguments.len(),
+            });
+        }
+
+        let mut parameters = HashMap::new();
+        for (param, value) in ritual.parameters.iter().zip(arguments) {
+            let value = coerce(value, &param.var_type).ok_or_else(|| RuntimeError::TypeError(
+                format!("argument '{}' of ritual '{}' expects {}", param.name, ritual_name, printer::type_name(&param.var_type))
+            ))?;
+            parameters.insert(param.name.clone(), value);
+        }
+
+        let mut frame = Frame {
+            realm: realm.to_string(),
+            being: being.to_string(),
+            scopes: vec![parameters],
+        };
+
+        match self.execute_block(&mut frame, &ritual.body, depth)? {
+            Flow::Return(value) if ritual.return_type == Type::Void => Ok(value),
+            Flow::Return(value) => coerce(value, &ritual.return_type).ok_or_else(|| RuntimeError::TypeError(
+                format!("ritual '{}' returns {}", ritual_name, printer::type_name(&ritual.return_type))
+            )),
+            Flow::Normal if ritual.return_type == Type::Void => Ok(RuntimeValue::Void),
+            Flow::Normal => Err(RuntimeError::MissingReturn(ritual_name.to_string())),
+        }
+    }
+
+    pub(super) fn lookup_ritual(&self, realm: &str, being: &str, ritual: &str) -> Result<Rc<Ritual>, RuntimeError> {
+        let runtime_realm = self.realms.get(realm)
+            .ok_or_else(|| RuntimeError::RealmNotFound(realm.to_string()))?;
+        let runtime_being = runtime_realm.beings.get(being)
+            .ok_or_else(|| RuntimeError::BeingNotFound(being.to_string(), realm.to_string()))?;
+        runtime_being.rituals.get(ritual)
+            .cloned()
+            .ok_or_else(|| RuntimeError::RitualNotFound(ritual.to_string(), being.to_string()))
+    }
+
+    // Esegue un blocco in un nuovo scope locale
+    fn execute_block(&mut self, frame: &mut Frame, statements: &[Statement], depth: usize) -> Result<Flow, RuntimeError> {
+        frame.scopes.push(HashMap::new());
+        let mut flow = Ok(Flow::Normal);
+        for stmt in statements {
+            flow = self.execute_statement(frame, stmt, depth);
+            if !matches!(flow, Ok(Flow::Normal)) {
+                break;
+            }
+        }
+        frame.scopes.pop();
+        flow
+    }
+
+    fn execute_statement(&mut self, frame: &mut Frame, stmt: &Statement, depth: usize) -> Result<Flow, RuntimeError> {
+        match stmt {
+            Statement::VariableDeclaration { variable, initializer, .. } => {
+                let value = match initializer {
+                    Some(init) => {
+                        let value = self.evaluate(frame, init, depth)?;
+                        coerce(value, &variable.var_type).ok_or_else(|| RuntimeError::TypeError(
+                            format!("cannot initialise '{}' of type {}", variable.name, printer::type_name(&variable.var_type))
+                        ))?
+                    }
+                    None => initial_value(&variable.var_type),
+                };
+                frame.scopes.last_mut()
+                    .expect("a block always has a scope")
+                    .insert(variable.name.clone(), value);
+                Ok(Flow::Normal)
+            }
+            Statement::Assignment { name, value, .. } => {
+                let value = self.evaluate(frame, value, depth)?;
+                self.assign(frame, name, value)?;
+                Ok(Flow::Normal)
+            }
+            Statement::FieldAssignment { object, field, value, .. } => {
+                let value = self.evaluate(frame, value, depth)?;
+                self.update_place(frame, object, |target| {
+                    let slot = field_slot(target, &[field])?;
+                    *slot = assign_value(slot, value, field)?;
+                    Ok(())
+                })?;
+                Ok(Flow::Normal)
+            }
+            Statement::IndexAssignment { object, indices, value, .. } => {
+                let indices = self.evaluate_indices(frame, indices, depth)?;
+                let value = element_value(self.evaluate(frame, value, depth)?)?;
+                self.update_place(frame, object, |target| match target {
+                    RuntimeValue::Hive { hive, .. } => Ok(hive.set(&indices, value)?),
+                    other => Err(RuntimeError::TypeError(format!("{} cannot be indexed", other.type_name()))),
+                })?;
+                Ok(Flow::Normal)
+            }
+            Statement::RitualCall { name, arguments, .. } => {
+                self.evaluate_call(frame, name, arguments, depth)?;
+                Ok(Flow::Normal)
+            }
+            Statement::Conditional { condition, true_branch, false_branch, .. } => {
+                if self.evaluate_condition(frame, condition, depth)? {
+                    self.execute_block(frame, true_branch, depth)
+                } else if let Some(false_branch) = false_branch {
+                    self.execute_block(frame, false_branch, depth)
+                } else {
+                    Ok(Flow::Normal)
+                }
+            }
+            Statement::Cycle { condition, body, .. } => {
+                loop {
+                    if let Some(condition) = condition {
+                        if !self.evaluate_condition(frame, condition, depth)? {
+                            return Ok(Flow::Normal);
+                        }
+                    }
+                    if let Flow::Return(value) = self.execute_block(frame, body, depth)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            Statement::Return(value, _) => {
+                let value = match value {
+                    Some(expr) => self.evaluate(frame, expr, depth)?,
+                    None => RuntimeValue::Void,
+                };
+                Ok(Flow::Return(value))
+            }
+            Statement::Expression(expr, _) => {
+                self.evaluate(frame, expr, depth)?;
+                Ok(Flow::Normal)
+            }
+            Statement::Error(_) => Err(RuntimeError::InvalidProgram),
+        }
+    }
+
+    // Assegna a una variabile locale o, in mancanza, a una variabile del being
+    fn assign(&mut self, frame: &mut Frame, name: &str, value: RuntimeValue) -> Result<(), RuntimeError> {
+        if let Some(slot) = frame.lookup_mut(name) {
+            return store(slot, value, name);
+        }
+
+        let slot = self.realms.get_mut(&frame.realm)
+            .and_then(|realm| realm.beings.get_mut(&frame.being))
+            .and_then(|being| being.variables.get_mut(name))
+            .ok_or_else(|| RuntimeError::UndefinedVariable(name.to_string()))?;
+        store(slot, value, name)
+    }
+
+    // Modifica il valore indicato da una destinazione: una variabile, o un campo di un'essence
+    // contenuta in una variabile, anche attraverso campi annidati
+    fn update_place(
+        &mut self,
+        frame: &mut Frame,
+        place: &Expression,
+        update: impl FnOnce(&mut RuntimeValue) -> Result<(), RuntimeError>,
+    ) -> Result<(), RuntimeError> {
+        let mut path = Vec::new();
+        let mut root = place;
+        while let Expression::FieldAccess { object, field, .. } = root {
+            path.push(field.as_str());
+            root = object;
+        }
+        path.reverse();
+        let Expression::Variable(name, _) = root else {
+            return Err(RuntimeError::TypeError("invalid assignment target".to_string()));
+        };
+
+        let slot = match frame.lookup_mut(name) {
+            Some(slot) => slot,
+            None => self.realms.get_mut(&frame.realm)
+                .and_then(|realm| realm.beings.get_mut(&frame.being))
+                .and_then(|being| being.variables.get_mut(name))
+                .ok_or_else(|| RuntimeError::UndefinedVariable(name.clone()))?,
+        };
+        update(field_slot(slot, &path)?)
+    }
+
+    // Valore indicato da una destinazione come in `update_place`, senza copiarlo; `None` se
+    // l'espressione non è una variabile del ritual o del being, o un campo di un'essence contenuta
+    fn place<'a>(&'a self, frame: &'a Frame, place: &Expression) -> Option<&'a RuntimeValue> {
+        let mut path = Vec::new();
+        let mut root = place;
+        while let Expression::FieldAccess { object, field, .. } = root {
+            path.push(field.as_str());
+            root = object;
+        }
+        let Expression::Variable(name, _) = root else {
+            return None;
+        };
+
+        let mut value = frame.lookup(name).or_else(|| self.variable(&frame.realm, &frame.being, name))?;
+        for field in path.iter().rev() {
+            let RuntimeValue::Essence { fields, .. } = value else {
+                return None;
+            };
+            value = fields.iter().find(|(name, _)| name == field).map(|(_, value)| value)?;
+        }
+        Some(value)
+    }
+
+    // Valuta gli indici di una hive, che devono essere interi
+    fn evaluate_indices(&mut self, frame: &mut Frame, indices: &[Expression], depth: usize) -> Result<Vec<i64>, RuntimeError> {
+        let mut values = Vec::with_capacity(indices.len());
+        for index in indices {
+            match self.evaluate(frame, index, depth)? {
+                RuntimeValue::Integer(n) => values.push(n),
+                other => return Err(RuntimeError::TypeError(format!("hive index must be an int, found {}", other.type_name()))),
+            }
+        }
+        Ok(values)
+    }
+
+    fn evaluate_condition(&mut self, frame: &mut Frame, condition: &Expression, depth: usize) -> Result<bool, RuntimeError> {
+        match self.evaluate(frame, condition, depth)? {
+            RuntimeValue::Boolean(b) => Ok(b),
+            other => Err(RuntimeError::TypeError(format!("condition must be a boolean, found {}", other.type_name()))),
+        }
+    }
+
+    fn evaluate_call(
+        &mut self,
+        frame: &mut Frame,
+        name: &str,
+        arguments: &[Expression],
+        depth: usize,
+    ) -> Result<RuntimeValue, RuntimeError> {
+        let mut values = Vec::with_capacity(arguments.len());
+        for arg in arguments {
+            values.push(self.evaluate(frame, arg, depth)?);
+        }
+        let (realm, being) = (frame.realm.clone(), frame.being.clone());
+        self.call_ritual(&realm, &being, name, values, depth + 1)
+    }
+
+    fn evaluate(&mut self, frame: &mut Frame, expr: &Expression, depth: usize) -> Result<RuntimeValue, RuntimeError> {
+        match expr {
+            Expression::Literal(literal, _) => Ok(match literal {
+                Literal::Integer(n) => RuntimeValue::Integer(*n),
+                Literal::Float(x) => RuntimeValue::Float(*x),
+                Literal::String(s) => RuntimeValue::String(s.clone()),
+                Literal::Boolean(b) => RuntimeValue::Boolean(*b),
+            }),
+            Expression::Variable(name, _) => {
+                if let Some(value) = frame.lookup(name) {
+                    return value.try_clone();
+                }
+                if let Some(value) = self.variable(&frame.realm, &frame.being, name) {
+                    return value.try_clone();
+                }
+                // Il nome di un being del realm indica la sua istanza
+                let being_exists = self.realms.get(&frame.realm)
+                    .is_some_and(|realm| realm.beings.contains_key(name));
+                if being_exists {
+                    return Ok(RuntimeValue::Being { realm: frame.realm.clone(), being: name.clone() });
+                }
+                Err(RuntimeError::UndefinedVariable(name.clone()))
+            }
+            Expression::BeingReference { realm, being, .. } => {
+                let being_exists = self.realms.get(realm)
+                    .is_some_and(|runtime_realm| runtime_realm.beings.contains_key(being));
+                if !being_exists {
+                    return Err(RuntimeError::BeingNotFound(being.clone(), realm.clone()));
+                }
+                Ok(RuntimeValue::Being { realm: realm.clone(), being: being.clone() })
+            }
+            Expression::BinaryOperation { left, operator, right, .. } => {
+                let left = self.evaluate(frame, left, depth)?;
+                // Valutazione in corto circuito: l'operando destro può non essere valutato
+                match (operator, &left) {
+                    (BinaryOperator::And, RuntimeValue::Boolean(false)) |
+                    (BinaryOperator::Or, RuntimeValue::Boolean(true)) => return Ok(left),
+                    _ => {}
+                }
+                let right = self.evaluate(frame, right, depth)?;
+                binary_operation(operator, left, right)
+            }
+            Expression::UnaryOperation { operator, operand, .. } => {
+                let operand = self.evaluate(frame, operand, depth)?;
+                unary_operation(operator, operand)
+            }
+            Expression::FunctionCall { name, arguments, .. } => {
+                self.evaluate_call(frame, name, arguments, depth)
+            }
+            Expression::FieldAccess { object, field, .. } => match self.evaluate(frame, object, depth)? {
+                RuntimeValue::Essence { essence, fields } => fields.into_iter()
+                    .find(|(name, _)| name == field)
+                    .map(|(_, value)| value)
+                    .ok_or_else(|| RuntimeError::UndefinedVariable(format!("{}.{}", essence, field))),
+                object => {
+                    let (realm, being) = being_reference(object)?;
+                    self.variable(&realm, &being, field)
+                        .ok_or_else(|| RuntimeError::UndefinedVariable(format!("{}.{}", being, field)))?
+                        .try_clone()
+                }
+            },
+            Expression::Construction { essence, fields, .. } => {
+                let (realm, essence) = essence.split_once("::").unwrap_or((&frame.realm, essence));
+                let declaration = self.realms.get(realm)
+                    .and_then(|realm| realm.essences.get(essence))
+                    .cloned()
+                    .ok_or(RuntimeError::InvalidProgram)?;
+                self.construct(frame, &declaration, fields, depth)
+            }
+            Expression::Index { object, indices, .. } => {
+                let indices = self.evaluate_indices(frame, indices, depth)?;
+                // Una hive contenuta in una variabile viene letta sul posto, senza copiarla
+                if let Some(target) = self.place(frame, object) {
+                    return index(target, &indices);
+                }
+                let object = self.evaluate(frame, object, depth)?;
+                index(&object, &indices)
+            }
+            Expression::MethodCall { object, name, arguments, .. } => {
+                let (realm, being) = being_reference(self.evaluate(frame, object, depth)?)?;
+                let mut values = Vec::with_capacity(arguments.len());
+                for arg in arguments {
+                    values.push(self.evaluate(frame, arg, depth)?);
+                }
+                self.call_ritual(&realm, &being, name, values, depth + 1)
+            }
+            Expression::Error(_) => Err(RuntimeError::InvalidProgram),
+        }
+    }
+
+    // Valuta i campi nell'ordine in cui sono scritti e li dispone nell'ordine di dichiarazione
+    fn construct(
+        &mut self,
+        frame: &mut Frame,
+        essence: &Essence,
+        fields: &[FieldInit],
+        depth: usize,
+    ) -> Result<RuntimeValue, RuntimeError> {
+        let mut values = HashMap::new();
+        for field in fields {
+            values.insert(field.name.as_str(), self.evaluate(frame, &field.value, depth)?);
+        }
+
+        let mut result = Vec::with_capacity(essence.fields.len());
+        for declared in &essence.fields {
+            let value = values.remove(declared.name.as_str()).ok_or(RuntimeError::InvalidProgram)?;
+            let value = coerce(value, &declared.var_type).ok_or_else(|| RuntimeError::TypeError(
+                format!("field '{}' of essence '{}' expects {}", declared.name, essence.name, printer::type_name(&declared.var_type))
+            ))?;
+            result.push((declared.name.clone(), value));
+        }
+        Ok(RuntimeValue::Essence { essence: essence.name.clone(), fields: result })
+    }
+}
+
+// Converte un valore nel tipo dichiarato, promuovendo gli interi a float. I tipi definiti
+// dall'utente si confrontano per nome, senza l'eventuale realm (`Realm::Nome`): la
+// corrispondenza esatta è già garantita dall'analisi semantica
+pub(super) fn coerce(value: RuntimeValue, target: &Type) -> Option<RuntimeValue> {
+    let custom_name = |name: &str| name.rsplit("::").next().unwrap_or(name).to_string();
+    match (value, target) {
+        (RuntimeValue::Integer(n), Type::Float) => Some(RuntimeValue::Float(n as f64)),
+        (value @ RuntimeValue::Integer(_), Type::Integer) |
+        (value @ RuntimeValue::Float(_), Type::Float) |
+        (value @ RuntimeValue::String(_), Type::String) |
+        (value @ RuntimeValue::Boolean(_), Type::Boolean) => Some(value),
+        (RuntimeValue::Being { realm, being }, Type::Custom(name)) if being == custom_name(name) => {
+            Some(RuntimeValue::Being { realm, being })
+        }
+        (RuntimeValue::Essence { essence, fields }, Type::Custom(name)) if essence == custom_name(name) => {
+            Some(RuntimeValue::Essence { essence, fields })
+        }
+        // La hive assume il comportamento degli indici del tipo dichiarato, come nel codice C
+        (RuntimeValue::Hive { element, mut hive }, Type::Hive { element: declared, dimensions, circular, .. })
+            if element == **declared && hive.dimensions() == dimensions.as_slice() => {
+            hive.set_circular(*circular);
+            Some(RuntimeValue::Hive { element, hive })
+        }
+        _ => None,
+    }
+}
+
+/// Valore di una variabile dichiarata senza inizializzatore: i tipi primitivi partono dal valore
+/// zero, così un'assegnazione successiva viene convertita nel tipo dichiarato; le hive contengono
+/// zeri, le variabili di tipo essence o being restano senza valore finché non vengono assegnate
+pub(super) fn initial_value(var_type: &Type) -> RuntimeValue {
+    match var_type {
+        Type::Integer => RuntimeValue::Integer(0),
+        Type::Float => RuntimeValue::Float(0.0),
+        Type::String => RuntimeValue::String(String::new()),
+        Type::Boolean => RuntimeValue::Boolean(false),
+        // Le hive persistenti dei being vengono aperte da `being_variable`
+        Type::Hive { element, dimensions, circular, .. } => RuntimeValue::Hive {
+            element: (**element).clone(),
+            hive: Hive::new(dimensions.clone(), *circular, false),
+        },
+        _ => RuntimeValue::Void,
+    }
+}
+
+// Valore memorizzato in una hive: gli elementi sono sempre numeri in virgola mobile
+fn element_value(value: RuntimeValue) -> Result<f64, RuntimeError> {
+    match value {
+        RuntimeValue::Integer(n) => Ok(n as f64),
+        RuntimeValue::Float(x) => Ok(x),
+        other => Err(RuntimeError::TypeError(format!("hive elements must be numbers, found {}", other.type_name()))),
+    }
+}
+
+// Scrive un valore in una variabile. Una hive riceve una copia degli elementi, così una hive
+// persistente resta collegata al suo file
+pub(super) fn store(slot: &mut RuntimeValue, value: RuntimeValue, name: &str) -> Result<(), RuntimeError> {
+    if let (RuntimeValue::Hive { hive: current, .. }, RuntimeValue::Hive { hive, .. }) = (&mut *slot, &value) {
+        return Ok(current.copy_from(hive)?);
+    }
+    *slot = assign_value(slot, value, name)?;
+    Ok(())
+}
+
+// Una variabile mantiene il tipo del valore che contiene; una non inizializzata accetta qualsiasi valore
+fn assign_value(current: &RuntimeValue, value: RuntimeValue, name: &str) -> Result<RuntimeValue, RuntimeError> {
+    let result = match (current, value) {
+        (RuntimeValue::Void, value) => Some(value),
+        (RuntimeValue::Float(_), RuntimeValue::Integer(n)) => Some(RuntimeValue::Float(n as f64)),
+        (current, value) if std::mem::discriminant(current) == std::mem::discriminant(&value) => Some(value),
+        _ => None,
+    };
+    result.ok_or_else(|| RuntimeError::TypeError(
+        format!("cannot assign a different type to '{}' ({})", name, current.type_name())
+    ))
+}
+
+fn binary_operation(operator: &BinaryOperator, left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+    use RuntimeValue::{Boolean, Float, Integer};
+
+    match operator {
+        BinaryOperator::Modulo => match (left, right) {
+            (Integer(a), Integer(b)) => integer_arithmetic(operator, a, b).map(Integer),
+            (left, right) => Err(operand_error(operator, &left, &right)),
+        },
+        BinaryOperator::And | BinaryOperator::Or => match (left, right) {
+            (Boolean(a), Boolean(b)) => Ok(Boolean(if matches!(operator, BinaryOperator::And) { a && b } else { a || b })),
+            (left, right) => Err(operand_error(operator, &left, &right)),
+        },
+        BinaryOperator::Add | BinaryOperator::Subtract |
+        BinaryOperator::Multiply | BinaryOperator::Divide => match (left, right) {
+            (Integer(a), Integer(b)) => integer_arithmetic(operator, a, b).map(Integer),
+            (Integer(a), Float(b)) => Ok(Float(float_arithmetic(operator, a as f64, b))),
+            (Float(a), Integer(b)) => Ok(Float(float_arithmetic(operator, a, b as f64))),
+            (Float(a), Float(b)) => Ok(Float(float_arithmetic(operator, a, b))),
+            (left, right) => Err(operand_error(operator, &left, &right)),
+        },
+        BinaryOperator::Equal => Ok(Boolean(values_equal(&left, &right)?)),
+        BinaryOperator::NotEqual => Ok(Boolean(!values_equal(&left, &right)?)),
+        BinaryOperator::LessThan | BinaryOperator::GreaterThan |
+        BinaryOperator::LessEqual | BinaryOperator::GreaterEqual => {
+            let (a, b) = match (&left, &right) {
+                (Integer(a), Integer(b)) => return Ok(Boolean(compare(operator, a, b))),
+                (Integer(a), Float(b)) => (*a as f64, *b),
+                (Float(a), Integer(b)) => (*a, *b as f64),
+                (Float(a), Float(b)) => (*a, *b),
+                _ => return Err(operand_error(operator, &left, &right)),
+            };
+            Ok(Boolean(compare(operator, &a, &b)))
+        }
+    }
+}
+
+fn unary_operation(operator: &UnaryOperator, operand: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+    match (operator, operand) {
+        (UnaryOperator::Negate, RuntimeValue::Integer(n)) => n.checked_neg()
+            .map(RuntimeValue::Integer)
+            .ok_or(RuntimeError::IntegerOverflow),
+        (UnaryOperator::Negate, RuntimeValue::Float(x)) => Ok(RuntimeValue::Float(-x)),
+        (UnaryOperator::Not, RuntimeValue::Boolean(b)) => Ok(RuntimeValue::Boolean(!b)),
+        (operator, operand) => Err(RuntimeError::TypeError(format!(
+            "unsupported operand for `{}`: {}",
+            crate::ast::printer::unary_symbol(operator),
+            operand.type_name()
+        ))),
+    }
+}
+
+fn integer_arithmetic(operator: &BinaryOperator, a: i64, b: i64) -> Result<i64, RuntimeError> {
+    let result = match operator {
+        BinaryOperator::Add => a.checked_add(b),
+        BinaryOperator::Subtract => a.checked_sub(b),
+        BinaryOperator::Multiply => a.checked_mul(b),
+        BinaryOperator::Divide => {
+            if b == 0 {
+                return Err(RuntimeError::DivisionByZero);
+            }
+            a.checked_div(b)
+        }
+        BinaryOperator::Modulo => {
+            if b == 0 {
+                return Err(RuntimeError::DivisionByZero);
+            }
+            a.checked_rem(b)
+        }
+        _ => unreachable!("not an arithmetic operator"),
+    };
+    result.ok_or(RuntimeError::IntegerOverflow)
+}
+
+fn float_arithmetic(operator: &BinaryOperator, a: f64, b: f64) -> f64 {
+    match operator {
+        BinaryOperator::Add => a + b,
+        BinaryOperator::Subtract => a - b,
+        BinaryOperator::Multiply => a * b,
+        BinaryOperator::Divide => a / b,
+        _ => unreachable!("not an arithmetic operator"),
+    }
+}
+
+fn compare<T: PartialOrd>(operator: &BinaryOperator, a: &T, b: &T) -> bool {
+    match operator {
+        BinaryOperator::LessThan => a < b,
+        BinaryOperator::GreaterThan => a > b,
+        BinaryOperator::LessEqual => a <= b,
+        BinaryOperator::GreaterEqual => a >= b,
+        _ => unreachable!("not an ordering operator"),
+    }
+}
+
+fn values_equal(left: &RuntimeValue, right: &RuntimeValue) -> Result<bool, RuntimeError> {
+    use RuntimeValue::*;
+
+    match (left, right) {
+        (Integer(a), Integer(b)) => Ok(a == b),
+        (Integer(a), Float(b)) | (Float(b), Integer(a)) => Ok(*a as f64 == *b),
+        (Float(a), Float(b)) => Ok(a == b),
+        (String(a), String(b)) => Ok(a == b),
+        (Boolean(a), Boolean(b)) => Ok(a == b),
+        (Being { .. }, Being { .. }) => Ok(left == right),
+        // Come nel codice C generato contano solo le dimensioni e gli elementi
+        (Hive { hive: left_hive, .. }, Hive { hive: right_hive, .. }) => Ok(left_hive.equals(right_hive)?),
+        (Essence { essence: a, fields: left_fields }, Essence { essence: b, fields: right_fields }) if a == b => {
+            for ((_, l), (_, r)) in left_fields.iter().zip(right_fields) {
+                if !values_equal(l, r)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        _ => Err(RuntimeError::TypeError(
+            format!("cannot compare {} with {}", left.type_name(), right.type_name())
+        )),
+    }
+}
+
+// Percorre i campi annidati di un'essence fino all'ultimo del percorso
+fn field_slot<'v>(target: &'v mut RuntimeValue, path: &[&str]) -> Result<&'v mut RuntimeValue, RuntimeError> {
+    let Some((field, rest)) = path.split_first() else {
+        return Ok(target);
+    };
+    let RuntimeValue::Essence { essence, fields } = target else {
+        return Err(RuntimeError::TypeError(format!("{} has no assignable fields", target.type_name())));
+    };
+    let slot = fields.iter_mut()
+        .find(|(name, _)| name == field)
+        .map(|(_, slot)| slot)
+        .ok_or_else(|| RuntimeError::UndefinedVariable(format!("{}.{}", essence, field)))?;
+    field_slot(slot, rest)
+}
+
+// Elemento di una hive, convertito nel tipo dei suoi elementi
+fn index(target: &RuntimeValue, indices: &[i64]) -> Result<RuntimeValue, RuntimeError> {
+    match target {
+        RuntimeValue::Hive { element, hive } => Ok(RuntimeValue::hive_element(element, hive.get(indices)?)),
+        other => Err(RuntimeError::TypeError(format!("{} cannot be indexed", other.type_name()))),
+    }
+}
+
+// Realm e nome del being a cui si riferisce un valore usato con `.`
+fn being_reference(value: RuntimeValue) -> Result<(String, String), RuntimeError> {
+    match value {
+        RuntimeValue::Being { realm, being } => Ok((realm, being)),
+        other => Err(RuntimeError::TypeError(format!("{} has no fields or rituals", other.type_name()))),
+    }
+}
+
+fn operand_error(operator: &BinaryOperator, left: &RuntimeValue, right: &RuntimeValue) -> RuntimeError {
+    RuntimeError::TypeError(format!(
+        "unsupported operands for `{}`: {} and {}",
+        crate::ast::printer::operator_symbol(operator),
+        left.type_name(),
+        right.type_name()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer, parser};
+
+    fn runtime(source: &str) -> NervsRuntime {
+        let tokens = lexer::tokenize(source).expect("the program is valid");
+        let (program, errors) = parser::parse(tokens);
+        assert!(errors.is_empty());
+        NervsRuntime::new(&program.expect("the program is valid")).unwrap()
+    }
+
+    #[test]
+    fn factorial_is_computed_both_ways() {
+        let mut runtime = runtime("
+            realm R {
+                being B {
+                    int calls: 0;
+
+                    public ritual recursive(n: int) int {
+                        calls = calls + 1;
+                        if (n <= 1) {
+                            return 1;
+                        }
+                        return n * recursive(n - 1);
+                    }
+
+                    public ritual iterative(n: int) int {
+                        int result: 1;
+                        cycle (n > 1) {
+                            result = result * n;
+                            n = n - 1;
+                        }
+                        return result;
+                    }
+                }
+            }
+        ");
+        let five = || vec![RuntimeValue::Integer(5)];
+        assert_eq!(runtime.execute_ritual("R", "B", "recursive", five()).unwrap(), RuntimeValue::Integer(120));
+        assert_eq!(runtime.execute_ritual("R", "B", "iterative", five()).unwrap(), RuntimeValue::Integer(120));
+        // Le variabili del being restano da una chiamata all'altra
+        assert_eq!(runtime.variable("R", "B", "calls"), Some(&RuntimeValue::Integer(5)));
+    }
+
+    #[test]
+    fn runtime_errors_stop_the_ritual() {
+        let mut runtime = runtime("
+            realm R {
+                being B {
+                    public ritual divide(a: int, b: int) int {
+                        return a / b;
+                    }
+
+                    ritual hidden() {
+                    }
+                }
+            }
+        ");
+        let arguments = |a, b| vec![RuntimeValue::Integer(a), RuntimeValue::Integer(b)];
+        assert!(matches!(runtime.execute_ritual("R", "B", "divide", arguments(1, 0)), Err(RuntimeError::DivisionByZero)));
+        assert!(matches!(runtime.execute_ritual("R", "B", "divide", arguments(i64::MIN, -1)), Err(RuntimeError::IntegerOverflow)));
+        assert!(matches!(
+            runtime.execute_ritual("R", "B", "divide", vec![RuntimeValue::Integer(1)]),
+            Err(RuntimeError::ArgumentCount { expected: 2, found: 1, .. })
+        ));
+        assert!(matches!(runtime.execute_ritual("R", "B", "hidden", Vec::new()), Err(RuntimeError::PrivateRitual(..))));
+    }
+
+    #[test]
+    fn essences_are_copied_by_value() {
+        let mut runtime = runtime("
+            realm R {
+                essence Point {
+                    x: int;
+                    y: float;
+                }
+
+                essence Segment {
+                    start: Point;
+                    end: Point;
+                }
+
+                being B {
+                    public ritual run() float {
+                        Point p: Point { y: 2.5, x: 1 };
+                        Segment s: Segment { start: p, end: p };
+                        s.end.x = 10;
+                        p.x = 4;
+                        return s.start.x + s.end.x + p.x + s.end.y;
+                    }
+                }
+            }
+        ");
+        assert_eq!(runtime.execute_ritual("R", "B", "run", Vec::new()).unwrap(), RuntimeValue::Float(17.5));
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        let mut runtime = runtime("
+            realm R {
+                being B {
+                    ritual explode() bool {
+                        return 1 / 0 == 0;
+                    }
+
+                    public ritual run(n: int) bool {
+                        bool skipped: (n > 0 || explode()) && !(n < 0 && explode());
+                        return skipped && -n % 3 == -1 && !false;
+                    }
+                }
+            }
+        ");
+        assert_eq!(
+            runtime.execute_ritual("R", "B", "run", vec![RuntimeValue::Integer(4)]).unwrap(),
+            RuntimeValue::Boolean(true)
+        );
+        assert!(matches!(
+            runtime.execute_ritual("R", "B", "run", vec![RuntimeValue::Integer(-4)]),
+            Err(RuntimeError::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn rituals_of_other_beings_and_realms_are_called() {
+        let mut runtime = runtime("
+            realm Lab {
+                being Store {
+                    int secret: 1;
+                    public int shared: 2;
+
+                    ritual hidden() int {
+                        return secret;
+                    }
+
+                    public ritual open() int {
+                        shared = shared + 1;
+                        return hidden();
+                    }
+                }
+
+                being Client {
+                    public ritual f() int {
+                        return Store.open() + Store.shared + Other::Remote.ping();
+                    }
+                }
+            }
+
+            realm Other {
+                being Remote {
+                    public ritual ping() int {
+                        return 5;
+                    }
+                }
+            }
+        ");
+        // `open` viene eseguito prima della lettura di `shared`
+        assert_eq!(runtime.execute_ritual("Lab", "Client", "f", Vec::new()).unwrap(), RuntimeValue::Integer(9));
+        assert_eq!(runtime.variable("Lab", "Store", "shared"), Some(&RuntimeValue::Integer(3)));
+    }
+
+    #[test]
+    fn deep_recursion_stops_at_the_depth_limit() {
+        let mut runtime = runtime("
+            realm R {
+                being B {
+                    public ritual down(n: int) int {
+                        if (n == 0) {
+                            return 0;
+                        }
+                        return down(n - 1) + 1;
+                    }
+                }
+            }
+        ");
+        // I thread dei test hanno uno stack di soli 2 MB
+        let depth = (MAX_CALL_DEPTH - 1) as i64;
+        assert_eq!(
+            runtime.execute_ritual("R", "B", "down", vec![RuntimeValue::Integer(depth)]).unwrap(),
+            RuntimeValue::Integer(depth)
+        );
+        assert!(matches!(
+            runtime.execute_ritual("R", "B", "down", vec![RuntimeValue::Integer(100_000)]),
+            Err(RuntimeError::StackOverflow(name)) if name == "down"
+        ));
+    }
+
+    #[test]
+    fn integers_are_promoted_to_float() {
+        let mut runtime = runtime("
+            realm R {
+                being B {
+                    float later;
+
+                    ritual init() {
+                        later = 3;
+                    }
+
+                    ritual half(v: float) float {
+                        return v / 2;
+                    }
+
+                    public ritual run() float {
+                        float z: 1;
+                        return z + half(5) + later;
+                    }
+
+                    public ritual whole() float {
+                        return 2;
+                    }
+                }
+            }
+        ");
+        assert_eq!(runtime.variable("R", "B", "later"), Some(&RuntimeValue::Float(3.0)));
+        assert_eq!(runtime.execute_ritual("R", "B", "run", Vec::new()).unwrap(), RuntimeValue::Float(6.5));
+        assert_eq!(runtime.execute_ritual("R", "B", "whole", Vec::new()).unwrap(), RuntimeValue::Float(2.0));
+    }
+
+    #[test]
+    fn hive_elements_are_read_in_place() {
+        let mut runtime = runtime("
+            realm R {
+                being B {
+                    hive<float>[50000] samples;
+
+                    ritual fill() hive<int>[4] {
+                        hive<int>[4] h;
+                        h[2] = 7;
+                        return h;
+                    }
+
+                    public ritual run() float {
+                        int i: 0;
+                        cycle (i < 50000) {
+                            samples[i] = i;
+                            i = i + 1;
+                        }
+                        // Copiando la hive a ogni lettura il ciclo copierebbe 20 GB
+                        float total: 0;
+                        i = 0;
+                        cycle (i < 50000) {
+                            total = total + samples[i];
+                            i = i + 1;
+                        }
+                        hive<int>[4] local: fill();
+                        return total + local[2] + fill()[2];
+                    }
+                }
+            }
+        ");
+        assert_eq!(
+            runtime.execute_ritual("R", "B", "run", Vec::new()).unwrap(),
+            RuntimeValue::Float(49999.0 * 25000.0 + 14.0)
+        );
+    }
+
+    #[test]
+    fn hive_arguments_index_as_their_parameter() {
+        let mut runtime = runtime("
+            realm R {
+                being B {
+                    ritual wrap(h: circular hive<int>[3]) int {
+                        return h[4];
+                    }
+
+                    ritual check(h: hive<int>[3]) int {
+                        return h[4];
+                    }
+
+                    public ritual run(strict: bool) int {
+                        circular hive<int>[3] ring;
+                        hive<int>[3] plain;
+                        ring[1] = 5;
+                        plain = ring;
+                        if (strict) {
+                            return check(ring);
+                        }
+                        return wrap(plain);
+                    }
+                }
+            }
+        ");
+        assert_eq!(runtime.execute_ritual("R", "B", "run", vec![RuntimeValue::Boolean(false)]).unwrap(), RuntimeValue::Integer(5));
+        assert!(matches!(
+            runtime.execute_ritual("R", "B", "run", vec![RuntimeValue::Boolean(true)]),
+            Err(RuntimeError::Hive(crate::runtime::hive::HiveError::OutOfBounds { index: 4, .. }))
+        ));
+    }
+
+    #[test]
+    fn hives_compare_shape_and_elements() {
+        let mut runtime = runtime("
+            realm R {
+                being B {
+                    public ritual run() bool {
+                        circular hive<float>[2] ring;
+                        hive<float>[2] plain;
+                        ring[0] = 1.5;
+                        plain[0] = 1.5;
+                        bool same: ring == plain;
+                        plain[1] = 2.0;
+                        return same && ring != plain;
+                    }
+                }
+            }
+        ");
+        assert_eq!(runtime.execute_ritual("R", "B", "run", Vec::new()).unwrap(), RuntimeValue::Boolean(true));
+    }
+}
@@ -0,0 +1,442 @@
+// Blocchi `memory`: le variabili di memoria di ogni being vengono salvate in un'istantanea su
+// disco e ripristinate alla creazione del runtime successivo
+//
+// Ogni being con variabili di memoria ha un file `Realm.Being.memory` nella cartella indicata
+// dalle opzioni del runtime. Il file è testuale, con una riga per variabile dopo l'intestazione:
+//
+//   nervs-memory 1
+//   count: int = int(3)
+//   best: Game::Score { points: int } = essence(Score, points = int(10))
+//
+// Il tipo salvato accanto al valore rende visibili i cambiamenti di schema: le essence vengono
+// descritte con tutti i loro campi, così anche modificare un campo viene rilevato.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::ast::nodes::Type;
+use crate::ast::printer;
+use super::hive::Hive;
+use super::{interpreter, RuntimeError, RuntimeRealm, RuntimeValue};
+
+/// Estensione dei file con le istantanee della memoria
+pub const EXTENSION: &str = "memory";
+
+/// Versione del formato delle istantanee
+pub const FORMAT_VERSION: u32 = 1;
+
+const HEADER: &str = "nervs-memory";
+
+// Ripristina le variabili di memoria salvate dall'esecuzione precedente; quelle ripristinate
+// non vengono più inizializzate
+pub(super) fn restore(realms: &mut HashMap<String, RuntimeRealm>, directory: &Path) -> Result<(), RuntimeError> {
+    let mut restored = Vec::new();
+    for (realm_name, realm) in realms.iter() {
+        for (being_name, being) in &realm.beings {
+            if being.memory.is_empty() {
+                continue;
+            }
+            let path = snapshot_path(directory, realm_name, being_name);
+            if !path.exists() {
+                continue;
+            }
+
+            for entry in read_snapshot(&path)? {
+                let Some((_, declared)) = being.memory.iter().find(|(name, _)| *name == entry.name) else {
+                    // Variabile non più dichiarata in un blocco `memory`
+                    continue;
+                };
+                let current = schema(realms, realm_name, declared);
+                if entry.schema != current {
+                    return Err(RuntimeError::MemorySchema {
+                        being: format!("{}::{}", realm_name, being_name),
+                        variable: entry.name,
+                        stored: entry.schema,
+                        declared: current,
+                    });
+                }
+                let value = match entry.value {
+                    RuntimeValue::Void => RuntimeValue::Void,
+                    value => interpreter::coerce(value, declared).ok_or_else(|| corrupted(
+                        &path,
+                        entry.line,
+                        "the value does not match the declared type",
+                    ))?,
+                };
+                restored.push((realm_name.clone(), being_name.clone(), entry.name, value));
+            }
+        }
+    }
+
+    for (realm, being, name, value) in restored {
+        let being = realms.get_mut(&realm)
+            .and_then(|realm| realm.beings.get_mut(&being))
+            .expect("restored beings exist");
+        let slot = being.variables.get_mut(&name).expect("memory variables are being variables");
+        interpreter::store(slot, value, &name)?;
+        being.restored.insert(name);
+    }
+    Ok(())
+}
+
+// Salva le variabili di memoria di ogni being che ne dichiara
+pub(super) fn save(realms: &HashMap<String, RuntimeRealm>, directory: &Path) -> Result<(), RuntimeError> {
+    for (realm_name, realm) in realms {
+        for (being_name, being) in &realm.beings {
+            if being.memory.is_empty() {
+                continue;
+            }
+
+            let mut text = format!("{} {}\n", HEADER, FORMAT_VERSION);
+            for (name, declared) in &being.memory {
+                let value = being.variables.get(name).unwrap_or(&RuntimeValue::Void);
+                text.push_str(&format!("{}: {} = {}\n", name, schema(realms, realm_name, declared), encode(value)?));
+            }
+            write_snapshot(&snapshot_path(directory, realm_name, being_name), &text)?;
+        }
+    }
+    Ok(())
+}
+
+fn snapshot_path(directory: &Path, realm: &str, being: &str) -> PathBuf {
+    directory.join(format!("{}.{}.{}", realm, being, EXTENSION))
+}
+
+// Descrizione di un tipo indipendente dal realm in cui compare: i tipi definiti dall'utente
+// sono qualificati e le essence riportano i loro campi
+fn schema(realms: &HashMap<String, RuntimeRealm>, realm: &str, var_type: &Type) -> String {
+    let Type::Custom(name) = var_type else {
+        return printer::type_name(var_type);
+    };
+    let (realm, name) = name.split_once("::").unwrap_or((realm, name));
+    let Some(essence) = realms.get(realm).and_then(|r| r.essences.get(name)) else {
+        return format!("{}::{}", realm, name);
+    };
+
+    // Le essence non possono contenersi, quindi la descrizione è finita
+    let fields: Vec<String> = essence.fields.iter()
+        .map(|field| format!("{}: {}", field.name, schema(realms, realm, &field.var_type)))
+        .collect();
+    format!("{}::{} {{ {} }}", realm, name, fields.join(", "))
+}
+
+// Variabile letta da un'istantanea
+struct Entry {
+    name: String,
+    schema: String,
+    value: RuntimeValue,
+    line: usize,
+}
+
+fn read_snapshot(path: &Path) -> Result<Vec<Entry>, RuntimeError> {
+    let text = std::fs::read_to_string(path).map_err(|e| io_error(path, e))?;
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    match lines.next().and_then(|(_, line)| line.split_once(' ')) {
+        Some((HEADER, version)) if version == FORMAT_VERSION.to_string() => {}
+        Some((HEADER, version)) => {
+            return Err(corrupted(path, 1, &format!("unsupported format version {}", version)));
+        }
+        _ => return Err(corrupted(path, 1, "missing `nervs-memory` header")),
+    }
+
+    let mut entries = Vec::new();
+    for (line, text) in lines.filter(|(_, text)| !text.is_empty()) {
+        let parsed = text.split_once(": ")
+            .and_then(|(name, rest)| rest.split_once(" = ").map(|(schema, value)| (name, schema, value)));
+        let Some((name, schema, value)) = parsed else {
+            return Err(corrupted(path, line, "expected `name: type = value`"));
+        };
+        let value = Decoder::new(value).value_to_end().map_err(|reason| corrupted(path, line, &reason))?;
+        entries.push(Entry { name: name.to_string(), schema: schema.to_string(), value, line });
+    }
+    Ok(entries)
+}
+
+// Scrive l'istantanea in un file temporaneo e lo rinomina, per non lasciarla mai a metà
+fn write_snapshot(path: &Path, text: &str) -> Result<(), RuntimeError> {
+    let mut temporary = path.to_path_buf().into_os_string();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+    std::fs::write(&temporary, text).map_err(|e| io_error(&temporary, e))?;
+    std::fs::rename(&temporary, path).map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &Path, error: std::io::Error) -> RuntimeError {
+    RuntimeError::MemoryIo { path: path.display().to_string(), message: error.to_string() }
+}
+
+fn corrupted(path: &Path, line: usize, reason: &str) -> RuntimeError {
+    RuntimeError::MemoryCorrupted { path: path.display().to_string(), line, reason: reason.to_string() }
+}
+
+// Codifica un valore in modo che `Decoder` lo ricostruisca esattamente
+fn encode(value: &RuntimeValue) -> Result<String, RuntimeError> {
+    Ok(match value {
+        RuntimeValue::Integer(n) => format!("int({})", n),
+        // `{:?}` conserva tutte le cifre, compresi `inf` e `NaN`
+        RuntimeValue::Float(x) => format!("float({:?})", x),
+        RuntimeValue::String(s) => format!("string({})", quote(s)),
+        RuntimeValue::Boolean(b) => format!("bool({})", b),
+        RuntimeValue::Essence { essence, fields } => {
+            let fields: Vec<String> = fields.iter()
+                .map(|(name, value)| Ok(format!(", {} = {}", name, encode(value)?)))
+                .collect::<Result<_, RuntimeError>>()?;
+            format!("essence({}{})", essence, fields.concat())
+        }
+        RuntimeValue::Being { realm, being } => format!("being({}, {})", realm, being),
+        RuntimeValue::Hive { element, hive } => {
+            let dimensions: Vec<String> = hive.dimensions().iter().map(usize::to_string).collect();
+            let data: Vec<String> = hive.data()?.iter().map(|x| format!("{:?}", x)).collect();
+            format!(
+                "hive({}, [{}], [{}]{})",
+                printer::type_name(element),
+                dimensions.join(", "),
+                data.join(", "),
+                if hive.is_circular() { ", circular" } else { "" },
+            )
+        }
+        RuntimeValue::Void => "void".to_string(),
+    })
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Lettura dei valori scritti da `encode`
+struct Decoder<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(text: &'a str) -> Self {
+        Decoder { rest: text }
+    }
+
+    fn value_to_end(mut self) -> Result<RuntimeValue, String> {
+        let value = self.value()?;
+        if !self.rest.trim().is_empty() {
+            return Err(format!("unexpected `{}` after the value", self.rest.trim()));
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<RuntimeValue, String> {
+        let tag = self.word()?;
+        if tag == "void" {
+            return Ok(RuntimeValue::Void);
+        }
+        self.expect('(')?;
+        let value = match tag {
+            "int" => RuntimeValue::Integer(self.number()?),
+            "float" => RuntimeValue::Float(self.number()?),
+            "bool" => RuntimeValue::Boolean(self.number()?),
+            "string" => RuntimeValue::String(self.string()?),
+            "being" => {
+                let realm = self.word()?.to_string();
+                self.expect(',')?;
+                RuntimeValue::Being { realm, being: self.word()?.to_string() }
+            }
+            "essence" => {
+                let essence = self.word()?.to_string();
+                let mut fields = Vec::new();
+                while self.eat(',') {
+                    let name = self.word()?.to_string();
+                    self.expect('=')?;
+                    fields.push((name, self.value()?));
+                }
+                RuntimeValue::Essence { essence, fields }
+            }
+            "hive" => self.hive()?,
+            other => return Err(format!("unknown value `{}`", other)),
+        };
+        self.expect(')')?;
+        Ok(value)
+    }
+
+    // `hive(float, [2, 3], [elementi...], circular)`, con `circular` facoltativo
+    fn hive(&mut self) -> Result<RuntimeValue, String> {
+        let element = match self.word()? {
+            "int" => Type::Integer,
+            "float" => Type::Float,
+            other => return Err(format!("invalid hive element type `{}`", other)),
+        };
+        self.expect(',')?;
+        let dimensions = self.list()?;
+        self.expect(',')?;
+        let data = self.list()?;
+        let circular = self.eat(',') && self.word()? == "circular";
+        let hive = Hive::from_data(dimensions, data, circular, false).map_err(|e| e.to_string())?;
+        Ok(RuntimeValue::Hive { element, hive })
+    }
+
+    fn list<T: std::str::FromStr>(&mut self) -> Result<Vec<T>, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        if !self.eat(']') {
+            loop {
+                items.push(self.number()?);
+                if self.eat(']') {
+                    break;
+                }
+                self.expect(',')?;
+            }
+        }
+        Ok(items)
+    }
+
+    // Identificatore, eventualmente qualificato con `::`
+    fn word(&mut self) -> Result<&'a str, String> {
+        self.rest = self.rest.trim_start();
+        let end = self.rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':')).unwrap_or(self.rest.len());
+        if end == 0 {
+            return Err("expected a name".to_string());
+        }
+        let (word, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Ok(word)
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, String> {
+        self.rest = self.rest.trim_start();
+        let end = self.rest.find([',', ')', ']']).unwrap_or(self.rest.len());
+        let (text, rest) = self.rest.split_at(end);
+        let value = text.trim().parse().map_err(|_| format!("invalid value `{}`", text.trim()))?;
+        self.rest = rest;
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        let mut chars = self.rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[i + 1..];
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 'r')) => value.push('\r'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, c @ ('"' | '\\'))) => value.push(c),
+                    _ => return Err("invalid escape in string".to_string()),
+                },
+                c => value.push(c),
+            }
+        }
+        Err("unterminated string".to_string())
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(expected) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(format!("expected `{}`", expected))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{NervsRuntime, RuntimeOptions};
+    use crate::Session;
+
+    fn round_trip(value: RuntimeValue) {
+        let encoded = encode(&value).unwrap();
+        assert_eq!(Decoder::new(&encoded).value_to_end(), Ok(value), "{}", encoded);
+    }
+
+    #[test]
+    fn values_survive_encoding() {
+        round_trip(RuntimeValue::Integer(-42));
+        round_trip(RuntimeValue::Float(0.1 + 0.2));
+        round_trip(RuntimeValue::Float(f64::NEG_INFINITY));
+        round_trip(RuntimeValue::Boolean(true));
+        round_trip(RuntimeValue::String("a \"quoted\" = value,\n\ttab \\ end)".to_string()));
+        round_trip(RuntimeValue::Void);
+        round_trip(RuntimeValue::Being { realm: "Game".to_string(), being: "Player".to_string() });
+        round_trip(RuntimeValue::Essence {
+            essence: "Score".to_string(),
+            fields: vec![
+                ("points".to_string(), RuntimeValue::Integer(3)),
+                ("name".to_string(), RuntimeValue::String("ann".to_string())),
+            ],
+        });
+
+        let hive = Hive::from_data(vec![2, 2], vec![1.0, -2.5, 0.0, 4.0], true, false).unwrap();
+        round_trip(RuntimeValue::Hive { element: Type::Float, hive });
+        round_trip(RuntimeValue::Hive { element: Type::Integer, hive: Hive::new(vec![0], false, false) });
+    }
+
+    #[test]
+    fn malformed_values_are_rejected() {
+        assert_eq!(Decoder::new("int(1").value_to_end(), Err("expected `)`".to_string()));
+        assert_eq!(Decoder::new("int(1) int(2)").value_to_end(), Err("unexpected `int(2)` after the value".to_string()));
+        assert_eq!(Decoder::new("list(1)").value_to_end(), Err("unknown value `list`".to_string()));
+        assert!(Decoder::new("string(\"open)").value_to_end().is_err());
+        assert!(Decoder::new("hive(float, [2], [1.0])").value_to_end().is_err());
+    }
+
+    #[test]
+    fn restored_variables_survive_init() {
+        const PROGRAM: &str = "
+            realm Game {
+                being Counter {
+                    int runs;
+
+                    memory {
+                        int count;
+                    }
+
+                    ritual init() {
+                        runs = 1;
+                    }
+
+                    public ritual bump() int {
+                        count = count + runs;
+                        return count;
+                    }
+                }
+            }
+        ";
+        let program = Session::new("counter.nervs", PROGRAM).analyze().expect("the program is valid");
+        let directory = std::env::temp_dir().join(format!("nervs-memory-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let options = RuntimeOptions { memory_directory: directory.clone(), ..RuntimeOptions::default() };
+
+        let mut counts = Vec::new();
+        for _ in 0..2 {
+            let mut runtime = NervsRuntime::with_options(&program, &options).unwrap();
+            counts.push(runtime.execute_ritual("Game", "Counter", "bump", Vec::new()).unwrap());
+            runtime.flush().unwrap();
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(counts, [RuntimeValue::Integer(1), RuntimeValue::Integer(2)]);
+    }
+}
@@ -1,86 +1,431 @@
-pub mod hive;
-
-use std::collections::HashMap;
-use crate::ast::nodes::{Program, Ritual};
-
-
-/// Contesto di esecuzione per Nervs
-pub struct NervsRuntime {
-    /// Memoria globale per i realm
-    realms: HashMap<String, RuntimeRealm>,
-}
-
-/// Stato di esecuzione per un realm
-struct RuntimeRealm {
-    /// Beings attivi nel realm
-    beings: HashMap<String, RuntimeBeing>,
-}
-
-/// Stato di esecuzione per un being
-struct RuntimeBeing {
-    /// Variabili del being
-    variables: HashMap<String, RuntimeValue>,
-    /// Rituali definiti
-    rituals: HashMap<String, Ritual>,
-}
-
-/// Rappresentazione di un valore durante l'esecuzione
-#[derive(Clone, Debug)]
-enum RuntimeValue {
-    Integer(i64),
-    Float(f64),
-    String(String),
-    Boolean(bool),
-    Void,
-}
-
-impl NervsRuntime {
-    /// Inizializza il runtime da un programma Nervs
-    pub fn new(program: &Program) -> Self {
-        let mut realms = HashMap::new();
-        
-        for realm in &program.realms {
-            let mut runtime_realm = RuntimeRealm {
-                beings: HashMap::new(),
-            };
-            
-            for being in &realm.beings {
-                let runtime_being = RuntimeBeing {
-                    variables: being.variables.iter()
-                        .map(|var| (var.name.clone(), RuntimeValue::Void))
-                        .collect(),
-                    rituals: being.rituals.iter()
-                        .map(|ritual| (ritual.name.clone(), ritual.clone()))
-                        .collect(),
-                };
-                
-                runtime_realm.beings.insert(being.name.clone(), runtime_being);
-            }
-            
-            realms.insert(realm.name.clone(), runtime_realm);
-        }
-        
-        NervsRuntime { realms }
-    }
-    
-    /// Esegue un ritual in un being specifico
-    pub fn execute_ritual(&mut self, realm_name: &str, being_name: &str, ritual_name: &str) -> Result<RuntimeValue, String> {
-        let realm = self.realms.get_mut(realm_name)
-            .ok_or_else(|| format!("Realm {} not found", realm_name))?;
-        
-        let being = realm.beings.get_mut(being_name)
-            .ok_or_else(|| format!("Being {} not found in realm {}", being_name, realm_name))?;
-        
-        let _ritual = being.rituals.get(ritual_name)
-            .ok_or_else(|| format!("Ritual {} not found in being {}", ritual_name, being_name))?;
-        
-        // Esecuzione base del ritual (da implementare completamente)
-        // Qui andrà la logica di esecuzione degli statement
-        Ok(RuntimeValue::Void)
-    }
-}
-
-/// Inizializza il runtime del linguaggio
-pub fn initialize(program: &Program) -> NervsRuntime {
-    NervsRuntime::new(program)
+mod extensions;
+pub mod hive;
+mod interpreter;
+pub mod memory;
+mod perceptions;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::rc::Rc;
+use crate::ast::nodes::{Essence, Extension, Perception, Program, Ritual, Type, Variable, Visibility};
+use hive::{Hive, HiveError};
+pub use extensions::{Extensions, NativeRitual};
+
+/// Opzioni di esecuzione
+#[derive(Debug, Clone)]
+pub struct RuntimeOptions {
+    /// Cartella dei file delle hive persistenti, uno per variabile di being
+    pub hive_directory: PathBuf,
+    /// Cartella delle istantanee dei blocchi `memory`, una per being
+    pub memory_directory: PathBuf,
+}
+
+impl Default for RuntimeOptions {
+    fn default() -> Self {
+        RuntimeOptions {
+            hive_directory: PathBuf::from("."),
+            memory_directory: PathBuf::from("."),
+        }
+    }
+}
+
+/// Contesto di esecuzione per Nervs
+pub struct NervsRuntime {
+    /// Memoria globale per i realm
+    realms: HashMap<String, RuntimeRealm>,
+    options: RuntimeOptions,
+    /// Percezioni ricevute e non ancora gestite, nell'ordine di arrivo
+    events: VecDeque<perceptions::Event>,
+    /// Funzioni native collegate alle estensioni
+    extensions: Extensions,
+}
+
+/// Stato di esecuzione per un realm
+struct RuntimeRealm {
+    /// Essence dichiarate nel realm, per costruirne i valori
+    essences: HashMap<String, Rc<Essence>>,
+    /// Beings attivi nel realm
+    beings: HashMap<String, RuntimeBeing>,
+}
+
+/// Stato di esecuzione per un being
+struct RuntimeBeing {
+    /// Variabili del being
+    variables: HashMap<String, RuntimeValue>,
+    /// Variabili dei blocchi `memory`, con il tipo dichiarato, in ordine di dichiarazione
+    memory: Vec<(String, Type)>,
+    /// Variabili di memoria ripristinate dall'esecuzione precedente, da non inizializzare
+    restored: HashSet<String>,
+    /// Rituali definiti
+    rituals: HashMap<String, Rc<Ritual>>,
+    /// Percezioni dichiarate, gestite dai ritual con lo stesso nome
+    perceptions: HashMap<String, Rc<Perception>>,
+    /// Estensioni dichiarate, eseguite dalle funzioni native del runtime
+    extensions: HashMap<String, Rc<Extension>>,
+}
+
+/// Errori che possono interrompere l'esecuzione di un ritual
+#[derive(Debug, thiserror::Error)]
+pub enum RuntimeError {
+    #[error("Realm {0} not found")]
+    RealmNotFound(String),
+
+    #[error("Being {0} not found in realm {1}")]
+    BeingNotFound(String, String),
+
+    #[error("Ritual {0} not found in being {1}")]
+    RitualNotFound(String, String),
+
+    #[error("Perception {0} not found in being {1}")]
+    PerceptionNotFound(String, String),
+
+    #[error("Ritual {0} of being {1} is private and cannot be executed from outside the program")]
+    PrivateRitual(String, String),
+
+    #[error("Ritual {ritual} expects {expected} argument(s), found {found}")]
+    ArgumentCount {
+        ritual: String,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("Undefined variable: {0}")]
+    UndefinedVariable(String),
+
+    #[error("Type error: {0}")]
+    TypeError(String),
+
+    #[error("Division by zero")]
+    DivisionByZero,
+
+    #[error("Integer overflow")]
+    IntegerOverflow,
+
+    #[error("Ritual {0} finished without returning a value")]
+    MissingReturn(String),
+
+    #[error("Maximum call depth exceeded in ritual {0}")]
+    StackOverflow(String),
+
+    #[error(transparent)]
+    Hive(#[from] HiveError),
+
+    #[error("Cannot access memory snapshot {path}: {message}")]
+    MemoryIo {
+        path: String,
+        message: String,
+    },
+
+    #[error("Memory snapshot {path} is corrupted at line {line}: {reason}")]
+    MemoryCorrupted {
+        path: String,
+        line: usize,
+        reason: String,
+    },
+
+    #[error("Memory variable {variable} of being {being} was saved as {stored}, but is now declared as {declared}; delete the snapshot to start over")]
+    MemorySchema {
+        being: String,
+        variable: String,
+        stored: String,
+        declared: String,
+    },
+
+    #[error("Extensions declared but not bound by the host: {}", .0.join(", "))]
+    UnboundExtensions(Vec<String>),
+
+    #[error("Extension {extension} failed: {message}")]
+    Extension {
+        extension: String,
+        message: String,
+    },
+
+    #[error("Cannot execute a program containing syntax errors")]
+    InvalidProgram,
+}
+
+/// Rappresentazione di un valore durante l'esecuzione
+#[derive(Debug)]
+pub enum RuntimeValue {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    /// Valore di un'essence, con i campi nell'ordine di dichiarazione
+    Essence {
+        essence: String,
+        fields: Vec<(String, RuntimeValue)>,
+    },
+    /// Riferimento all'istanza di un being
+    Being {
+        realm: String,
+        being: String,
+    },
+    /// Hive con il tipo dei suoi elementi (`int` o `float`), memorizzati come `f64`
+    Hive {
+        element: Type,
+        hive: Hive,
+    },
+    Void,
+}
+
+impl std::fmt::Display for RuntimeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeValue::Integer(n) => write!(f, "{}", n),
+            RuntimeValue::Float(x) => write!(f, "{}", x),
+            RuntimeValue::String(s) => write!(f, "{}", s),
+            RuntimeValue::Boolean(b) => write!(f, "{}", b),
+            RuntimeValue::Essence { essence, fields } => {
+                write!(f, "{} {{", essence)?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    let separator = if i > 0 { "," } else { "" };
+                    write!(f, "{} {}: {}", separator, name, value)?;
+                }
+                write!(f, " }}")
+            }
+            RuntimeValue::Being { being, .. } => write!(f, "{}", being),
+            RuntimeValue::Hive { element, hive } => {
+                let element = |x: f64| RuntimeValue::hive_element(element, x);
+                write_hive(f, hive.dimensions(), &hive.data().map_err(|_| std::fmt::Error)?, &element)
+            }
+            RuntimeValue::Void => write!(f, "void"),
+        }
+    }
+}
+
+// Due hive sono uguali se hanno lo stesso tipo di elementi, la stessa forma e gli stessi
+// elementi; una hive il cui file non può essere letto non è uguale a nessun valore
+impl PartialEq for RuntimeValue {
+    fn eq(&self, other: &Self) -> bool {
+        use RuntimeValue::*;
+
+        match (self, other) {
+            (Integer(a), Integer(b)) => a == b,
+            (Float(a), Float(b)) => a == b,
+            (String(a), String(b)) => a == b,
+            (Boolean(a), Boolean(b)) => a == b,
+            (Essence { essence: a, fields: left }, Essence { essence: b, fields: right }) => a == b && left == right,
+            (Being { realm: r1, being: b1 }, Being { realm: r2, being: b2 }) => r1 == r2 && b1 == b2,
+            (Hive { element: a, hive: left }, Hive { element: b, hive: right }) => {
+                a == b && left.equals(right).unwrap_or(false)
+            }
+            (Void, Void) => true,
+            _ => false,
+        }
+    }
+}
+
+impl RuntimeValue {
+    /// Copia il valore. La copia di una hive letta a blocchi dal suo file contiene tutti i suoi
+    /// elementi, e fallisce se il file non può essere letto
+    pub fn try_clone(&self) -> Result<RuntimeValue, RuntimeError> {
+        Ok(match self {
+            RuntimeValue::Integer(n) => RuntimeValue::Integer(*n),
+            RuntimeValue::Float(x) => RuntimeValue::Float(*x),
+            RuntimeValue::String(s) => RuntimeValue::String(s.clone()),
+            RuntimeValue::Boolean(b) => RuntimeValue::Boolean(*b),
+            RuntimeValue::Essence { essence, fields } => RuntimeValue::Essence {
+                essence: essence.clone(),
+                fields: fields.iter()
+                    .map(|(name, value)| Ok((name.clone(), value.try_clone()?)))
+                    .collect::<Result<_, RuntimeError>>()?,
+            },
+            RuntimeValue::Being { realm, being } => RuntimeValue::Being { realm: realm.clone(), being: being.clone() },
+            RuntimeValue::Hive { element, hive } => RuntimeValue::Hive { element: element.clone(), hive: hive.try_clone()? },
+            RuntimeValue::Void => RuntimeValue::Void,
+        })
+    }
+
+    /// Nome del tipo Nervs corrispondente al valore
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RuntimeValue::Integer(_) => "int",
+            RuntimeValue::Float(_) => "float",
+            RuntimeValue::String(_) => "string",
+            RuntimeValue::Boolean(_) => "bool",
+            RuntimeValue::Essence { .. } => "essence",
+            RuntimeValue::Being { .. } => "being",
+            RuntimeValue::Hive { .. } => "hive",
+            RuntimeValue::Void => "void",
+        }
+    }
+
+    /// Valore di un elemento di una hive con elementi del tipo indicato
+    pub fn hive_element(element: &Type, value: f64) -> RuntimeValue {
+        match element {
+            Type::Integer => RuntimeValue::Integer(value as i64),
+            _ => RuntimeValue::Float(value),
+        }
+    }
+}
+
+// Stampa una hive come liste annidate, una per dimensione: `[[1, 2], [3, 4]]`
+fn write_hive(
+    f: &mut std::fmt::Formatter<'_>,
+    dimensions: &[usize],
+    data: &[f64],
+    element: &dyn Fn(f64) -> RuntimeValue,
+) -> std::fmt::Result {
+    let Some((&size, inner)) = dimensions.split_first() else {
+        return write!(f, "{}", element(data[0]));
+    };
+    let stride = inner.iter().product::<usize>();
+    write!(f, "[")?;
+    for i in 0..size {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write_hive(f, inner, &data[i * stride..(i + 1) * stride], element)?;
+    }
+    write!(f, "]")
+}
+
+impl NervsRuntime {
+    /// Inizializza il runtime da un programma Nervs con le opzioni predefinite
+    pub fn new(program: &Program) -> Result<Self, RuntimeError> {
+        Self::with_options(program, &RuntimeOptions::default())
+    }
+
+    /// Inizializza il runtime da un programma Nervs: apre i file delle hive persistenti, valuta
+    /// gli inizializzatori delle variabili di ogni being, in ordine di dichiarazione, ed esegue
+    /// il ritual `init` se presente
+    pub fn with_options(program: &Program, options: &RuntimeOptions) -> Result<Self, RuntimeError> {
+        Self::with_extensions(program, options, Extensions::new())
+    }
+
+    /// Come `with_options`, collegando le funzioni native alle estensioni dichiarate dal
+    /// programma; fallisce prima di eseguire qualsiasi codice se un'estensione non è collegata
+    pub fn with_extensions(
+        program: &Program,
+        options: &RuntimeOptions,
+        extensions: Extensions,
+    ) -> Result<Self, RuntimeError> {
+        let unbound = extensions.unbound(program);
+        if !unbound.is_empty() {
+            return Err(RuntimeError::UnboundExtensions(unbound));
+        }
+
+        let mut realms = HashMap::new();
+        
+        for realm in &program.realms {
+            let mut runtime_realm = RuntimeRealm {
+                essences: realm.essences.iter()
+                    .map(|essence| (essence.name.clone(), Rc::new(essence.clone())))
+                    .collect(),
+                beings: HashMap::new(),
+            };
+            
+            for being in &realm.beings {
+                let runtime_being = RuntimeBeing {
+                    variables: being.variables.iter()
+                        .map(|var| Ok((
+                            var.variable.name.clone(),
+                            being_variable(&realm.name, &being.name, &var.variable, options)?,
+                        )))
+                        .collect::<Result<_, RuntimeError>>()?,
+                    memory: being.variables.iter()
+                        .filter(|var| var.memory)
+                        .map(|var| (var.variable.name.clone(), var.variable.var_type.clone()))
+                        .collect(),
+                    restored: HashSet::new(),
+                    rituals: being.rituals.iter()
+                        .map(|ritual| (ritual.name.clone(), Rc::new(ritual.clone())))
+                        .collect(),
+                    perceptions: being.perceptions.iter()
+                        .map(|perception| (perception.name.clone(), Rc::new(perception.clone())))
+                        .collect(),
+                    extensions: being.extensions.iter()
+                        .map(|extension| (extension.name.clone(), Rc::new(extension.clone())))
+                        .collect(),
+                };
+                
+                runtime_realm.beings.insert(being.name.clone(), runtime_being);
+            }
+            
+            realms.insert(realm.name.clone(), runtime_realm);
+        }
+        
+        // Se il ripristino fallisce il runtime non esiste ancora, e l'istantanea resta intatta
+        memory::restore(&mut realms, &options.memory_directory)?;
+        let mut runtime = NervsRuntime { realms, options: options.clone(), events: VecDeque::new(), extensions };
+        for realm in &program.realms {
+            for being in &realm.beings {
+                runtime.initialize_being(&realm.name, being)?;
+            }
+        }
+        Ok(runtime)
+    }
+    
+    /// Scrive su disco le hive persistenti modificate e le istantanee dei blocchi `memory`;
+    /// avviene comunque quando il runtime viene distrutto, ma solo così si vedono gli errori
+    pub fn flush(&mut self) -> Result<(), RuntimeError> {
+        let beings = self.realms.values_mut().flat_map(|realm| realm.beings.values_mut());
+        for value in beings.flat_map(|being| being.variables.values_mut()) {
+            if let RuntimeValue::Hive { hive, .. } = value {
+                hive.flush()?;
+            }
+        }
+        memory::save(&self.realms, &self.options.memory_directory)
+    }
+
+    /// Legge il valore corrente di una variabile di un being
+    pub fn variable(&self, realm_name: &str, being_name: &str, variable_name: &str) -> Option<&RuntimeValue> {
+        self.realms.get(realm_name)?
+            .beings.get(being_name)?
+            .variables.get(variable_name)
+    }
+    
+    /// Esegue un ritual pubblico in un being specifico con gli argomenti indicati e ne restituisce
+    /// il risultato
+    pub fn execute_ritual(
+        &mut self,
+        realm_name: &str,
+        being_name: &str,
+        ritual_name: &str,
+        arguments: Vec<RuntimeValue>,
+    ) -> Result<RuntimeValue, RuntimeError> {
+        // Solo i ritual pubblici sono punti di ingresso
+        let ritual = self.lookup_ritual(realm_name, being_name, ritual_name)?;
+        if ritual.visibility == Visibility::Private {
+            return Err(RuntimeError::PrivateRitual(ritual_name.to_string(), being_name.to_string()));
+        }
+        self.call_ritual(realm_name, being_name, ritual_name, arguments, 0)
+    }
+}
+
+impl Drop for NervsRuntime {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+// Valore iniziale di una variabile di being: le hive persistenti vengono lette dal loro file,
+// `Realm.Being.variabile.hive`, che viene creato se non esiste
+fn being_variable(
+    realm: &str,
+    being: &str,
+    variable: &Variable,
+    options: &RuntimeOptions,
+) -> Result<RuntimeValue, RuntimeError> {
+    match &variable.var_type {
+        Type::Hive { element, dimensions, circular, persistent: true } => {
+            let file = format!("{}.{}.{}.{}", realm, being, variable.name, hive::storage::EXTENSION);
+            Ok(RuntimeValue::Hive {
+                element: (**element).clone(),
+                hive: Hive::open(&options.hive_directory.join(file), dimensions.clone(), *circular)?,
+            })
+        }
+        var_type => Ok(interpreter::initial_value(var_type)),
+    }
+}
+
+/// Inizializza il runtime del linguaggio
+pub fn initialize(program: &Program) -> Result<NervsRuntime, RuntimeError> {
+    NervsRuntime::new(program)
+}
+
+/// Inizializza il runtime del linguaggio con le opzioni indicate
+pub fn initialize_with(program: &Program, options: &RuntimeOptions) -> Result<NervsRuntime, RuntimeError> {
+    NervsRuntime::with_options(program, options)
 }
\ No newline at end of file
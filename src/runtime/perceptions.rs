@@ -0,0 +1,185 @@
+// Percezioni: eventi tipizzati ricevuti da un being e gestiti dal ritual con lo stesso nome
+//
+// Le percezioni ricevute con `perceive` vengono messe in coda e gestite da `dispatch`, una alla
+// volta e nell'ordine di arrivo, anche quando sono dirette a being diversi: un gestore termina
+// sempre prima che inizi quello della percezione successiva.
+
+use crate::ast::printer;
+use super::interpreter;
+use super::{NervsRuntime, RuntimeError, RuntimeValue};
+
+/// Percezione in attesa di essere gestita
+#[derive(Debug)]
+pub(super) struct Event {
+    realm: String,
+    being: String,
+    perception: String,
+    /// Argomenti già convertiti nei tipi dichiarati
+    arguments: Vec<RuntimeValue>,
+}
+
+impl NervsRuntime {
+    /// Mette in coda una percezione per un being, dopo averne controllato gli argomenti
+    /// rispetto alla dichiarazione nel blocco `perceptions`
+    pub fn perceive(
+        &mut self,
+        realm_name: &str,
+        being_name: &str,
+        perception_name: &str,
+        arguments: Vec<RuntimeValue>,
+    ) -> Result<(), RuntimeError> {
+        let runtime_realm = self.realms.get(realm_name)
+            .ok_or_else(|| RuntimeError::RealmNotFound(realm_name.to_string()))?;
+        let runtime_being = runtime_realm.beings.get(being_name)
+            .ok_or_else(|| RuntimeError::BeingNotFound(being_name.to_string(), realm_name.to_string()))?;
+        let perception = runtime_being.perceptions.get(perception_name)
+            .ok_or_else(|| RuntimeError::PerceptionNotFound(perception_name.to_string(), being_name.to_string()))?;
+
+        if arguments.len() != perception.parameters.len() {
+            return Err(RuntimeError::ArgumentCount {
+                ritual: perception_name.to_string(),
+                expected: perception.parameters.len(),
+                found: arguments.len(),
+            });
+        }
+        let mut converted = Vec::with_capacity(arguments.len());
+        for (param, value) in perception.parameters.iter().zip(arguments) {
+            converted.push(interpreter::coerce(value, &param.var_type).ok_or_else(|| RuntimeError::TypeError(
+                format!("argument '{}' of perception '{}' expects {}", param.name, perception_name, printer::type_name(&param.var_type))
+            ))?);
+        }
+
+        self.events.push_back(Event {
+            realm: realm_name.to_string(),
+            being: being_name.to_string(),
+            perception: perception_name.to_string(),
+            arguments: converted,
+        });
+        Ok(())
+    }
+
+    /// Gestisce le percezioni in coda nell'ordine di arrivo e restituisce quante ne ha gestite.
+    /// Se un gestore fallisce, la sua percezione viene scartata e le successive restano in coda
+    pub fn dispatch(&mut self) -> Result<usize, RuntimeError> {
+        let mut handled = 0;
+        while let Some(event) = self.events.pop_front() {
+            self.call_ritual(&event.realm, &event.being, &event.perception, event.arguments, 0)?;
+            handled += 1;
+        }
+        Ok(handled)
+    }
+
+    /// Numero di percezioni in attesa di essere gestite
+    pub fn pending_perceptions(&self) -> usize {
+        self.events.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer, parser};
+
+    const PROGRAM: &str = "
+        realm Sense {
+            being Sensor {
+                int log: 0;
+                string last: \"\";
+
+                perceptions {
+                    on_tick(n: int);
+                    on_message(text: string);
+                }
+
+                ritual on_tick(n: int) {
+                    log = log * 10 + 100 / n;
+                    Sense::Echo.record(n);
+                }
+
+                ritual on_message(text: string) {
+                    last = text;
+                }
+            }
+
+            being Echo {
+                public int log: 0;
+
+                perceptions {
+                    on_tick(n: int);
+                }
+
+                ritual on_tick(n: int) {
+                    log = log * 10 + n + 5;
+                }
+
+                public ritual record(n: int) {
+                    log = log * 10 + n;
+                }
+            }
+        }
+    ";
+
+    fn runtime() -> NervsRuntime {
+        let tokens = lexer::tokenize(PROGRAM).expect("the program is valid");
+        let (program, errors) = parser::parse(tokens);
+        assert!(errors.is_empty());
+        NervsRuntime::new(&program.expect("the program is valid")).unwrap()
+    }
+
+    fn variable(runtime: &NervsRuntime, being: &str, name: &str) -> RuntimeValue {
+        runtime.variable("Sense", being, name).unwrap().try_clone().unwrap()
+    }
+
+    #[test]
+    fn perceptions_are_handled_in_order() {
+        let mut runtime = runtime();
+        runtime.perceive("Sense", "Sensor", "on_tick", vec![RuntimeValue::Integer(50)]).unwrap();
+        runtime.perceive("Sense", "Echo", "on_tick", vec![RuntimeValue::Integer(3)]).unwrap();
+        runtime.perceive("Sense", "Sensor", "on_tick", vec![RuntimeValue::Integer(25)]).unwrap();
+        runtime.perceive("Sense", "Sensor", "on_message", vec![RuntimeValue::String("hi".to_string())]).unwrap();
+        assert_eq!(runtime.pending_perceptions(), 4);
+        assert_eq!(variable(&runtime, "Sensor", "log"), RuntimeValue::Integer(0));
+
+        assert_eq!(runtime.dispatch().unwrap(), 4);
+        assert_eq!(runtime.pending_perceptions(), 0);
+        // Sensor: 100 / 50 = 2, poi 100 / 25 = 4
+        assert_eq!(variable(&runtime, "Sensor", "log"), RuntimeValue::Integer(24));
+        // Echo: record(50), on_tick(3), record(25), ciascuno completato prima del successivo
+        assert_eq!(variable(&runtime, "Echo", "log"), RuntimeValue::Integer(50 * 100 + 8 * 10 + 25));
+        assert_eq!(variable(&runtime, "Sensor", "last"), RuntimeValue::String("hi".to_string()));
+    }
+
+    #[test]
+    fn failed_handlers_keep_the_rest_queued() {
+        let mut runtime = runtime();
+        runtime.perceive("Sense", "Sensor", "on_tick", vec![RuntimeValue::Integer(0)]).unwrap();
+        runtime.perceive("Sense", "Sensor", "on_tick", vec![RuntimeValue::Integer(10)]).unwrap();
+
+        assert!(matches!(runtime.dispatch(), Err(RuntimeError::DivisionByZero)));
+        assert_eq!(runtime.pending_perceptions(), 1);
+        assert_eq!(runtime.dispatch().unwrap(), 1);
+        assert_eq!(variable(&runtime, "Sensor", "log"), RuntimeValue::Integer(10));
+    }
+
+    #[test]
+    fn perceptions_are_checked_when_received() {
+        let mut runtime = runtime();
+        assert!(matches!(
+            runtime.perceive("Sense", "Echo", "on_message", vec![RuntimeValue::String("hi".to_string())]),
+            Err(RuntimeError::PerceptionNotFound(..))
+        ));
+        assert!(matches!(
+            runtime.perceive("Sense", "Sensor", "on_tick", vec![]),
+            Err(RuntimeError::ArgumentCount { expected: 1, found: 0, .. })
+        ));
+        assert!(matches!(
+            runtime.perceive("Sense", "Sensor", "on_tick", vec![RuntimeValue::Float(1.5)]),
+            Err(RuntimeError::TypeError(_))
+        ));
+        assert!(matches!(
+            runtime.perceive("Sense", "Ghost", "on_tick", vec![RuntimeValue::Integer(1)]),
+            Err(RuntimeError::BeingNotFound(..))
+        ));
+        assert_eq!(runtime.pending_perceptions(), 0);
+    }
+}
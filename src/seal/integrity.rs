@@ -1,8 +1,8 @@
-// Funzioni per l'integrità dei sigilli
-pub fn seal_program(_program: &crate::ast::nodes::Program) -> Result<(), Box<dyn std::error::Error>> {
-    Ok(()) // Da implementare
-}
-
-pub fn verify_seal(_program: &crate::ast::nodes::Program) -> Result<bool, Box<dyn std::error::Error>> {
-    Ok(true) // Da implementare
+// Funzioni per l'integrità dei sigilli
+pub fn seal_program(_program: &crate::ast::nodes::Program) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(()) // Da implementare
+}
+
+pub fn verify_seal(_program: &crate::ast::nodes::Program) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(true) // Da implementare
 }
\ No newline at end of file
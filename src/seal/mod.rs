@@ -1,6 +1,6 @@
-pub mod integrity;
-
-// Placeholder per le funzioni dei sigilli
-pub fn apply_seals(_program: &crate::ast::nodes::Program) -> Result<(), Box<dyn std::error::Error>> {
-    Ok(()) // Da implementare
+pub mod integrity;
+
+// Placeholder per le funzioni dei sigilli
+pub fn apply_seals(_program: &crate::ast::nodes::Program) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(()) // Da implementare
 }
\ No newline at end of file
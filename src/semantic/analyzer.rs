@@ -1,512 +1,1574 @@
-use std::collections::{HashMap, HashSet};
-use crate::ast::nodes::{Program, Realm, Being, Ritual, Statement, Expression, Type, Literal, Variable};
-use crate::semantic::SemanticError;
-
-// Struttura per tenere traccia dell'ambiente semantico
-pub struct SemanticContext {
-    // Tabella dei simboli per ogni realm, being e scope locale
-    realm_table: HashMap<String, RealmInfo>,
-    current_realm: Option<String>,
-    current_being: Option<String>,
-    current_ritual: Option<String>,
-    // Tiene traccia dello scope attuale
-    scope_stack: Vec<HashMap<String, Type>>,
-}
-
-// Informazioni sul realm
-struct RealmInfo {
-    beings: HashMap<String, BeingInfo>,
-}
-
-// Informazioni sul being
-struct BeingInfo {
-    variables: HashMap<String, Type>,
-    rituals: HashMap<String, RitualInfo>,
-}
-
-// Informazioni sul ritual
-struct RitualInfo {
-    parameters: Vec<Variable>,
-    return_type: Type,
-}
-
-impl SemanticContext {
-    // Crea un nuovo contesto semantico
-    pub fn new() -> Self {
-        SemanticContext {
-            realm_table: HashMap::new(),
-            current_realm: None,
-            current_being: None,
-            current_ritual: None,
-            scope_stack: vec![HashMap::new()],
-        }
-    }
-
-    // Entra in un nuovo scope
-    pub fn enter_scope(&mut self) {
-        self.scope_stack.push(HashMap::new());
-    }
-
-    // Esci dallo scope corrente
-    pub fn exit_scope(&mut self) {
-        self.scope_stack.pop();
-    }
-
-    // Aggiungi una variabile allo scope corrente
-    pub fn add_variable(&mut self, name: &str, var_type: Type) -> Result<(), SemanticError> {
-        let scope = self.scope_stack.last_mut().unwrap();
-        
-        if scope.contains_key(name) {
-            return Err(SemanticError::Generic(format!("Variable '{}' already defined in this scope", name)));
-        }
-        
-        scope.insert(name.to_string(), var_type);
-        Ok(())
-    }
-
-    // Cerca una variabile negli scope, partendo dal più interno
-    pub fn lookup_variable(&self, name: &str) -> Option<&Type> {
-        for scope in self.scope_stack.iter().rev() {
-            if let Some(var_type) = scope.get(name) {
-                return Some(var_type);
-            }
-        }
-        
-        // Se non trovata negli scope locali, cerca nelle variabili del being
-        if let Some(realm) = &self.current_realm {
-            if let Some(being) = &self.current_being {
-                if let Some(realm_info) = self.realm_table.get(realm) {
-                    if let Some(being_info) = realm_info.beings.get(being) {
-                        if let Some(var_type) = being_info.variables.get(name) {
-                            return Some(var_type);
-                        }
-                    }
-                }
-            }
-        }
-        
-        None
-    }
-
-    // Aggiungi un realm al contesto
-    pub fn add_realm(&mut self, name: &str) -> Result<(), SemanticError> {
-        if self.realm_table.contains_key(name) {
-            return Err(SemanticError::Generic(format!("Realm '{}' already defined", name)));
-        }
-        
-        self.realm_table.insert(name.to_string(), RealmInfo {
-            beings: HashMap::new(),
-        });
-        
-        self.current_realm = Some(name.to_string());
-        Ok(())
-    }
-
-    // Aggiungi un being al realm corrente
-    pub fn add_being(&mut self, name: &str) -> Result<(), SemanticError> {
-        if let Some(realm) = &self.current_realm {
-            if let Some(realm_info) = self.realm_table.get_mut(realm) {
-                if realm_info.beings.contains_key(name) {
-                    return Err(SemanticError::Generic(
-                        format!("Being '{}' already defined in realm '{}'", name, realm)
-                    ));
-                }
-                
-                realm_info.beings.insert(name.to_string(), BeingInfo {
-                    variables: HashMap::new(),
-                    rituals: HashMap::new(),
-                });
-                
-                self.current_being = Some(name.to_string());
-                return Ok(());
-            }
-        }
-        
-        Err(SemanticError::Generic("No current realm".to_string()))
-    }
-
-    // Aggiungi una variabile al being corrente
-    pub fn add_being_variable(&mut self, var: &Variable) -> Result<(), SemanticError> {
-        if let Some(realm) = &self.current_realm {
-            if let Some(being) = &self.current_being {
-                if let Some(realm_info) = self.realm_table.get_mut(realm) {
-                    if let Some(being_info) = realm_info.beings.get_mut(being) {
-                        if being_info.variables.contains_key(&var.name) {
-                            return Err(SemanticError::Generic(
-                                format!("Variable '{}' already defined in being '{}'", var.name, being)
-                            ));
-                        }
-                        
-                        being_info.variables.insert(var.name.clone(), var.var_type.clone());
-                        return Ok(());
-                    }
-                }
-            }
-        }
-        
-        Err(SemanticError::Generic("No current being".to_string()))
-    }
-
-    // Aggiungi un ritual al being corrente
-    pub fn add_ritual(&mut self, ritual: &Ritual) -> Result<(), SemanticError> {
-        if let Some(realm) = &self.current_realm {
-            if let Some(being) = &self.current_being {
-                if let Some(realm_info) = self.realm_table.get_mut(realm) {
-                    if let Some(being_info) = realm_info.beings.get_mut(being) {
-                        if being_info.rituals.contains_key(&ritual.name) {
-                            return Err(SemanticError::Generic(
-                                format!("Ritual '{}' already defined in being '{}'", ritual.name, being)
-                            ));
-                        }
-                        
-                        // Verifica che non ci siano nomi duplicati nei parametri
-                        let mut param_names = HashSet::new();
-                        for param in &ritual.parameters {
-                            if !param_names.insert(&param.name) {
-                                return Err(SemanticError::Generic(
-                                    format!("Duplicate parameter name '{}' in ritual '{}'", param.name, ritual.name)
-                                ));
-                            }
-                        }
-                        
-                        being_info.rituals.insert(ritual.name.clone(), RitualInfo {
-                            parameters: ritual.parameters.clone(),
-                            return_type: ritual.return_type.clone(),
-                        });
-                        
-                        self.current_ritual = Some(ritual.name.clone());
-                        return Ok(());
-                    }
-                }
-            }
-        }
-        
-        Err(SemanticError::Generic("No current being".to_string()))
-    }
-
-    // Verifica che una chiamata a ritual sia valida
-    pub fn check_ritual_call(&self, name: &str, args: &[Expression]) -> Result<Type, SemanticError> {
-        if let Some(realm) = &self.current_realm {
-            if let Some(being) = &self.current_being {
-                if let Some(realm_info) = self.realm_table.get(realm) {
-                    if let Some(being_info) = realm_info.beings.get(being) {
-                        if let Some(ritual_info) = being_info.rituals.get(name) {
-                            // Verifica che il numero di argomenti corrisponda
-                            if args.len() != ritual_info.parameters.len() {
-                                return Err(SemanticError::Generic(
-                                    format!(
-                                        "Ritual '{}' expects {} arguments, but {} were provided",
-                                        name, ritual_info.parameters.len(), args.len()
-                                    )
-                                ));
-                            }
-                            
-                            // Verifica il tipo di ogni argomento
-                            for (_i, (arg, param)) in args.iter().zip(&ritual_info.parameters).enumerate() {
-                                let arg_type = self.infer_expression_type(arg)?;
-                                if !self.types_compatible(&arg_type, &param.var_type) {
-                                    return Err(SemanticError::TypeMismatch {
-                                        expected: format!("{:?}", param.var_type),
-                                        found: format!("{:?}", arg_type),
-                                    });
-                                }
-                            }
-                            
-                            return Ok(ritual_info.return_type.clone());
-                        }
-                    }
-                }
-            }
-        }
-        
-        Err(SemanticError::UndefinedRitual(name.to_string()))
-    }
-
-    // Inferisci il tipo di un'espressione
-    pub fn infer_expression_type(&self, expr: &Expression) -> Result<Type, SemanticError> {
-        match expr {
-            Expression::Literal(lit) => {
-                match lit {
-                    Literal::Integer(_) => Ok(Type::Integer),
-                    Literal::Float(_) => Ok(Type::Float),
-                    Literal::String(_) => Ok(Type::String),
-                    Literal::Boolean(_) => Ok(Type::Boolean),
-                }
-            },
-            Expression::Variable(name) => {
-                if let Some(var_type) = self.lookup_variable(name) {
-                    Ok(var_type.clone())
-                } else {
-                    Err(SemanticError::UndefinedVariable(name.clone()))
-                }
-            },
-            Expression::BinaryOperation { left, operator, right } => {
-                let left_type = self.infer_expression_type(left)?;
-                let right_type = self.infer_expression_type(right)?;
-                
-                // Tipizzazione delle operazioni binarie
-                match operator {
-                    // Operazioni aritmetiche
-                    crate::ast::nodes::BinaryOperator::Add |
-                    crate::ast::nodes::BinaryOperator::Subtract |
-                    crate::ast::nodes::BinaryOperator::Multiply |
-                    crate::ast::nodes::BinaryOperator::Divide => {
-                        if matches!(left_type, Type::Integer | Type::Float) &&
-                           matches!(right_type, Type::Integer | Type::Float) {
-                            // Promozione di tipo: se uno è float, il risultato è float
-                            if matches!(left_type, Type::Float) || matches!(right_type, Type::Float) {
-                                Ok(Type::Float)
-                            } else {
-                                Ok(Type::Integer)
-                            }
-                        } else {
-                            Err(SemanticError::TypeMismatch {
-                                expected: "numeric type".to_string(),
-                                found: format!("{:?} and {:?}", left_type, right_type),
-                            })
-                        }
-                    },
-                    // Operazioni di confronto
-                    crate::ast::nodes::BinaryOperator::Equal |
-                    crate::ast::nodes::BinaryOperator::NotEqual => {
-                        // Confronto di uguaglianza permesso tra tipi compatibili
-                        if self.types_compatible(&left_type, &right_type) {
-                            Ok(Type::Boolean)
-                        } else {
-                            Err(SemanticError::TypeMismatch {
-                                expected: format!("{:?}", left_type),
-                                found: format!("{:?}", right_type),
-                            })
-                        }
-                    },
-                    crate::ast::nodes::BinaryOperator::LessThan |
-                    crate::ast::nodes::BinaryOperator::GreaterThan => {
-                        // Confronto di ordine permesso solo tra numeri
-                        if matches!(left_type, Type::Integer | Type::Float) &&
-                           matches!(right_type, Type::Integer | Type::Float) {
-                            Ok(Type::Boolean)
-                        } else {
-                            Err(SemanticError::TypeMismatch {
-                                expected: "numeric type".to_string(),
-                                found: format!("{:?} and {:?}", left_type, right_type),
-                            })
-                        }
-                    },
-                }
-            },
-            Expression::FunctionCall { name, arguments } => {
-                self.check_ritual_call(name, arguments)
-            },
-        }
-    }
-
-    // Verifica la compatibilità tra due tipi
-    pub fn types_compatible(&self, t1: &Type, t2: &Type) -> bool {
-        // Regole di compatibilità base
-        if t1 == t2 {
-            return true;
-        }
-        
-        // Si potrebbero aggiungere altre regole di compatibilità
-        // ad esempio Integer e Float potrebbero essere compatibili in certi contesti
-        
-        false
-    }
-}
-
-// Funzione principale di analisi semantica
-pub fn analyze_program(program: &Program) -> Result<(), SemanticError> {
-    let mut context = SemanticContext::new();
-    
-    // Analizza tutti i realm
-    for realm in &program.realms {
-        analyze_realm(&mut context, realm)?;
-    }
-    
-    Ok(())
-}
-
-// Analizza un realm
-fn analyze_realm(context: &mut SemanticContext, realm: &Realm) -> Result<(), SemanticError> {
-    context.add_realm(&realm.name)?;
-    
-    // Analizza tutti i being nel realm
-    for being in &realm.beings {
-        analyze_being(context, being)?;
-    }
-    
-    Ok(())
-}
-
-// Analizza un being
-fn analyze_being(context: &mut SemanticContext, being: &Being) -> Result<(), SemanticError> {
-    context.add_being(&being.name)?;
-    
-    // Aggiungi tutte le variabili del being
-    for var in &being.variables {
-        context.add_being_variable(var)?;
-    }
-    
-    // Analizza tutti i ritual nel being
-    for ritual in &being.rituals {
-        analyze_ritual(context, ritual)?;
-    }
-    
-    Ok(())
-}
-
-// Analizza un ritual
-fn analyze_ritual(context: &mut SemanticContext, ritual: &Ritual) -> Result<(), SemanticError> {
-    context.add_ritual(ritual)?;
-    
-    // Crea un nuovo scope per i parametri e il corpo del ritual
-    context.enter_scope();
-    
-    // Aggiungi i parametri allo scope locale
-    for param in &ritual.parameters {
-        context.add_variable(&param.name, param.var_type.clone())?;
-    }
-    
-    // Analizza il corpo del ritual
-    let mut has_return = false;
-    for stmt in &ritual.body {
-        if let Statement::Return(_) = stmt {
-            has_return = true;
-        }
-        analyze_statement(context, stmt, &ritual.return_type)?;
-    }
-    
-    // Verifica che un ritual non void abbia un return
-    if !matches!(ritual.return_type, Type::Void) && !has_return {
-        return Err(SemanticError::Generic(
-            format!("Ritual '{}' must return a value of type {:?}", ritual.name, ritual.return_type)
-        ));
-    }
-    
-    // Esci dallo scope del ritual
-    context.exit_scope();
-    
-    Ok(())
-}
-
-// Analizza uno statement
-fn analyze_statement(context: &mut SemanticContext, stmt: &Statement, expected_return_type: &Type) -> Result<(), SemanticError> {
-    match stmt {
-        Statement::VariableDeclaration { variable, initializer } => {
-            // Se c'è un initializer, verifica che il tipo sia compatibile
-            if let Some(init) = initializer {
-                let init_type = context.infer_expression_type(init)?;
-                if !context.types_compatible(&init_type, &variable.var_type) {
-                    return Err(SemanticError::TypeMismatch {
-                        expected: format!("{:?}", variable.var_type),
-                        found: format!("{:?}", init_type),
-                    });
-                }
-            }
-            
-            // Aggiungi la variabile allo scope corrente
-            context.add_variable(&variable.name, variable.var_type.clone())?;
-            Ok(())
-        },
-        Statement::Assignment { name, value } => {
-            // Verifica che la variabile esista
-            let var_type = context.lookup_variable(name)
-                .ok_or_else(|| SemanticError::UndefinedVariable(name.clone()))?;
-            
-            // Verifica che il tipo del valore sia compatibile
-            let value_type = context.infer_expression_type(value)?;
-            if !context.types_compatible(&value_type, var_type) {
-                return Err(SemanticError::TypeMismatch {
-                    expected: format!("{:?}", var_type),
-                    found: format!("{:?}", value_type),
-                });
-            }
-            
-            Ok(())
-        },
-        Statement::RitualCall { name, arguments } => {
-            // Verifica che la chiamata al ritual sia valida
-            let _ = context.check_ritual_call(name, arguments)?;
-            Ok(())
-        },
-        Statement::Conditional { condition, true_branch, false_branch } => {
-            // Verifica che la condizione sia booleana
-            let condition_type = context.infer_expression_type(condition)?;
-            if !matches!(condition_type, Type::Boolean) {
-                return Err(SemanticError::TypeMismatch {
-                    expected: "Boolean".to_string(),
-                    found: format!("{:?}", condition_type),
-                });
-            }
-            
-            // Analizza i branch
-            context.enter_scope();
-            for stmt in true_branch {
-                analyze_statement(context, stmt, expected_return_type)?;
-            }
-            context.exit_scope();
-            
-            if let Some(false_stmts) = false_branch {
-                context.enter_scope();
-                for stmt in false_stmts {
-                    analyze_statement(context, stmt, expected_return_type)?;
-                }
-                context.exit_scope();
-            }
-            
-            Ok(())
-        },
-        Statement::Cycle { condition, body } => {
-            // Se c'è una condizione, verifica che sia booleana
-            if let Some(cond) = condition {
-                let condition_type = context.infer_expression_type(cond)?;
-                if !matches!(condition_type, Type::Boolean) {
-                    return Err(SemanticError::TypeMismatch {
-                        expected: "Boolean".to_string(),
-                        found: format!("{:?}", condition_type),
-                    });
-                }
-            }
-            
-            // Analizza il corpo del ciclo
-            context.enter_scope();
-            for stmt in body {
-                analyze_statement(context, stmt, expected_return_type)?;
-            }
-            context.exit_scope();
-            
-            Ok(())
-        },
-        Statement::Return(expr_opt) => {
-            match (expr_opt, expected_return_type) {
-                // Return senza espressione per tipo void
-                (None, Type::Void) => Ok(()),
-                
-                // Return con espressione per tipo non void
-                (Some(expr), _) => {
-                    let expr_type = context.infer_expression_type(expr)?;
-                    if !context.types_compatible(&expr_type, expected_return_type) {
-                        return Err(SemanticError::TypeMismatch {
-                            expected: format!("{:?}", expected_return_type),
-                            found: format!("{:?}", expr_type),
-                        });
-                    }
-                    Ok(())
-                },
-                
-                // Return senza espressione per tipo non void
-                (None, _) => {
-                    Err(SemanticError::TypeMismatch {
-                        expected: format!("{:?}", expected_return_type),
-                        found: "void".to_string(),
-                    })
-                },
-            }
-        },
-    }
-}
-
-// Funzione principale che analizza l'intero programma
-pub fn analyze(program: &Program) -> Result<(), Box<dyn std::error::Error>> {
-    analyze_program(program).map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
-}
\ No newline at end of file
+use std::collections::{HashMap, HashSet};
+use crate::ast::nodes::{Program, Realm, Essence, Being, BeingVariable, Extension, Ritual, Statement, Expression, FieldInit, Type, Literal, UnaryOperator, Variable, Visibility, Span};
+use crate::ast::printer;
+use crate::semantic::{control_flow, initialization, suggestions, SemanticError};
+use crate::diagnostics::Diagnostic;
+
+// Struttura per tenere traccia dell'ambiente semantico
+pub struct SemanticContext {
+    // Tabella dei simboli per ogni realm, being e scope locale
+    realm_table: HashMap<String, RealmInfo>,
+    current_realm: Option<String>,
+    current_being: Option<String>,
+    current_ritual: Option<String>,
+    // Tiene traccia dello scope attuale
+    scope_stack: Vec<HashMap<String, Type>>,
+    // Errori e warning raccolti durante l'analisi
+    diagnostics: Vec<Diagnostic>,
+}
+
+// Informazioni sul realm
+struct RealmInfo {
+    essences: HashMap<String, EssenceInfo>,
+    beings: HashMap<String, BeingInfo>,
+    // Posizione della dichiarazione registrata, per riconoscere i duplicati
+    span: Span,
+}
+
+// Informazioni sull'essence
+struct EssenceInfo {
+    span: Span,
+    // Campi nell'ordine di dichiarazione
+    fields: Vec<(String, Type)>,
+}
+
+// Informazioni sul being
+struct BeingInfo {
+    span: Span,
+    variables: HashMap<String, VariableInfo>,
+    rituals: HashMap<String, RitualInfo>,
+}
+
+// Informazioni su una variabile del being
+struct VariableInfo {
+    var_type: Type,
+    visibility: Visibility,
+}
+
+// Tipi primitivi, nell'ordine in cui vengono proposti come suggerimento
+const PRIMITIVE_TYPES: [&str; 5] = ["int", "float", "string", "bool", "void"];
+
+// Informazioni sul ritual
+struct RitualInfo {
+    parameters: Vec<Variable>,
+    return_type: Type,
+    visibility: Visibility,
+}
+
+impl RealmInfo {
+    // Nomi delle essence e dei being del realm
+    fn type_names(&self) -> impl Iterator<Item = &str> {
+        self.essences.keys().chain(self.beings.keys()).map(String::as_str)
+    }
+}
+
+impl Default for SemanticContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SemanticContext {
+    // Crea un nuovo contesto semantico
+    pub fn new() -> Self {
+        SemanticContext {
+            realm_table: HashMap::new(),
+            current_realm: None,
+            current_being: None,
+            current_ritual: None,
+            scope_stack: vec![HashMap::new()],
+            diagnostics: Vec::new(),
+        }
+    }
+
+    // Entra in un nuovo scope
+    pub fn enter_scope(&mut self) {
+        self.scope_stack.push(HashMap::new());
+    }
+
+    // Esci dallo scope corrente
+    pub fn exit_scope(&mut self) {
+        self.scope_stack.pop();
+    }
+
+    // Aggiungi una variabile allo scope corrente
+    pub fn add_variable(&mut self, name: &str, var_type: Type, span: Span) -> Result<(), SemanticError> {
+        let scope = self.scope_stack.last_mut().unwrap();
+        
+        if scope.contains_key(name) {
+            return Err(SemanticError::Generic(format!("Variable '{}' already defined in this scope", name), span));
+        }
+        
+        scope.insert(name.to_string(), var_type);
+        Ok(())
+    }
+
+    // Cerca una variabile negli scope, partendo dal più interno
+    pub fn lookup_variable(&self, name: &str) -> Option<&Type> {
+        for scope in self.scope_stack.iter().rev() {
+            if let Some(var_type) = scope.get(name) {
+                return Some(var_type);
+            }
+        }
+        
+        // Se non trovata negli scope locali, cerca nelle variabili del being
+        if let Some(realm) = &self.current_realm {
+            if let Some(being) = &self.current_being {
+                if let Some(realm_info) = self.realm_table.get(realm) {
+                    if let Some(being_info) = realm_info.beings.get(being) {
+                        if let Some(info) = being_info.variables.get(name) {
+                            return Some(&info.var_type);
+                        }
+                    }
+                }
+            }
+        }
+        
+        None
+    }
+
+    // Aggiungi un realm al contesto
+    pub fn add_realm(&mut self, name: &str, span: Span) -> Result<(), SemanticError> {
+        if let Some(previous) = self.realm_table.get(name) {
+            return Err(SemanticError::DuplicateRealm {
+                name: name.to_string(),
+                span,
+                previous: previous.span.clone(),
+            });
+        }
+        
+        self.realm_table.insert(name.to_string(), RealmInfo {
+            essences: HashMap::new(),
+            beings: HashMap::new(),
+            span: span.clone(),
+        });
+        
+        self.current_realm = Some(name.to_string());
+        Ok(())
+    }
+
+    // Aggiungi un being al realm corrente
+    pub fn add_being(&mut self, name: &str, span: Span) -> Result<(), SemanticError> {
+        if let Some(realm) = &self.current_realm {
+            if let Some(realm_info) = self.realm_table.get_mut(realm) {
+                if realm_info.beings.contains_key(name) {
+                    return Err(SemanticError::Generic(
+                        format!("Being '{}' already defined in realm '{}'", name, realm),
+                        span
+                    ));
+                }
+                if realm_info.essences.contains_key(name) {
+                    return Err(SemanticError::Generic(
+                        format!("Type '{}' already defined in realm '{}'", name, realm),
+                        span
+                    ));
+                }
+                
+                realm_info.beings.insert(name.to_string(), BeingInfo {
+                    span: span.clone(),
+                    variables: HashMap::new(),
+                    rituals: HashMap::new(),
+                });
+                
+                self.current_being = Some(name.to_string());
+                return Ok(());
+            }
+        }
+        
+        Err(SemanticError::Generic("No current realm".to_string(), span))
+    }
+
+    // Aggiungi un'essence al realm corrente; i campi vengono registrati dopo, con `add_essence_fields`
+    pub fn add_essence(&mut self, name: &str, span: Span) -> Result<(), SemanticError> {
+        let Some(realm) = &self.current_realm else {
+            return Err(SemanticError::Generic("No current realm".to_string(), span));
+        };
+        let Some(realm_info) = self.realm_table.get_mut(realm) else {
+            return Err(SemanticError::Generic("No current realm".to_string(), span));
+        };
+        
+        // Essence e being condividono lo spazio dei nomi dei tipi
+        if realm_info.essences.contains_key(name) || realm_info.beings.contains_key(name) {
+            return Err(SemanticError::Generic(
+                format!("Type '{}' already defined in realm '{}'", name, realm),
+                span
+            ));
+        }
+        
+        realm_info.essences.insert(name.to_string(), EssenceInfo { span, fields: Vec::new() });
+        Ok(())
+    }
+    
+    // Registra i campi di un'essence già aggiunta, risolvendone i tipi
+    fn add_essence_fields(&mut self, essence: &Essence) {
+        let mut fields: Vec<(String, Type)> = Vec::new();
+        for field in &essence.fields {
+            self.reject_persistent(&field.var_type, &field.type_span, "essence fields");
+            let field_type = self.resolve_type(&field.var_type, &field.type_span);
+            if fields.iter().any(|(name, _)| *name == field.name) {
+                self.report(SemanticError::Generic(
+                    format!("Field '{}' already defined in essence '{}'", field.name, essence.name),
+                    field.span.clone()
+                ));
+                continue;
+            }
+            fields.push((field.name.clone(), field_type));
+        }
+        
+        let registered = self.current_realm.as_ref()
+            .and_then(|realm| self.realm_table.get_mut(realm))
+            .and_then(|realm_info| realm_info.essences.get_mut(&essence.name))
+            .filter(|info| info.span == essence.span);
+        if let Some(info) = registered {
+            info.fields = fields;
+        }
+    }
+    
+    // Aggiungi una variabile al being corrente
+    pub fn add_being_variable(&mut self, var: &Variable, visibility: Visibility, span: Span) -> Result<(), SemanticError> {
+        if let Some(realm) = &self.current_realm {
+            if let Some(being) = &self.current_being {
+                if let Some(realm_info) = self.realm_table.get_mut(realm) {
+                    if let Some(being_info) = realm_info.beings.get_mut(being) {
+                        if being_info.variables.contains_key(&var.name) {
+                            return Err(SemanticError::Generic(
+                                format!("Variable '{}' already defined in being '{}'", var.name, being),
+                                span
+                            ));
+                        }
+                        
+                        being_info.variables.insert(var.name.clone(), VariableInfo {
+                            var_type: var.var_type.clone(),
+                            visibility,
+                        });
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        
+        Err(SemanticError::Generic("No current being".to_string(), span))
+    }
+
+    // Aggiungi un ritual al being corrente
+    pub fn add_ritual(&mut self, ritual: &Ritual) -> Result<(), SemanticError> {
+        self.add_signature(
+            &ritual.name, &ritual.parameters, &ritual.return_type, &ritual.return_type_span,
+            ritual.visibility, &ritual.span,
+        )?;
+        self.current_ritual = Some(ritual.name.clone());
+        Ok(())
+    }
+
+    // Aggiungi un'estensione al being corrente: si chiama come un ritual, ma è fornita dall'host
+    pub fn add_extension(&mut self, extension: &Extension) -> Result<(), SemanticError> {
+        self.add_signature(
+            &extension.name, &extension.parameters, &extension.return_type, &extension.return_type_span,
+            extension.visibility, &extension.span,
+        )
+    }
+
+    fn add_signature(
+        &mut self,
+        name: &str,
+        parameters: &[Variable],
+        return_type: &Type,
+        return_type_span: &Span,
+        visibility: Visibility,
+        span: &Span,
+    ) -> Result<(), SemanticError> {
+        for param in parameters {
+            self.reject_persistent(&param.var_type, &param.type_span, "parameters");
+        }
+        self.reject_persistent(return_type, return_type_span, "return types");
+        // I tipi della firma vengono risolti una sola volta, qui
+        let resolved: Vec<Variable> = parameters.iter()
+            .map(|param| Variable { var_type: self.resolve_type(&param.var_type, &param.type_span), ..param.clone() })
+            .collect();
+        let return_type = self.resolve_type(return_type, return_type_span);
+        
+        if let Some(realm) = &self.current_realm {
+            if let Some(being) = &self.current_being {
+                if let Some(realm_info) = self.realm_table.get_mut(realm) {
+                    if let Some(being_info) = realm_info.beings.get_mut(being) {
+                        if being_info.rituals.contains_key(name) {
+                            return Err(SemanticError::Generic(
+                                format!("Ritual '{}' already defined in being '{}'", name, being),
+                                span.clone()
+                            ));
+                        }
+                        
+                        // Verifica che non ci siano nomi duplicati nei parametri
+                        let mut param_names = HashSet::new();
+                        for param in parameters {
+                            if !param_names.insert(&param.name) {
+                                return Err(SemanticError::Generic(
+                                    format!("Duplicate parameter name '{}' in ritual '{}'", param.name, name),
+                                    param.span.clone()
+                                ));
+                            }
+                        }
+                        
+                        being_info.rituals.insert(name.to_string(), RitualInfo {
+                            parameters: resolved,
+                            return_type,
+                            visibility,
+                        });
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        
+        Err(SemanticError::Generic("No current being".to_string(), span.clone()))
+    }
+
+    // Rende corrente un realm già registrato; fallisce se la dichiarazione è un duplicato
+    fn enter_realm(&mut self, realm: &Realm) -> bool {
+        let registered = self.realm_table.get(&realm.name)
+            .is_some_and(|info| info.span == realm.span);
+        self.current_realm = registered.then(|| realm.name.clone());
+        self.current_being = None;
+        registered
+    }
+
+    // Rende corrente un being già registrato nel realm corrente; fallisce se è un duplicato
+    fn enter_being(&mut self, being: &Being) -> bool {
+        let registered = self.current_realm.as_ref()
+            .and_then(|realm| self.realm_table.get(realm))
+            .and_then(|realm_info| realm_info.beings.get(&being.name))
+            .is_some_and(|info| info.span == being.span);
+        self.current_being = registered.then(|| being.name.clone());
+        self.current_ritual = None;
+        registered
+    }
+
+    // Solo le variabili di un being sono collegate a un file: una hive `persistent` in qualsiasi
+    // altra posizione verrebbe creata in memoria e andrebbe persa
+    fn reject_persistent(&mut self, declared: &Type, span: &Span, position: &str) {
+        if let Type::Hive { persistent: true, .. } = declared {
+            self.report(SemanticError::Generic(
+                format!("{} cannot be persistent: only being variables are stored in files", position),
+                span.clone()
+            ));
+        }
+    }
+
+    // Risolve un tipo scritto nel sorgente; un tipo sconosciuto viene segnalato e diventa Type::Error
+    pub fn resolve_type(&mut self, declared: &Type, span: &Span) -> Type {
+        let resolved = self.lookup_type(declared);
+        if let (Type::Custom(name), Type::Error) = (declared, &resolved) {
+            // I tipi qualificati vengono confrontati con quelli del realm indicato
+            let candidates: Vec<String> = match name.split_once("::") {
+                Some((realm, _)) => self.realm_table.get(realm).into_iter()
+                    .flat_map(|info| info.type_names().map(|type_name| format!("{}::{}", realm, type_name)))
+                    .collect(),
+                None => PRIMITIVE_TYPES.into_iter()
+                    .chain(self.current_realm_info().into_iter().flat_map(RealmInfo::type_names))
+                    .map(str::to_string)
+                    .collect(),
+            };
+            let suggestion = suggestions::closest_match(name, candidates.iter().map(String::as_str)).map(str::to_string);
+            self.report(SemanticError::UnknownType { name: name.clone(), span: span.clone(), suggestion });
+        }
+        if let (Type::Hive { element, .. }, Type::Error) = (declared, &resolved) {
+            self.report(SemanticError::Generic(
+                format!("hive elements must be int or float, found {}", printer::type_name(element)),
+                span.clone()
+            ));
+        }
+        resolved
+    }
+    
+    // Come `resolve_type`, senza segnalare errori: usato per i tipi già risolti nella prima passata.
+    // I tipi definiti dall'utente diventano `Realm::Nome`, così restano univoci tra realm diversi
+    fn lookup_type(&self, declared: &Type) -> Type {
+        match declared {
+            Type::Custom(name) if self.lookup_being(name).is_none() && self.lookup_essence(name).is_none() => Type::Error,
+            Type::Custom(name) => Type::Custom(self.qualified_name(name)),
+            // Gli elementi sono memorizzati come numeri in virgola mobile
+            Type::Hive { element, .. } if !matches!(**element, Type::Integer | Type::Float) => Type::Error,
+            other => other.clone(),
+        }
+    }
+    
+    fn current_realm_info(&self) -> Option<&RealmInfo> {
+        self.realm_table.get(self.current_realm.as_ref()?)
+    }
+    
+    // Separa un nome `Realm::Nome`; un nome senza realm appartiene al realm corrente
+    fn split_name<'a>(&'a self, name: &'a str) -> Option<(&'a str, &'a str)> {
+        match name.split_once("::") {
+            Some(parts) => Some(parts),
+            None => Some((self.current_realm.as_deref()?, name)),
+        }
+    }
+    
+    fn qualified_name(&self, name: &str) -> String {
+        match self.split_name(name) {
+            Some((realm, name)) => format!("{}::{}", realm, name),
+            None => name.to_string(),
+        }
+    }
+    
+    // Nome da mostrare nei messaggi: il realm viene omesso per i tipi del realm corrente
+    fn display_name<'a>(&self, name: &'a str) -> &'a str {
+        match name.split_once("::") {
+            Some((realm, short)) if self.current_realm.as_deref() == Some(realm) => short,
+            _ => name,
+        }
+    }
+    
+    // Cerca un being per nome, eventualmente qualificato con il realm
+    fn lookup_being(&self, name: &str) -> Option<&BeingInfo> {
+        let (realm, being) = self.split_name(name)?;
+        self.realm_table.get(realm)?.beings.get(being)
+    }
+    
+    // Cerca un'essence per nome, eventualmente qualificato con il realm
+    fn lookup_essence(&self, name: &str) -> Option<&EssenceInfo> {
+        let (realm, essence) = self.split_name(name)?;
+        self.realm_table.get(realm)?.essences.get(essence)
+    }
+    
+    // Vero se il being indicato (con nome qualificato) è quello che si sta analizzando
+    fn is_current_being(&self, being: &str) -> bool {
+        self.current_being.as_ref().is_some_and(|current| self.qualified_name(current) == being)
+    }
+    
+    fn essence_field(&self, essence: &str, field: &str) -> Option<Type> {
+        self.lookup_essence(essence)?.fields.iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, field_type)| field_type.clone())
+    }
+    
+    // Registra un errore e prosegue l'analisi
+    pub fn report(&mut self, error: SemanticError) {
+        self.diagnostics.push(error.to_diagnostic());
+    }
+
+    // Verifica che una chiamata a ritual sia valida; in caso di errore restituisce Type::Error
+    pub fn check_ritual_call(&mut self, name: &str, args: &[Expression], span: &Span) -> Type {
+        let being = self.current_being.as_ref().map(|being| self.qualified_name(being));
+        self.check_call(being.as_deref(), name, args, span)
+    }
+    
+    // Verifica una chiamata a un ritual del being indicato (con nome qualificato); `None` indica
+    // un being sconosciuto, già segnalato, per cui vengono analizzati solo gli argomenti
+    fn check_call(&mut self, being: Option<&str>, name: &str, args: &[Expression], span: &Span) -> Type {
+        // Gli argomenti vengono sempre analizzati, anche se il ritual non esiste
+        let arg_types: Vec<Type> = args.iter()
+            .map(|arg| self.infer_expression_type(arg))
+            .collect();
+        
+        let Some(being) = being else {
+            return Type::Error;
+        };
+        let signature = self.lookup_being(being)
+            .and_then(|info| info.rituals.get(name))
+            .map(|info| (info.parameters.clone(), info.return_type.clone(), info.visibility));
+        
+        let Some((parameters, return_type, visibility)) = signature else {
+            if self.is_current_being(being) {
+                self.report(SemanticError::UndefinedRitual(name.to_string(), span.clone()));
+            } else {
+                self.report(SemanticError::Generic(
+                    format!("no ritual '{}' on being '{}'", name, self.display_name(being)),
+                    span.clone()
+                ));
+            }
+            return Type::Error;
+        };
+        
+        // I ritual privati possono essere chiamati solo dal loro being
+        if visibility == Visibility::Private && !self.is_current_being(being) {
+            self.report(SemanticError::PrivateMember {
+                kind: "ritual",
+                name: name.to_string(),
+                being: self.display_name(being).to_string(),
+                span: span.clone(),
+            });
+        }
+        
+        // Verifica che il numero di argomenti corrisponda
+        if args.len() != parameters.len() {
+            self.report(SemanticError::Generic(
+                format!(
+                    "Ritual '{}' expects {} arguments, but {} were provided",
+                    name, parameters.len(), args.len()
+                ),
+                span.clone()
+            ));
+            return return_type;
+        }
+        
+        // Verifica il tipo di ogni argomento
+        for ((arg, arg_type), param) in args.iter().zip(&arg_types).zip(&parameters) {
+            if !self.types_compatible(arg_type, &param.var_type) {
+                self.report(SemanticError::TypeMismatch {
+                    expected: printer::type_name(&param.var_type),
+                    found: printer::type_name(arg_type),
+                    span: arg.span(),
+                });
+            }
+        }
+        
+        return_type
+    }
+    
+    // Nome del tipo (being o essence) di un valore usato con `.`; segnala i tipi che non hanno membri
+    fn member_owner(&mut self, object: &Expression) -> Option<String> {
+        match self.infer_expression_type(object) {
+            Type::Custom(being) => Some(being),
+            Type::Error => None,
+            other => {
+                self.report(SemanticError::Generic(
+                    format!("type {} has no fields or rituals", printer::type_name(&other)),
+                    object.span()
+                ));
+                None
+            }
+        }
+    }
+    
+    // Tipo di `object.field`; in caso di errore restituisce Type::Error
+    fn field_access_type(&mut self, object: &Expression, field: &str, span: &Span) -> Type {
+        let Some(owner) = self.member_owner(object) else {
+            return Type::Error;
+        };
+        let field_type = match self.lookup_essence(&owner) {
+            Some(_) => self.essence_field(&owner, field),
+            None => self.being_variable(&owner, field, span),
+        };
+        field_type.unwrap_or_else(|| {
+            let kind = if self.lookup_essence(&owner).is_some() { "essence" } else { "being" };
+            self.report(SemanticError::Generic(
+                format!("no field '{}' on {} '{}'", field, kind, self.display_name(&owner)),
+                span.clone()
+            ));
+            Type::Error
+        })
+    }
+    
+    // Tipo di una variabile letta attraverso il suo being; da fuori del being deve essere pubblica
+    fn being_variable(&mut self, being: &str, name: &str, span: &Span) -> Option<Type> {
+        let (var_type, visibility) = self.lookup_being(being)?.variables.get(name)
+            .map(|info| (info.var_type.clone(), info.visibility))?;
+        // Gli inizializzatori vengono valutati prima che tutti i being abbiano un valore
+        if self.current_ritual.is_none() {
+            self.report(SemanticError::Generic(
+                "variables of a being cannot be read through a being reference in a being variable initializer".to_string(),
+                span.clone()
+            ));
+        }
+        if visibility == Visibility::Private && !self.is_current_being(being) {
+            self.report(SemanticError::PrivateMember {
+                kind: "variable",
+                name: name.to_string(),
+                being: self.display_name(being).to_string(),
+                span: span.clone(),
+            });
+        }
+        Some(var_type)
+    }
+    
+    // Tipo di `Realm::Being`, il riferimento all'istanza di un being di qualsiasi realm
+    fn being_reference_type(&mut self, realm: &str, being: &str, span: &Span) -> Type {
+        let Some(realm_info) = self.realm_table.get(realm) else {
+            self.report(SemanticError::Generic(format!("cannot find realm '{}'", realm), span.clone()));
+            return Type::Error;
+        };
+        if !realm_info.beings.contains_key(being) {
+            self.report(SemanticError::Generic(format!("no being '{}' in realm '{}'", being, realm), span.clone()));
+            return Type::Error;
+        }
+        Type::Custom(format!("{}::{}", realm, being))
+    }
+    
+
+    
+    // Verifica la costruzione di un'essence: ogni campo deve essere indicato una sola volta
+    fn check_construction(&mut self, essence: &str, fields: &[FieldInit], span: &Span) -> Type {
+        // I valori vengono sempre analizzati, anche se l'essence non esiste
+        let value_types: Vec<Type> = fields.iter()
+            .map(|field| self.infer_expression_type(&field.value))
+            .collect();
+        
+        let name_span = span.start..span.start + essence.len();
+        let Some(declared) = self.lookup_essence(essence).map(|info| info.fields.clone()) else {
+            if self.lookup_being(essence).is_some() {
+                self.report(SemanticError::Generic(
+                    format!("being '{}' cannot be constructed, only essences can", essence),
+                    name_span
+                ));
+            } else {
+                self.resolve_type(&Type::Custom(essence.to_string()), &name_span);
+            }
+            return Type::Error;
+        };
+        
+        let mut provided: HashSet<&str> = HashSet::new();
+        for (field, value_type) in fields.iter().zip(&value_types) {
+            let Some((_, field_type)) = declared.iter().find(|(name, _)| *name == field.name) else {
+                self.report(SemanticError::Generic(
+                    format!("no field '{}' on essence '{}'", field.name, essence),
+                    field.span.clone()
+                ));
+                continue;
+            };
+            if !provided.insert(&field.name) {
+                self.report(SemanticError::Generic(
+                    format!("field '{}' specified more than once", field.name),
+                    field.span.clone()
+                ));
+                continue;
+            }
+            if !self.types_compatible(value_type, field_type) {
+                self.report(SemanticError::TypeMismatch {
+                    expected: printer::type_name(field_type),
+                    found: printer::type_name(value_type),
+                    span: field.value.span(),
+                });
+            }
+        }
+        
+        let missing: Vec<String> = declared.iter()
+            .filter(|(name, _)| !provided.contains(name.as_str()))
+            .map(|(name, _)| format!("'{}'", name))
+            .collect();
+        if !missing.is_empty() {
+            self.report(SemanticError::Generic(
+                format!("missing field(s) {} in construction of essence '{}'", missing.join(", "), essence),
+                span.clone()
+            ));
+        }
+        
+        Type::Custom(self.qualified_name(essence))
+    }
+    
+    // Verifica `object.field = value`: si possono modificare solo i campi delle essence
+    // contenute in una variabile
+    fn check_field_assignment(&mut self, object: &Expression, field: &str, value: &Expression, span: &Span) {
+        let owner = self.member_owner(object);
+        let field_type = match owner {
+            Some(owner) if self.lookup_essence(&owner).is_some() => {
+                let field_type = self.essence_field(&owner, field);
+                if field_type.is_none() {
+                    self.report(SemanticError::Generic(
+                        format!("no field '{}' on essence '{}'", field, self.display_name(&owner)),
+                        span.clone()
+                    ));
+                } else if self.place_type(object).is_none() {
+                    self.report(SemanticError::Generic(
+                        "invalid assignment target: only fields of essences stored in variables can be assigned".to_string(),
+                        object.span()
+                    ));
+                }
+                field_type.unwrap_or(Type::Error)
+            }
+            Some(owner) => {
+                self.report(SemanticError::Generic(
+                    format!("cannot assign to field '{}' of being '{}'", field, self.display_name(&owner)),
+                    span.clone()
+                ));
+                Type::Error
+            }
+            None => Type::Error,
+        };
+        self.expect_type(value, &field_type);
+    }
+    
+    // Tipo di `hive[i, j]`: un indice intero per dimensione; gli indici letterali di una hive non
+    // circolare devono cadere nei limiti. Restituisce Type::Error in caso di errore
+    fn check_index(&mut self, object: &Expression, indices: &[Expression], span: &Span) -> Type {
+        let object_type = self.infer_expression_type(object);
+        for index in indices {
+            self.expect_type(index, &Type::Integer);
+        }
+        
+        let (element, dimensions, circular) = match object_type {
+            Type::Hive { element, dimensions, circular, .. } => (*element, dimensions, circular),
+            Type::Error => return Type::Error,
+            other => {
+                self.report(SemanticError::Generic(
+                    format!("type {} cannot be indexed, only hives can", printer::type_name(&other)),
+                    object.span()
+                ));
+                return Type::Error;
+            }
+        };
+        
+        if indices.len() != dimensions.len() {
+            self.report(SemanticError::Generic(
+                format!(
+                    "hive with {} dimension(s) indexed with {} index(es)",
+                    dimensions.len(), indices.len()
+                ),
+                span.clone()
+            ));
+            return Type::Error;
+        }
+        if !circular {
+            for (axis, (index, size)) in indices.iter().zip(&dimensions).enumerate() {
+                if let Some(value) = constant_index(index).filter(|value| !(0..*size as i64).contains(value)) {
+                    self.report(SemanticError::Generic(
+                        format!("index {} is out of bounds for dimension {} of size {}", value, axis, size),
+                        index.span()
+                    ));
+                }
+            }
+        }
+        element
+    }
+    
+    // Verifica `hive[i, j] = value`: la hive deve essere contenuta in una variabile
+    fn check_index_assignment(&mut self, object: &Expression, indices: &[Expression], value: &Expression, span: &Span) {
+        let element = self.check_index(object, indices, span);
+        if element != Type::Error && self.place_type(object).is_none() {
+            self.report(SemanticError::Generic(
+                "invalid assignment target: only elements of hives stored in variables can be assigned".to_string(),
+                object.span()
+            ));
+        }
+        self.expect_type(value, &element);
+    }
+    
+    // Tipo di una destinazione modificabile: una variabile, oppure un campo di un'essence contenuta
+    // in una destinazione modificabile; `None` se l'espressione non è assegnabile
+    fn place_type(&self, expr: &Expression) -> Option<Type> {
+        match expr {
+            Expression::Variable(name, _) => self.lookup_variable(name).cloned(),
+            Expression::FieldAccess { object, field, .. } => match self.place_type(object)? {
+                Type::Custom(essence) => self.essence_field(&essence, field),
+                _ => Some(Type::Error),
+            },
+            _ => None,
+        }
+    }
+    
+    // Inferisci il tipo di un'espressione; gli errori vengono registrati e
+    // l'espressione assume Type::Error per non generare errori a cascata
+    pub fn infer_expression_type(&mut self, expr: &Expression) -> Type {
+        match expr {
+            Expression::Literal(lit, _) => {
+                match lit {
+                    Literal::Integer(_) => Type::Integer,
+                    Literal::Float(_) => Type::Float,
+                    Literal::String(_) => Type::String,
+                    Literal::Boolean(_) => Type::Boolean,
+                }
+            },
+            Expression::Variable(name, span) => {
+                if let Some(var_type) = self.lookup_variable(name) {
+                    var_type.clone()
+                } else if self.lookup_being(name).is_some() {
+                    // Il nome di un being indica la sua istanza
+                    Type::Custom(self.qualified_name(name))
+                } else {
+                    self.report(SemanticError::UndefinedVariable(name.clone(), span.clone()));
+                    Type::Error
+                }
+            },
+            Expression::BinaryOperation { left, operator, right, span } => {
+                let left_type = self.infer_expression_type(left);
+                let right_type = self.infer_expression_type(right);
+                
+                // Un operando già errato non genera ulteriori errori
+                if left_type == Type::Error || right_type == Type::Error {
+                    return Type::Error;
+                }
+                
+                // Tipizzazione delle operazioni binarie
+                let result = match operator {
+                    // Operazioni aritmetiche
+                    crate::ast::nodes::BinaryOperator::Add |
+                    crate::ast::nodes::BinaryOperator::Subtract |
+                    crate::ast::nodes::BinaryOperator::Multiply |
+                    crate::ast::nodes::BinaryOperator::Divide => {
+                        if matches!(left_type, Type::Integer | Type::Float) &&
+                           matches!(right_type, Type::Integer | Type::Float) {
+                            // Promozione di tipo: se uno è float, il risultato è float
+                            if matches!(left_type, Type::Float) || matches!(right_type, Type::Float) {
+                                Ok(Type::Float)
+                            } else {
+                                Ok(Type::Integer)
+                            }
+                        } else {
+                            Err(SemanticError::TypeMismatch {
+                                expected: "numeric type".to_string(),
+                                found: format!("{} and {}", printer::type_name(&left_type), printer::type_name(&right_type)),
+                                span: span.clone(),
+                            })
+                        }
+                    },
+                    // Il resto è definito solo tra interi
+                    crate::ast::nodes::BinaryOperator::Modulo => {
+                        if left_type == Type::Integer && right_type == Type::Integer {
+                            Ok(Type::Integer)
+                        } else {
+                            Err(SemanticError::TypeMismatch {
+                                expected: "Integer operands".to_string(),
+                                found: format!("{} and {}", printer::type_name(&left_type), printer::type_name(&right_type)),
+                                span: span.clone(),
+                            })
+                        }
+                    },
+                    // Operatori logici, solo tra booleani; viene segnalato il primo operando errato
+                    crate::ast::nodes::BinaryOperator::And |
+                    crate::ast::nodes::BinaryOperator::Or => {
+                        match [(&left_type, left), (&right_type, right)].into_iter().find(|(t, _)| **t != Type::Boolean) {
+                            None => Ok(Type::Boolean),
+                            Some((found, operand)) => Err(SemanticError::TypeMismatch {
+                                expected: printer::type_name(&Type::Boolean),
+                                found: printer::type_name(found),
+                                span: operand.span(),
+                            }),
+                        }
+                    },
+                    // Operazioni di confronto
+                    crate::ast::nodes::BinaryOperator::Equal |
+                    crate::ast::nodes::BinaryOperator::NotEqual => {
+                        // Confronto di uguaglianza permesso tra tipi compatibili, in entrambi i versi
+                        if self.types_compatible(&left_type, &right_type) || self.types_compatible(&right_type, &left_type) {
+                            Ok(Type::Boolean)
+                        } else {
+                            Err(SemanticError::TypeMismatch {
+                                expected: printer::type_name(&left_type),
+                                found: printer::type_name(&right_type),
+                                span: right.span(),
+                            })
+                        }
+                    },
+                    crate::ast::nodes::BinaryOperator::LessThan |
+                    crate::ast::nodes::BinaryOperator::GreaterThan |
+                    crate::ast::nodes::BinaryOperator::LessEqual |
+                    crate::ast::nodes::BinaryOperator::GreaterEqual => {
+                        // Confronto di ordine permesso solo tra numeri
+                        if matches!(left_type, Type::Integer | Type::Float) &&
+                           matches!(right_type, Type::Integer | Type::Float) {
+                            Ok(Type::Boolean)
+                        } else {
+                            Err(SemanticError::TypeMismatch {
+                                expected: "numeric type".to_string(),
+                                found: format!("{} and {}", printer::type_name(&left_type), printer::type_name(&right_type)),
+                                span: span.clone(),
+                            })
+                        }
+                    },
+                };
+                
+                result.unwrap_or_else(|error| {
+                    self.report(error);
+                    Type::Error
+                })
+            },
+            Expression::UnaryOperation { operator, operand, .. } => {
+                let operand_type = self.infer_expression_type(operand);
+                let expected = match (operator, &operand_type) {
+                    (_, Type::Error) => return Type::Error,
+                    (UnaryOperator::Negate, Type::Integer | Type::Float) => return operand_type,
+                    (UnaryOperator::Not, Type::Boolean) => return Type::Boolean,
+                    (UnaryOperator::Negate, _) => "numeric type".to_string(),
+                    (UnaryOperator::Not, _) => printer::type_name(&Type::Boolean),
+                };
+                self.report(SemanticError::TypeMismatch {
+                    expected,
+                    found: printer::type_name(&operand_type),
+                    span: operand.span(),
+                });
+                Type::Error
+            },
+            Expression::FunctionCall { name, arguments, span } => {
+                self.check_ritual_call(name, arguments, span)
+            },
+            Expression::FieldAccess { object, field, span } => {
+                self.field_access_type(object, field, span)
+            },
+            Expression::MethodCall { object, name, arguments, span } => {
+                let mut owner = self.member_owner(object);
+                if let Some(essence) = owner.as_ref().filter(|owner| self.lookup_essence(owner).is_some()) {
+                    self.report(SemanticError::Generic(
+                        format!("essence '{}' has no rituals", self.display_name(essence)),
+                        span.clone()
+                    ));
+                    owner = None;
+                }
+                self.check_call(owner.as_deref(), name, arguments, span)
+            },
+            Expression::Construction { essence, fields, span } => {
+                self.check_construction(essence, fields, span)
+            },
+            Expression::BeingReference { realm, being, span } => {
+                self.being_reference_type(realm, being, span)
+            },
+            Expression::Index { object, indices, span } => {
+                self.check_index(object, indices, span)
+            },
+            // Già segnalata dal parser
+            Expression::Error(_) => Type::Error,
+        }
+    }
+
+    // Verifica che un valore di tipo `t1` possa essere usato dove è atteso `t2`
+    pub fn types_compatible(&self, t1: &Type, t2: &Type) -> bool {
+        // Regole di compatibilità base
+        if t1 == t2 {
+            return true;
+        }
+        
+        // Il tipo di errore è compatibile con tutto, per evitare errori a cascata
+        if matches!(t1, Type::Error) || matches!(t2, Type::Error) {
+            return true;
+        }
+        
+        match (t1, t2) {
+            // Un intero viene promosso a float, come fa il runtime
+            (Type::Integer, Type::Float) => true,
+            // `circular` e `persistent` descrivono la variabile, non i valori: una hive può
+            // essere usata al posto di un'altra con gli stessi elementi e dimensioni
+            (
+                Type::Hive { element: e1, dimensions: d1, .. },
+                Type::Hive { element: e2, dimensions: d2, .. },
+            ) => e1 == e2 && d1 == d2,
+            _ => false,
+        }
+    }
+    
+    // Verifica che il tipo di un'espressione sia quello atteso, registrando l'errore
+    fn expect_type(&mut self, expr: &Expression, expected: &Type) {
+        let found = self.infer_expression_type(expr);
+        if !self.types_compatible(&found, expected) {
+            self.report(SemanticError::TypeMismatch {
+                expected: printer::type_name(expected),
+                found: printer::type_name(&found),
+                span: expr.span(),
+            });
+        }
+    }
+}
+
+// Funzione principale di analisi semantica: raccoglie tutti gli errori e i warning
+pub fn analyze_program(program: &Program) -> Vec<Diagnostic> {
+    let mut context = SemanticContext::new();
+    
+    // Prima passata: tutte le dichiarazioni, così l'ordine nel sorgente non conta; i nomi dei
+    // tipi di ogni realm vengono registrati prima dei membri, che possono usare tipi di altri realm
+    for realm in &program.realms {
+        collect_realm(&mut context, realm);
+    }
+    for realm in &program.realms {
+        collect_members(&mut context, realm);
+    }
+    check_recursive_essences(&mut context, program);
+    
+    // Seconda passata: analizza i corpi con la tabella dei simboli completa
+    for realm in &program.realms {
+        analyze_realm(&mut context, realm);
+    }
+    
+    context.diagnostics
+}
+
+// Registra un realm con i nomi delle sue essence e dei suoi being
+fn collect_realm(context: &mut SemanticContext, realm: &Realm) {
+    // Un realm duplicato viene segnalato e ignorato
+    if let Err(error) = context.add_realm(&realm.name, realm.span.clone()) {
+        context.report(error);
+        return;
+    }
+    
+    // I nomi dei tipi vengono registrati prima dei membri, che possono usarli
+    for essence in &realm.essences {
+        if let Err(error) = context.add_essence(&essence.name, essence.span.clone()) {
+            context.report(error);
+        }
+    }
+    for being in &realm.beings {
+        if let Err(error) = context.add_being(&being.name, being.span.clone()) {
+            context.report(error);
+        }
+    }
+}
+
+// Registra i campi delle essence, le variabili dei being e le firme dei ritual di un realm
+fn collect_members(context: &mut SemanticContext, realm: &Realm) {
+    // Un realm duplicato è già stato segnalato
+    if !context.enter_realm(realm) {
+        return;
+    }
+    
+    for essence in &realm.essences {
+        context.add_essence_fields(essence);
+    }
+    for being in &realm.beings {
+        collect_being(context, being);
+    }
+}
+
+// Un'essence contiene i propri campi per valore: non può contenere sé stessa, nemmeno indirettamente
+// attraverso essence di altri realm
+fn check_recursive_essences(context: &mut SemanticContext, program: &Program) {
+    // Essence contenute direttamente da ciascuna essence, per nome qualificato
+    let mut contained: HashMap<String, Vec<String>> = HashMap::new();
+    let mut declarations = Vec::new();
+    for realm in &program.realms {
+        if !context.enter_realm(realm) {
+            continue;
+        }
+        for essence in &realm.essences {
+            let Some(info) = context.lookup_essence(&essence.name).filter(|info| info.span == essence.span) else {
+                continue;
+            };
+            let inner = info.fields.iter()
+                .filter_map(|(_, field_type)| match field_type {
+                    Type::Custom(name) if context.lookup_essence(name).is_some() => Some(name.clone()),
+                    _ => None,
+                })
+                .collect();
+            let name = context.qualified_name(&essence.name);
+            contained.insert(name.clone(), inner);
+            declarations.push((name, essence));
+        }
+    }
+    
+    let mut errors = Vec::new();
+    for (qualified, essence) in &declarations {
+        let mut stack: Vec<&str> = contained[qualified].iter().map(String::as_str).collect();
+        let mut visited = HashSet::new();
+        while let Some(name) = stack.pop() {
+            if name == qualified {
+                errors.push(SemanticError::Generic(
+                    format!("essence '{}' contains itself and would have infinite size", essence.name),
+                    essence.span.clone()
+                ));
+                break;
+            }
+            if visited.insert(name) {
+                stack.extend(contained.get(name).into_iter().flatten().map(String::as_str));
+            }
+        }
+    }
+    for error in errors {
+        context.report(error);
+    }
+}
+
+// Registra le variabili e le firme dei ritual di un being
+fn collect_being(context: &mut SemanticContext, being: &Being) {
+    // Un being duplicato è già stato segnalato
+    if !context.enter_being(being) {
+        return;
+    }
+    
+    for var in &being.variables {
+        let BeingVariable { visibility, variable, span, .. } = var;
+        let var_type = context.resolve_type(&variable.var_type, &variable.type_span);
+        let variable = Variable { var_type, ..variable.clone() };
+        if let Err(error) = context.add_being_variable(&variable, *visibility, span.clone()) {
+            context.report(error);
+        }
+    }
+    
+    // Le estensioni vengono registrate prima dei ritual: un ritual omonimo è il duplicato
+    for extension in &being.extensions {
+        if let Err(error) = context.add_extension(extension) {
+            context.report(error);
+        }
+    }
+    
+    for ritual in &being.rituals {
+        if let Err(error) = context.add_ritual(ritual) {
+            context.report(error);
+        }
+    }
+}
+
+// Analizza un realm
+fn analyze_realm(context: &mut SemanticContext, realm: &Realm) {
+    // Un realm duplicato è già stato segnalato e non viene analizzato
+    if !context.enter_realm(realm) {
+        return;
+    }
+    
+    // Analizza tutti i being nel realm
+    for being in &realm.beings {
+        analyze_being(context, being);
+    }
+}
+
+// Analizza un being
+fn analyze_being(context: &mut SemanticContext, being: &Being) {
+    if !context.enter_being(being) {
+        return;
+    }
+    
+    // Se una variabile ha un initializer, verifica che il tipo sia compatibile
+    for var in &being.variables {
+        if let Some(init) = &var.initializer {
+            let var_type = context.lookup_type(&var.variable.var_type);
+            context.expect_type(init, &var_type);
+        }
+    }
+    
+    // Analizza tutti i ritual nel being
+    for ritual in &being.rituals {
+        analyze_ritual(context, ritual);
+    }
+    
+    check_perceptions(context, being);
+    
+    // Verifica che nessuna variabile venga letta prima di avere un valore
+    context.diagnostics.extend(initialization::check_being(being));
+}
+
+// Ogni percezione è gestita dal ritual con lo stesso nome, che ne riceve i parametri e non
+// restituisce nulla
+fn check_perceptions(context: &mut SemanticContext, being: &Being) {
+    let mut declared = HashSet::new();
+    for perception in &being.perceptions {
+        if !declared.insert(perception.name.as_str()) {
+            context.report(SemanticError::Generic(
+                format!("Perception '{}' already declared in being '{}'", perception.name, being.name),
+                perception.span.clone()
+            ));
+            continue;
+        }
+        
+        let mut names = HashSet::new();
+        let mut parameters = Vec::new();
+        for param in &perception.parameters {
+            if !names.insert(param.name.as_str()) {
+                context.report(SemanticError::Generic(
+                    format!("Duplicate parameter name '{}' in perception '{}'", param.name, perception.name),
+                    param.span.clone()
+                ));
+            }
+            context.reject_persistent(&param.var_type, &param.type_span, "parameters");
+            parameters.push(context.resolve_type(&param.var_type, &param.type_span));
+        }
+        
+        let signature = format!("{}({})", perception.name, printer::parameter_list(&perception.parameters));
+        let Some(handler) = being.rituals.iter().find(|ritual| ritual.name == perception.name) else {
+            context.report(SemanticError::MissingPerceptionHandler {
+                perception: perception.name.clone(),
+                being: being.name.clone(),
+                signature,
+                span: perception.span.clone(),
+            });
+            continue;
+        };
+        
+        // I tipi già segnalati come sconosciuti non producono un secondo errore
+        let same_type = |a: &Type, b: &Type| a == b || *a == Type::Error || *b == Type::Error;
+        let matches = context.lookup_being(&being.name)
+            .and_then(|info| info.rituals.get(&handler.name))
+            .is_some_and(|info| {
+                info.return_type == Type::Void
+                    && info.parameters.len() == parameters.len()
+                    && info.parameters.iter().zip(&parameters).all(|(param, expected)| same_type(&param.var_type, expected))
+            });
+        if !matches {
+            let return_type = match &handler.return_type {
+                Type::Void => String::new(),
+                other => format!(" {}", printer::type_name(other)),
+            };
+            context.report(SemanticError::PerceptionHandlerMismatch {
+                perception: perception.name.clone(),
+                expected: signature,
+                found: format!("{}({}){}", handler.name, printer::parameter_list(&handler.parameters), return_type),
+                span: handler.span.clone(),
+                declaration: perception.span.clone(),
+            });
+        }
+    }
+}
+
+// Analizza un ritual
+fn analyze_ritual(context: &mut SemanticContext, ritual: &Ritual) {
+    // Anche un ritual duplicato viene analizzato, per segnalare gli errori nel corpo
+    context.current_ritual = Some(ritual.name.clone());
+    
+    // Crea un nuovo scope per i parametri e il corpo del ritual
+    context.enter_scope();
+    
+    // Aggiungi i parametri allo scope locale; i tipi sconosciuti sono già stati segnalati
+    for param in &ritual.parameters {
+        let param_type = context.lookup_type(&param.var_type);
+        if let Err(error) = context.add_variable(&param.name, param_type, param.span.clone()) {
+            context.report(error);
+        }
+    }
+    
+    // Analizza il corpo del ritual
+    let return_type = context.lookup_type(&ritual.return_type);
+    for stmt in &ritual.body {
+        analyze_statement(context, stmt, &return_type);
+    }
+    
+    // Verifica che ogni percorso di un ritual non void termini con un return
+    context.diagnostics.extend(control_flow::check_ritual(ritual));
+    
+    // Esci dallo scope del ritual
+    context.exit_scope();
+}
+
+// Analizza uno statement
+fn analyze_statement(context: &mut SemanticContext, stmt: &Statement, expected_return_type: &Type) {
+    match stmt {
+        Statement::VariableDeclaration { variable, initializer, span } => {
+            context.reject_persistent(&variable.var_type, &variable.type_span, "local variables");
+            let var_type = context.resolve_type(&variable.var_type, &variable.type_span);
+            
+            // Se c'è un initializer, verifica che il tipo sia compatibile
+            if let Some(init) = initializer {
+                context.expect_type(init, &var_type);
+            }
+            
+            // Aggiungi la variabile allo scope corrente
+            if let Err(error) = context.add_variable(&variable.name, var_type, span.clone()) {
+                context.report(error);
+            }
+        },
+        Statement::Assignment { name, value, span } => {
+            // Verifica che la variabile esista; il valore viene analizzato comunque
+            match context.lookup_variable(name).cloned() {
+                Some(var_type) => context.expect_type(value, &var_type),
+                None => {
+                    context.report(SemanticError::UndefinedVariable(name.clone(), span.clone()));
+                    context.infer_expression_type(value);
+                }
+            }
+        },
+        Statement::FieldAssignment { object, field, value, span } => {
+            context.check_field_assignment(object, field, value, span);
+        },
+        Statement::IndexAssignment { object, indices, value, span } => {
+            context.check_index_assignment(object, indices, value, span);
+        },
+        Statement::RitualCall { name, arguments, span } => {
+            // Verifica che la chiamata al ritual sia valida
+            context.check_ritual_call(name, arguments, span);
+        },
+        Statement::Conditional { condition, true_branch, false_branch, .. } => {
+            // Verifica che la condizione sia booleana
+            context.expect_type(condition, &Type::Boolean);
+            
+            // Analizza i branch
+            context.enter_scope();
+            for stmt in true_branch {
+                analyze_statement(context, stmt, expected_return_type);
+            }
+            context.exit_scope();
+            
+            if let Some(false_stmts) = false_branch {
+                context.enter_scope();
+                for stmt in false_stmts {
+                    analyze_statement(context, stmt, expected_return_type);
+                }
+                context.exit_scope();
+            }
+        },
+        Statement::Cycle { condition, body, .. } => {
+            // Se c'è una condizione, verifica che sia booleana
+            if let Some(cond) = condition {
+                context.expect_type(cond, &Type::Boolean);
+            }
+            
+            // Analizza il corpo del ciclo
+            context.enter_scope();
+            for stmt in body {
+                analyze_statement(context, stmt, expected_return_type);
+            }
+            context.exit_scope();
+        },
+        Statement::Return(expr_opt, span) => {
+            match (expr_opt, expected_return_type) {
+                // Return senza espressione per tipo void
+                (None, Type::Void) => {},
+                
+                // Return con espressione per tipo non void
+                (Some(expr), _) => context.expect_type(expr, expected_return_type),
+                
+                // Return senza espressione per tipo non void
+                (None, _) => {
+                    context.report(SemanticError::TypeMismatch {
+                        expected: printer::type_name(expected_return_type),
+                        found: "void".to_string(),
+                        span: span.clone(),
+                    });
+                },
+            }
+        },
+        Statement::Expression(expr, _) => {
+            context.infer_expression_type(expr);
+        },
+        // Già segnalato dal parser
+        Statement::Error(_) => {},
+    }
+}
+
+// Valore di un indice scritto come letterale intero, eventualmente negativo
+fn constant_index(index: &Expression) -> Option<i64> {
+    match index {
+        Expression::Literal(Literal::Integer(value), _) => Some(*value),
+        Expression::UnaryOperation { operator: UnaryOperator::Negate, operand, .. } => constant_index(operand)?.checked_neg(),
+        _ => None,
+    }
+}
+
+// Funzione principale che analizza l'intero programma
+pub fn analyze(program: &Program) -> Vec<Diagnostic> {
+    analyze_program(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer, parser};
+
+    fn program(source: &str) -> Program {
+        let (program, errors) = parser::parse(lexer::tokenize(source).expect("the source is lexically valid"));
+        assert!(errors.is_empty(), "{:?}", errors);
+        program.expect("the source parses")
+    }
+
+    // Messaggi degli errori, nell'ordine in cui sono segnalati
+    fn errors(source: &str) -> Vec<String> {
+        analyze(&program(source)).into_iter()
+            .filter(Diagnostic::is_error)
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn independent_errors_are_all_reported() {
+        let errors = errors("
+            realm R {
+                being B {
+                    ritual f() {
+                        int a: \"text\";
+                        bool b: missing + 1;
+                    }
+
+                    ritual g() {
+                        string s: 1 + true;
+                        h(3);
+                    }
+                }
+            }
+        ");
+        // `missing + 1` ha tipo errore: la dichiarazione di `b` non aggiunge un secondo errore
+        assert_eq!(errors, ["mismatched types", "undefined variable `missing`", "mismatched types", "ritual `h` not found"]);
+    }
+
+    #[test]
+    fn rituals_can_be_used_before_their_declaration() {
+        let source = "
+            realm R {
+                being A {
+                    public ritual first() int {
+                        return second(1) + B.value();
+                    }
+
+                    ritual second(x: int) int {
+                        return x;
+                    }
+                }
+
+                being B {
+                    public ritual value() int {
+                        return 2;
+                    }
+                }
+            }
+        ";
+        assert!(errors(source).is_empty());
+
+        let duplicated = source.replace("ritual second", "ritual first");
+        assert_eq!(errors(&duplicated), ["Ritual 'first' already defined in being 'A'", "ritual `second` not found"]);
+    }
+
+    #[test]
+    fn custom_types_resolve_to_beings() {
+        let program = program("
+            realm R {
+                being Counter {
+                    public int count: 0;
+
+                    public ritual bump() int {
+                        count = count + 1;
+                        return count;
+                    }
+                }
+
+                being User {
+                    public ritual f() int {
+                        Counter c: Counter;
+                        Countr d: Counter;
+                        return c.bump() + c.count + c.missing;
+                    }
+                }
+            }
+        ");
+        let diagnostics: Vec<Diagnostic> = analyze(&program).into_iter().filter(Diagnostic::is_error).collect();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, ["cannot find type `Countr` in this realm", "no field 'missing' on being 'Counter'"]);
+        assert_eq!(diagnostics[0].help, ["a type with a similar name exists: `Counter`"]);
+    }
+
+    #[test]
+    fn essence_constructions_and_fields_are_checked() {
+        let errors = errors("
+            realm R {
+                essence Point {
+                    x: int;
+                    y: float;
+                }
+
+                being B {
+                    public ritual f() float {
+                        Point p: Point { x: 1, y: 2.5 };
+                        p.x = p.x + 2;
+                        Point q: Point { x: 1 };
+                        Point r: Point { x: 1, y: 2.0, z: 3 };
+                        return p.x + p.y + p.w;
+                    }
+                }
+            }
+        ");
+        assert_eq!(errors, [
+            "missing field(s) 'y' in construction of essence 'Point'",
+            "no field 'z' on essence 'Point'",
+            "no field 'w' on essence 'Point'",
+        ]);
+    }
+
+    #[test]
+    fn private_members_are_not_accessible_from_other_beings() {
+        let errors = errors("
+            realm Lab {
+                being Store {
+                    int secret: 1;
+                    public int shared: 2;
+
+                    ritual hidden() int {
+                        return secret;
+                    }
+
+                    public ritual open() int {
+                        shared = shared + 1;
+                        return hidden();
+                    }
+                }
+
+                being Client {
+                    public ritual f() int {
+                        return Store.open() + Store.shared + Store.secret + Store.hidden() + Other::Remote.ping();
+                    }
+                }
+            }
+
+            realm Other {
+                being Remote {
+                    public ritual ping() int {
+                        return 5;
+                    }
+                }
+            }
+        ");
+        assert_eq!(errors, ["variable `secret` of being `Store` is private", "ritual `hidden` of being `Store` is private"]);
+    }
+
+    #[test]
+    fn hive_indices_are_checked() {
+        let errors = errors("
+            realm R {
+                being B {
+                    public ritual f() float {
+                        hive<float>[2, 3] grid;
+                        circular hive<int>[4] ring;
+                        hive<string>[2] names;
+                        int n: 5;
+                        grid[1, 2] = 0.5;
+                        ring[7] = 1;
+                        grid[2, 0] = grid[0, n] + grid[1];
+                        grid[0, 1.5] = 1.0;
+                        return grid[0, 0] + n[0];
+                    }
+                }
+            }
+        ");
+        // La destinazione è controllata prima del valore; gli indici variabili e quelli di una
+        // hive circolare non sono controllati
+        assert_eq!(errors, [
+            "hive elements must be int or float, found string",
+            "index 2 is out of bounds for dimension 0 of size 2",
+            "hive with 2 dimension(s) indexed with 1 index(es)",
+            "mismatched types",
+            "type int cannot be indexed, only hives can",
+        ]);
+    }
+
+    #[test]
+    fn hive_modifiers_are_not_part_of_the_type() {
+        let errors = errors("
+            realm R {
+                being B {
+                    persistent hive<float>[2] store;
+
+                    ritual total(h: hive<float>[2]) float {
+                        return h[0] + h[1];
+                    }
+
+                    public ritual f() float {
+                        circular hive<float>[2] ring;
+                        hive<float>[2] plain;
+                        hive<int>[2] counts;
+                        plain = ring;
+                        ring = store;
+                        plain = counts;
+                        return total(store) + total(ring) + total(hive_of_three());
+                    }
+
+                    ritual hive_of_three() hive<float>[3] {
+                        hive<float>[3] h;
+                        return h;
+                    }
+                }
+            }
+        ");
+        // Elementi e dimensioni fanno parte del tipo
+        assert_eq!(errors, ["mismatched types", "mismatched types"]);
+    }
+
+    #[test]
+    fn only_being_variables_are_persistent() {
+        let errors = errors("
+            realm R {
+                essence Sample {
+                    values: persistent hive<float>[2];
+                }
+
+                being B {
+                    persistent hive<float>[2] store;
+
+                    ritual f(p: persistent hive<float>[2]) persistent hive<float>[2] {
+                        persistent hive<float>[2] local: p;
+                        return local;
+                    }
+
+                    public ritual g() float {
+                        hive<float>[2] copy: f(store);
+                        return copy[0];
+                    }
+                }
+            }
+        ");
+        assert_eq!(errors, [
+            "essence fields cannot be persistent: only being variables are stored in files",
+            "parameters cannot be persistent: only being variables are stored in files",
+            "return types cannot be persistent: only being variables are stored in files",
+            "local variables cannot be persistent: only being variables are stored in files",
+        ]);
+    }
+}
@@ -0,0 +1,192 @@
+// Analisi del flusso di controllo dei ritual: percorsi senza return e codice irraggiungibile
+
+use crate::ast::nodes::{Expression, Literal, Ritual, Statement, Span, Type};
+use crate::diagnostics::Diagnostic;
+use crate::semantic::SemanticError;
+
+/// Checks that every path of a non-void ritual returns and warns about unreachable statements
+pub fn check_ritual(ritual: &Ritual) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    unreachable_statements(&ritual.body, &mut diagnostics);
+
+    if ritual.return_type != Type::Void && !block_terminates(&ritual.body) {
+        let error = SemanticError::MissingReturn {
+            ritual: ritual.name.clone(),
+            return_type: crate::ast::printer::type_name(&ritual.return_type),
+            span: ritual.span.clone(),
+            path: open_path(&ritual.body),
+        };
+        diagnostics.push(error.to_diagnostic());
+    }
+
+    diagnostics
+}
+
+// Un blocco termina se uno dei suoi statement non prosegue mai al successivo
+fn block_terminates(statements: &[Statement]) -> bool {
+    statements.iter().any(terminates)
+}
+
+// Vero se l'esecuzione non può proseguire dopo lo statement: un return su ogni percorso o un ciclo infinito
+fn terminates(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Return(..) => true,
+        Statement::Conditional { true_branch, false_branch, .. } => {
+            block_terminates(true_branch)
+                && false_branch.as_deref().is_some_and(block_terminates)
+        }
+        // Non esiste `break`: un ciclo senza condizione (o con condizione sempre vera) non termina
+        Statement::Cycle { condition, .. } => condition.as_ref().is_none_or(always_true),
+        // Già segnalato dal parser: non genera ulteriori errori
+        Statement::Error(_) => true,
+        Statement::VariableDeclaration { .. } |
+        Statement::Assignment { .. } |
+        Statement::FieldAssignment { .. } |
+        Statement::IndexAssignment { .. } |
+        Statement::RitualCall { .. } |
+        Statement::Expression(..) => false,
+    }
+}
+
+fn always_true(condition: &Expression) -> bool {
+    matches!(condition, Expression::Literal(Literal::Boolean(true), _))
+}
+
+// Posizione di un percorso che raggiunge la fine del blocco senza return, se individuabile
+fn open_path(statements: &[Statement]) -> Option<Span> {
+    // Il percorso più vicino alla fine del blocco è quello più utile da mostrare
+    statements.iter().rev().find_map(|stmt| match stmt {
+        Statement::Conditional { true_branch, false_branch, span, .. } => {
+            let true_returns = block_terminates(true_branch);
+            let false_returns = false_branch.as_deref().is_some_and(block_terminates);
+            match (true_returns, false_branch) {
+                (false, _) if false_returns || contains_return(true_branch) => {
+                    Some(open_path(true_branch).unwrap_or_else(|| block_span(true_branch, span)))
+                }
+                (true, None) => Some(span.clone()),
+                (_, Some(false_branch)) if !false_returns && (true_returns || contains_return(false_branch)) => {
+                    Some(open_path(false_branch).unwrap_or_else(|| block_span(false_branch, span)))
+                }
+                _ => None,
+            }
+        }
+        Statement::Cycle { body, span, .. } if contains_return(body) => Some(span.clone()),
+        _ => None,
+    })
+}
+
+fn contains_return(statements: &[Statement]) -> bool {
+    statements.iter().any(|stmt| match stmt {
+        Statement::Return(..) => true,
+        Statement::Conditional { true_branch, false_branch, .. } => {
+            contains_return(true_branch) || false_branch.as_deref().is_some_and(contains_return)
+        }
+        Statement::Cycle { body, .. } => contains_return(body),
+        _ => false,
+    })
+}
+
+// Intervallo coperto dagli statement di un ramo, oppure dallo statement che lo contiene se è vuoto
+fn block_span(statements: &[Statement], fallback: &Span) -> Span {
+    match (statements.first(), statements.last()) {
+        (Some(first), Some(last)) => first.span().start..last.span().end,
+        _ => fallback.clone(),
+    }
+}
+
+// Segnala il primo statement irraggiungibile di ogni blocco, scendendo nei blocchi annidati
+fn unreachable_statements(statements: &[Statement], diagnostics: &mut Vec<Diagnostic>) {
+    for (index, stmt) in statements.iter().enumerate() {
+        match stmt {
+            Statement::Conditional { true_branch, false_branch, .. } => {
+                unreachable_statements(true_branch, diagnostics);
+                if let Some(false_branch) = false_branch {
+                    unreachable_statements(false_branch, diagnostics);
+                }
+            }
+            Statement::Cycle { body, .. } => unreachable_statements(body, diagnostics),
+            _ => {}
+        }
+
+        let rest = &statements[index + 1..];
+        if terminates(stmt) && !matches!(stmt, Statement::Error(_)) && !rest.is_empty() {
+            diagnostics.push(
+                Diagnostic::warning("unreachable statement", block_span(rest, &stmt.span()))
+                    .with_primary_label("unreachable statement")
+                    .with_label(stmt.span(), "any code following this statement is unreachable")
+            );
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer, parser};
+
+    // Diagnostic di ogni ritual del primo being, come `(ritual, messaggio)`
+    fn check(source: &str) -> Vec<(String, String)> {
+        let (program, errors) = parser::parse(lexer::tokenize(source).expect("the source is lexically valid"));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let program = program.expect("the source parses");
+        program.realms[0].beings[0].rituals.iter()
+            .flat_map(|ritual| check_ritual(ritual).into_iter().map(|d| (ritual.name.clone(), d.message)))
+            .collect()
+    }
+
+    #[test]
+    fn every_path_must_return() {
+        let diagnostics = check("
+            realm R {
+                being A {
+                    ritual open(n: int) int {
+                        if (n > 0) {
+                            return 1;
+                        }
+                    }
+
+                    ritual closed(n: int) int {
+                        if (n > 0) {
+                            return 1;
+                        } else if (n < 0) {
+                            return -1;
+                        } else {
+                            return 0;
+                        }
+                    }
+
+                    ritual endless() int {
+                        cycle {
+                        }
+                    }
+
+                    ritual nothing() {
+                    }
+                }
+            }
+        ");
+        assert_eq!(diagnostics, [("open".to_string(), "ritual `open` must return a value of type int".to_string())]);
+    }
+
+    #[test]
+    fn statements_after_a_return_are_unreachable() {
+        let diagnostics = check("
+            realm R {
+                being A {
+                    ritual f(n: int) int {
+                        if (n > 0) {
+                            return 1;
+                        } else {
+                            return 2;
+                        }
+                        n = 3;
+                        n = 4;
+                    }
+                }
+            }
+        ");
+        // Solo il primo statement irraggiungibile viene segnalato
+        assert_eq!(diagnostics, [("f".to_string(), "unreachable statement".to_string())]);
+    }
+}
@@ -0,0 +1,448 @@
+// Analisi di assegnazione definita: nessuna variabile può essere letta prima di ricevere un valore.
+//
+// Regola per le variabili dei being: ogni variabile riceve il valore dal proprio inizializzatore,
+// valutato in ordine di dichiarazione, oppure dal ritual `init` del being, eseguito subito dopo
+// gli inizializzatori. Un inizializzatore può leggere solo variabili già inizializzate e non può
+// chiamare ritual; `init` deve assegnare ogni variabile rimasta senza valore prima di terminare.
+// Le hive dichiarate senza inizializzatore hanno già un valore: tutti gli elementi a zero.
+// Lo stesso vale per le variabili di memoria di tipo primitivo, che partono dal valore zero del
+// tipo: `init` viene eseguito a ogni avvio, anche dopo il ripristino dell'istantanea, e non può
+// quindi essere il punto in cui ricevono il primo valore.
+
+use std::collections::{BTreeSet, HashMap};
+use crate::ast::nodes::{Being, Expression, Ritual, Statement, Span, Type};
+use crate::diagnostics::Diagnostic;
+use crate::semantic::SemanticError;
+
+/// Checks how the variables of a being and the locals of its rituals are initialised
+pub fn check_being(being: &Being) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    let init = being.init_ritual();
+
+    if let Some(init) = init {
+        if !init.parameters.is_empty() || init.return_type != Type::Void {
+            errors.push(SemanticError::Generic(
+                format!("`{}` ritual must take no parameters and return nothing", Being::INIT_RITUAL),
+                init.span.clone()
+            ));
+        }
+    }
+
+    // Variabili del being, nell'ordine in cui vengono inizializzate
+    let declarations: Vec<Declaration> = being.variables.iter()
+        .map(|var| Declaration { name: &var.variable.name, span: var.span.clone() })
+        .collect();
+    let being_ids: HashMap<&str, usize> = declarations.iter()
+        .enumerate()
+        .map(|(id, decl)| (decl.name, id))
+        .collect();
+
+    // Tutte le variabili sono senza valore finché il loro inizializzatore non è stato valutato
+    let mut pending: State = Some((0..declarations.len()).collect());
+    let mut flow = Flow::new(declarations, being_ids.clone());
+    for (id, var) in being.variables.iter().enumerate() {
+        match &var.initializer {
+            Some(init) => {
+                flow.read(init, &pending);
+                if contains_call(init) {
+                    flow.errors.push(SemanticError::Generic(
+                        "ritual calls are not allowed in being variable initializers".to_string(),
+                        init.span()
+                    ));
+                }
+                if let Some(set) = &mut pending {
+                    set.remove(&id);
+                }
+            }
+            // Una hive senza inizializzatore contiene zeri
+            None if is_hive(&var.variable.var_type) => {
+                if let Some(set) = &mut pending {
+                    set.remove(&id);
+                }
+            }
+            None if var.memory && has_zero_value(&var.variable.var_type) => {
+                if let Some(set) = &mut pending {
+                    set.remove(&id);
+                }
+            }
+            None if var.memory => {
+                flow.errors.push(SemanticError::Generic(
+                    format!(
+                        "memory variable '{}' needs an initializer: `{}` runs on every start and would overwrite the saved value",
+                        var.variable.name, Being::INIT_RITUAL
+                    ),
+                    var.span.clone()
+                ));
+                if let Some(set) = &mut pending {
+                    set.remove(&id);
+                }
+            }
+            None if init.is_none() => {
+                flow.errors.push(SemanticError::Generic(
+                    format!("being variable '{}' has no initial value", var.variable.name),
+                    var.span.clone()
+                ));
+                // Segnalata una sola volta
+                if let Some(set) = &mut pending {
+                    set.remove(&id);
+                }
+            }
+            None => {}
+        }
+    }
+    errors.append(&mut flow.errors);
+
+    for ritual in &being.rituals {
+        // Solo `init` vede le variabili del being ancora senza valore
+        let entry = match init {
+            Some(init) if std::ptr::eq(init, ritual) => pending.clone(),
+            _ => Some(BTreeSet::new()),
+        };
+        errors.extend(check_ritual(ritual, &flow.declarations, &being_ids, entry));
+    }
+
+    errors.iter().map(SemanticError::to_diagnostic).collect()
+}
+
+// Verifica un ritual partendo dallo stato delle variabili del being all'ingresso
+fn check_ritual(
+    ritual: &Ritual,
+    being_declarations: &[Declaration],
+    being_ids: &HashMap<&str, usize>,
+    entry: State,
+) -> Vec<SemanticError> {
+    let mut flow = Flow::new(being_declarations.to_vec(), being_ids.clone());
+    flow.being_pending = entry.as_ref().is_some_and(|set| !set.is_empty());
+
+    // I parametri hanno sempre un valore
+    let parameters = ritual.parameters.iter()
+        .map(|param| (param.name.as_str(), flow.declare(&param.name, param.span.clone())))
+        .collect();
+    flow.scopes.push(parameters);
+
+    let exit = flow.block(&ritual.body, entry);
+    if flow.being_pending {
+        flow.check_init_complete(&exit, ritual.span.clone());
+    }
+    flow.errors
+}
+
+// Insieme delle variabili (per identificatore) che potrebbero non avere un valore;
+// `None` indica un punto del programma irraggiungibile
+type State = Option<BTreeSet<usize>>;
+
+#[derive(Clone)]
+struct Declaration<'a> {
+    name: &'a str,
+    span: Span,
+}
+
+struct Flow<'a> {
+    declarations: Vec<Declaration<'a>>,
+    // Variabili del being, per nome; occupano i primi `being_count` identificatori
+    being_ids: HashMap<&'a str, usize>,
+    being_count: usize,
+    // Variabili locali visibili, dal blocco più esterno al più interno
+    scopes: Vec<HashMap<&'a str, usize>>,
+    // Vero nel ritual `init` finché alcune variabili del being possono essere senza valore
+    being_pending: bool,
+    errors: Vec<SemanticError>,
+}
+
+impl<'a> Flow<'a> {
+    fn new(declarations: Vec<Declaration<'a>>, being_ids: HashMap<&'a str, usize>) -> Self {
+        let being_count = declarations.len();
+        Flow { declarations, being_ids, being_count, scopes: Vec::new(), being_pending: false, errors: Vec::new() }
+    }
+
+    fn declare(&mut self, name: &'a str, span: Span) -> usize {
+        self.declarations.push(Declaration { name, span });
+        self.declarations.len() - 1
+    }
+
+    fn resolve(&self, name: &str) -> Option<usize> {
+        self.scopes.iter().rev()
+            .find_map(|scope| scope.get(name))
+            .or_else(|| self.being_ids.get(name))
+            .copied()
+    }
+
+    fn block(&mut self, statements: &'a [Statement], mut state: State) -> State {
+        self.scopes.push(HashMap::new());
+        for stmt in statements {
+            state = self.statement(stmt, state);
+        }
+        self.scopes.pop();
+        state
+    }
+
+    fn statement(&mut self, stmt: &'a Statement, mut state: State) -> State {
+        match stmt {
+            Statement::VariableDeclaration { variable, initializer, span } => {
+                if let Some(init) = initializer {
+                    self.read(init, &state);
+                }
+                let id = self.declare(&variable.name, span.clone());
+                self.scopes.last_mut()
+                    .expect("a block always has a scope")
+                    .insert(&variable.name, id);
+                if initializer.is_none() && !is_hive(&variable.var_type) {
+                    if let Some(set) = &mut state {
+                        set.insert(id);
+                    }
+                }
+                state
+            }
+            Statement::Assignment { name, value, .. } => {
+                self.read(value, &state);
+                if let (Some(id), Some(set)) = (self.resolve(name), &mut state) {
+                    set.remove(&id);
+                }
+                state
+            }
+            // Modificare un campo richiede che la variabile che lo contiene abbia già un valore
+            Statement::FieldAssignment { object, value, .. } => {
+                self.read(value, &state);
+                self.read(object, &state);
+                state
+            }
+            Statement::IndexAssignment { object, indices, value, .. } => {
+                self.read(value, &state);
+                for index in indices {
+                    self.read(index, &state);
+                }
+                self.read(object, &state);
+                state
+            }
+            Statement::RitualCall { arguments, span, .. } => {
+                for arg in arguments {
+                    self.read(arg, &state);
+                }
+                self.check_call(span, &state);
+                state
+            }
+            Statement::Conditional { condition, true_branch, false_branch, .. } => {
+                self.read(condition, &state);
+                let after_true = self.block(true_branch, state.clone());
+                let after_false = match false_branch {
+                    Some(false_branch) => self.block(false_branch, state),
+                    None => state,
+                };
+                merge(after_true, after_false)
+            }
+            Statement::Cycle { condition, body, .. } => {
+                if let Some(condition) = condition {
+                    self.read(condition, &state);
+                }
+                // Il corpo potrebbe non essere mai eseguito: dopo il ciclo vale lo stato di ingresso
+                let after_body = self.block(body, state.clone());
+                match condition {
+                    Some(_) => merge(state, after_body),
+                    None => None,
+                }
+            }
+            Statement::Return(value, span) => {
+                if let Some(value) = value {
+                    self.read(value, &state);
+                }
+                if self.being_pending {
+                    self.check_init_complete(&state, span.clone());
+                }
+                None
+            }
+            Statement::Expression(expr, _) => {
+                self.read(expr, &state);
+                state
+            }
+            Statement::Error(_) => state,
+        }
+    }
+
+    // Segnala le letture di variabili che potrebbero non avere un valore
+    fn read(&mut self, expr: &Expression, state: &State) {
+        match expr {
+            Expression::Variable(name, span) => {
+                let Some(id) = self.resolve(name) else { return };
+                if state.as_ref().is_some_and(|set| set.contains(&id)) && !self.reported(id) {
+                    self.errors.push(SemanticError::UninitializedVariable {
+                        name: name.clone(),
+                        span: span.clone(),
+                        declaration: self.declarations[id].span.clone(),
+                    });
+                }
+            }
+            Expression::BinaryOperation { left, right, .. } => {
+                self.read(left, state);
+                self.read(right, state);
+            }
+            Expression::UnaryOperation { operand, .. } => self.read(operand, state),
+            Expression::FunctionCall { arguments, span, .. } => {
+                for arg in arguments {
+                    self.read(arg, state);
+                }
+                self.check_call(span, state);
+            }
+            Expression::FieldAccess { object, .. } => self.read(object, state),
+            Expression::Index { object, indices, .. } => {
+                self.read(object, state);
+                for index in indices {
+                    self.read(index, state);
+                }
+            }
+            Expression::Construction { fields, .. } => {
+                for field in fields {
+                    self.read(&field.value, state);
+                }
+            }
+            // Il ritual chiamato potrebbe leggere le variabili di questo being
+            Expression::MethodCall { object, arguments, span, .. } => {
+                self.read(object, state);
+                for arg in arguments {
+                    self.read(arg, state);
+                }
+                self.check_call(span, state);
+            }
+            Expression::Literal(..) | Expression::BeingReference { .. } | Expression::Error(_) => {}
+        }
+    }
+
+    // Ogni variabile viene segnalata una sola volta, per non ripetere lo stesso errore a ogni lettura
+    fn reported(&self, id: usize) -> bool {
+        let declaration = &self.declarations[id].span;
+        self.errors.iter().any(|error| matches!(
+            error,
+            SemanticError::UninitializedVariable { declaration: d, .. } if d == declaration
+        ))
+    }
+
+    // In `init` un altro ritual potrebbe leggere variabili del being non ancora assegnate
+    fn check_call(&mut self, span: &Span, state: &State) {
+        if !self.being_pending {
+            return;
+        }
+        let missing = self.missing_being_variables(state);
+        if !missing.is_empty() {
+            self.errors.push(SemanticError::Generic(
+                format!(
+                    "ritual called in `{}` before being variable(s) {} are initialised",
+                    Being::INIT_RITUAL,
+                    missing.iter().map(|name| format!("'{}'", name)).collect::<Vec<_>>().join(", ")
+                ),
+                span.clone()
+            ));
+        }
+    }
+
+    // All'uscita da `init` tutte le variabili del being devono avere un valore
+    fn check_init_complete(&mut self, state: &State, span: Span) {
+        for name in self.missing_being_variables(state) {
+            self.errors.push(SemanticError::Generic(
+                format!("being variable '{}' may not be initialised when `{}` returns", name, Being::INIT_RITUAL),
+                span.clone()
+            ));
+        }
+    }
+
+    fn missing_being_variables(&self, state: &State) -> Vec<String> {
+        state.iter()
+            .flatten()
+            .filter(|&&id| id < self.being_count)
+            .map(|&id| self.declarations[id].name.to_string())
+            .collect()
+    }
+}
+
+// Unione dei due stati: una variabile è senza valore se lo è su almeno uno dei percorsi
+fn merge(a: State, b: State) -> State {
+    match (a, b) {
+        (None, state) | (state, None) => state,
+        (Some(mut a), Some(b)) => {
+            a.extend(b);
+            Some(a)
+        }
+    }
+}
+
+fn is_hive(var_type: &Type) -> bool {
+    matches!(var_type, Type::Hive { .. })
+}
+
+/// Whether a memory variable of this type can start from a zero value instead of an initializer
+pub fn has_zero_value(var_type: &Type) -> bool {
+    matches!(var_type, Type::Integer | Type::Float | Type::String | Type::Boolean | Type::Hive { .. })
+}
+
+fn contains_call(expr: &Expression) -> bool {
+    match expr {
+        Expression::FunctionCall { .. } | Expression::MethodCall { .. } => true,
+        Expression::BinaryOperation { left, right, .. } => contains_call(left) || contains_call(right),
+        Expression::UnaryOperation { operand, .. } => contains_call(operand),
+        Expression::FieldAccess { object, .. } => contains_call(object),
+        Expression::Index { object, indices, .. } => contains_call(object) || indices.iter().any(contains_call),
+        Expression::Construction { fields, .. } => fields.iter().any(|field| contains_call(&field.value)),
+        Expression::Literal(..) | Expression::Variable(..) |
+        Expression::BeingReference { .. } | Expression::Error(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer, parser};
+
+    fn check(source: &str) -> Vec<String> {
+        let (program, errors) = parser::parse(lexer::tokenize(source).expect("the source is lexically valid"));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let program = program.expect("the source parses");
+        check_being(&program.realms[0].beings[0]).into_iter().map(|d| d.message).collect()
+    }
+
+    #[test]
+    fn locals_must_be_assigned_on_every_path() {
+        let errors = check("
+            realm R {
+                being A {
+                    ritual f(n: int) int {
+                        int x;
+                        if (n > 0) {
+                            x = 1;
+                        }
+                        int y;
+                        if (n > 0) {
+                            y = 1;
+                        } else {
+                            y = 2;
+                        }
+                        return x + y;
+                    }
+                }
+            }
+        ");
+        assert_eq!(errors, ["use of possibly uninitialised variable `x`"]);
+    }
+
+    #[test]
+    fn being_variables_need_an_initializer_or_init() {
+        let errors = check("
+            realm R {
+                being A {
+                    int ready;
+                    int later;
+                    int early: first;
+                    int first: 1;
+
+                    ritual init() {
+                        ready = early;
+                    }
+                }
+            }
+        ");
+        assert_eq!(errors, [
+            "use of possibly uninitialised variable `first`",
+            "being variable 'later' may not be initialised when `init` returns",
+        ]);
+
+        let errors = check("realm R { being A { int missing; } }");
+        assert_eq!(errors, ["being variable 'missing' has no initial value"]);
+    }
+}
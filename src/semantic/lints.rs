@@ -0,0 +1,623 @@
+// Lint: avvisi su codice valido ma sospetto, configurabili dalla riga di comando e con attributi
+//
+// Il livello di un lint si decide in quest'ordine: l'attributo più interno (ritual, poi being,
+// poi realm) che lo nomina, poi la configurazione esterna, infine il livello predefinito.
+
+use std::collections::{HashMap, HashSet};
+use crate::ast::nodes::{Attribute, Being, Expression, Program, Realm, Ritual, Span, Statement, Visibility};
+use crate::diagnostics::{Diagnostic, Severity};
+
+/// A configurable check reporting suspicious but valid code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariables,
+    UnusedParameters,
+    UnusedRituals,
+    UnusedBeingVariables,
+    Shadowing,
+    SelfAssignment,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 6] = [
+        Lint::UnusedVariables,
+        Lint::UnusedParameters,
+        Lint::UnusedRituals,
+        Lint::UnusedBeingVariables,
+        Lint::Shadowing,
+        Lint::SelfAssignment,
+    ];
+
+    /// Name used on the command line and in attributes
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::UnusedParameters => "unused_parameters",
+            Lint::UnusedRituals => "unused_rituals",
+            Lint::UnusedBeingVariables => "unused_being_variables",
+            Lint::Shadowing => "shadowing",
+            Lint::SelfAssignment => "self_assignment",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+
+    pub fn default_level(self) -> LintLevel {
+        LintLevel::Warn
+    }
+}
+
+/// What to do when a lint fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl LintLevel {
+    /// Name of the attribute setting this level, e.g. `allow` for `@allow(...)`
+    pub fn name(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LintLevel> {
+        [LintLevel::Allow, LintLevel::Warn, LintLevel::Deny].into_iter().find(|level| level.name() == name)
+    }
+}
+
+/// Lint levels requested outside the source, e.g. on the command line
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<Lint, LintLevel>,
+}
+
+impl LintConfig {
+    pub fn set(&mut self, lint: Lint, level: LintLevel) {
+        self.levels.insert(lint, level);
+    }
+
+    /// Level of a lint when no attribute in the source overrides it
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.levels.get(&lint).copied().unwrap_or_else(|| lint.default_level())
+    }
+}
+
+/// Runs all lints over a program
+pub fn check_program(program: &Program, config: &LintConfig) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        config,
+        members: MemberUsage::collect(program),
+        attributes: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    for realm in &program.realms {
+        linter.realm(realm);
+    }
+    linter.diagnostics
+}
+
+// Origine del livello di un lint, mostrata nel diagnostic
+enum Origin {
+    Default,
+    Config,
+    Attribute(Span),
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    members: MemberUsage<'a>,
+    // Attributi delle dichiarazioni che racchiudono il codice analizzato, dalla più esterna
+    attributes: Vec<&'a [Attribute]>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    fn level(&self, lint: Lint) -> (LintLevel, Origin) {
+        for attribute in self.attributes.iter().rev().flat_map(|attributes| attributes.iter().rev()) {
+            if let Some(level) = LintLevel::from_name(&attribute.name) {
+                if attribute.arguments.iter().any(|arg| arg == lint.name()) {
+                    return (level, Origin::Attribute(attribute.span.clone()));
+                }
+            }
+        }
+        match self.config.levels.get(&lint) {
+            Some(&level) => (level, Origin::Config),
+            None => (lint.default_level(), Origin::Default),
+        }
+    }
+
+    // Registra un lint al livello configurato; `Deny` lo trasforma in errore
+    fn emit(&mut self, lint: Lint, diagnostic: Diagnostic) {
+        let (level, origin) = self.level(lint);
+        let mut diagnostic = match origin {
+            Origin::Default => diagnostic.with_note(format!("`@{}({})` on by default", level.name(), lint.name())),
+            Origin::Config => diagnostic.with_note(format!("`{}` set to {} on the command line", lint.name(), level.name())),
+            Origin::Attribute(span) => diagnostic.with_label(span, "lint level set here"),
+        };
+        match level {
+            LintLevel::Allow => return,
+            LintLevel::Warn => diagnostic.severity = Severity::Warning,
+            LintLevel::Deny => diagnostic.severity = Severity::Error,
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    // Verifica che gli attributi siano livelli di lint con nomi di lint conosciuti
+    fn check_attributes(&mut self, attributes: &[Attribute]) {
+        for attribute in attributes {
+            if LintLevel::from_name(&attribute.name).is_none() {
+                self.diagnostics.push(
+                    Diagnostic::error(format!("unknown attribute `@{}`", attribute.name), attribute.span.clone())
+                        .with_help("the supported attributes are `@allow`, `@warn` and `@deny`")
+                );
+                continue;
+            }
+            if attribute.arguments.is_empty() {
+                self.diagnostics.push(
+                    Diagnostic::error(format!("`@{}` expects at least one lint name", attribute.name), attribute.span.clone())
+                );
+            }
+            for argument in &attribute.arguments {
+                if Lint::from_name(argument).is_none() {
+                    let known: Vec<&str> = Lint::ALL.iter().map(|lint| lint.name()).collect();
+                    self.diagnostics.push(
+                        Diagnostic::warning(format!("unknown lint `{}`", argument), attribute.span.clone())
+                            .with_note(format!("the known lints are: {}", known.join(", ")))
+                    );
+                }
+            }
+        }
+    }
+
+    fn realm(&mut self, realm: &'a Realm) {
+        self.check_attributes(&realm.attributes);
+        self.attributes.push(&realm.attributes);
+        for essence in &realm.essences {
+            self.check_attributes(&essence.attributes);
+        }
+        for being in &realm.beings {
+            self.being(being);
+        }
+        self.attributes.pop();
+    }
+
+    fn being(&mut self, being: &'a Being) {
+        self.check_attributes(&being.attributes);
+        self.attributes.push(&being.attributes);
+
+        // Negli inizializzatori non ci sono variabili locali
+        let mut usage = BeingUsage::default();
+        for var in &being.variables {
+            if let Some(init) = &var.initializer {
+                let mut reads = Vec::new();
+                collect_reads(init, &mut reads);
+                usage.read.extend(reads);
+                usage.calls(init);
+            }
+        }
+
+        for ritual in &being.rituals {
+            self.check_attributes(&ritual.attributes);
+            self.attributes.push(&ritual.attributes);
+            self.ritual(being, ritual, &mut usage);
+            self.attributes.pop();
+        }
+
+        // I ritual pubblici possono essere chiamati da fuori del programma (`nervs run`), i gestori
+        // delle percezioni dal runtime
+        for ritual in being.rituals.iter().filter(|ritual| ritual.visibility == Visibility::Private) {
+            let name = ritual.name.as_str();
+            let called = usage.called.contains(name) || self.members.rituals.contains(name);
+            if name != Being::INIT_RITUAL && being.perception(name).is_none() && !called {
+                self.attributes.push(&ritual.attributes);
+                self.emit(Lint::UnusedRituals, Diagnostic::warning(
+                    format!("ritual `{}` is never called", ritual.name),
+                    ritual.span.clone()
+                ));
+                self.attributes.pop();
+            }
+        }
+
+        // Le variabili pubbliche possono essere lette da altri being
+        for var in being.variables.iter().filter(|var| var.visibility == Visibility::Private) {
+            let name = &var.variable.name;
+            let used = usage.read.contains(name.as_str()) || self.members.fields.contains(name.as_str());
+            if !name.starts_with('_') && !used {
+                self.emit(Lint::UnusedBeingVariables,
+                    Diagnostic::warning(format!("being variable `{}` is never read", name), var.variable.span.clone())
+                        .with_help(format!("if this is intentional, prefix it with an underscore: `_{}`", name))
+                );
+            }
+        }
+
+        self.attributes.pop();
+    }
+
+    fn ritual(&mut self, being: &Being, ritual: &'a Ritual, usage: &mut BeingUsage<'a>) {
+        let mut walker = RitualWalker::default();
+        walker.scopes.push(HashMap::new());
+        for param in &ritual.parameters {
+            self.shadowing(being, &param.name, &param.span);
+            walker.declare(&param.name, &param.span, true);
+        }
+
+        walker.block(&ritual.body, usage);
+        for finding in std::mem::take(&mut walker.findings) {
+            match finding {
+                Finding::Declared(name, span) => self.shadowing(being, name, span),
+                Finding::SelfAssignment(name, span) => self.emit(Lint::SelfAssignment,
+                    Diagnostic::warning(format!("`{}` is assigned to itself", name), span.clone())
+                        .with_primary_label("this assignment has no effect")
+                ),
+            }
+        }
+
+        for local in &walker.locals {
+            if local.used || local.name.starts_with('_') {
+                continue;
+            }
+            let (lint, what) = if local.parameter {
+                (Lint::UnusedParameters, "parameter")
+            } else {
+                (Lint::UnusedVariables, "variable")
+            };
+            self.emit(lint,
+                Diagnostic::warning(format!("unused {} `{}`", what, local.name), local.span.clone())
+                    .with_help(format!("if this is intentional, prefix it with an underscore: `_{}`", local.name))
+            );
+        }
+    }
+
+    // Una variabile locale con lo stesso nome di una variabile del being la rende inaccessibile
+    fn shadowing(&mut self, being: &Being, name: &str, span: &Span) {
+        if let Some(var) = being.variables.iter().find(|var| var.variable.name == name) {
+            self.emit(Lint::Shadowing,
+                Diagnostic::warning(format!("`{}` shadows a being variable", name), span.clone())
+                    .with_label(var.variable.span.clone(), "being variable declared here")
+            );
+        }
+    }
+}
+
+// Rituali chiamati e variabili del being lette, in tutto il being
+#[derive(Default)]
+struct BeingUsage<'a> {
+    called: HashSet<&'a str>,
+    read: HashSet<&'a str>,
+}
+
+struct Local<'a> {
+    name: &'a str,
+    span: Span,
+    parameter: bool,
+    used: bool,
+}
+
+// Eventi trovati nel corpo di un ritual che richiedono il contesto del being
+enum Finding<'a> {
+    Declared(&'a str, &'a Span),
+    SelfAssignment(&'a str, &'a Span),
+}
+
+#[derive(Default)]
+struct RitualWalker<'a> {
+    locals: Vec<Local<'a>>,
+    findings: Vec<Finding<'a>>,
+    // Variabili locali visibili, dal blocco più esterno al più interno
+    scopes: Vec<HashMap<&'a str, usize>>,
+}
+
+impl<'a> RitualWalker<'a> {
+    fn declare(&mut self, name: &'a str, span: &Span, parameter: bool) {
+        self.locals.push(Local { name, span: span.clone(), parameter, used: false });
+        let id = self.locals.len() - 1;
+        self.scopes.last_mut().expect("a ritual always has a scope").insert(name, id);
+    }
+
+    fn block(&mut self, statements: &'a [Statement], usage: &mut BeingUsage<'a>) {
+        self.scopes.push(HashMap::new());
+        for stmt in statements {
+            self.statement(stmt, usage);
+        }
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, stmt: &'a Statement, usage: &mut BeingUsage<'a>) {
+        match stmt {
+            Statement::VariableDeclaration { variable, initializer, .. } => {
+                if let Some(init) = initializer {
+                    self.expression(init, usage);
+                }
+                self.findings.push(Finding::Declared(&variable.name, &variable.span));
+                self.declare(&variable.name, &variable.span, false);
+            }
+            Statement::Assignment { name, value, span } => {
+                if matches!(value, Expression::Variable(source, _) if source == name) {
+                    self.findings.push(Finding::SelfAssignment(name, span));
+                }
+                self.expression(value, usage);
+            }
+            // Modificare un campo o un elemento conta come uso della variabile che lo contiene
+            Statement::FieldAssignment { object, value, .. } => {
+                self.expression(object, usage);
+                self.expression(value, usage);
+            }
+            Statement::IndexAssignment { object, indices, value, .. } => {
+                self.expression(object, usage);
+                for index in indices {
+                    self.expression(index, usage);
+                }
+                self.expression(value, usage);
+            }
+            Statement::RitualCall { name, arguments, .. } => {
+                usage.called.insert(name);
+                for arg in arguments {
+                    self.expression(arg, usage);
+                }
+            }
+            Statement::Conditional { condition, true_branch, false_branch, .. } => {
+                self.expression(condition, usage);
+                self.block(true_branch, usage);
+                if let Some(false_branch) = false_branch {
+                    self.block(false_branch, usage);
+                }
+            }
+            Statement::Cycle { condition, body, .. } => {
+                if let Some(condition) = condition {
+                    self.expression(condition, usage);
+                }
+                self.block(body, usage);
+            }
+            Statement::Return(value, _) => {
+                if let Some(value) = value {
+                    self.expression(value, usage);
+                }
+            }
+            Statement::Expression(expr, _) => self.expression(expr, usage),
+            Statement::Error(_) => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &'a Expression, usage: &mut BeingUsage<'a>) {
+        // Le letture di variabili locali le marcano come usate; le altre riguardano il being
+        let mut reads = Vec::new();
+        collect_reads(expr, &mut reads);
+        for name in reads {
+            match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+                Some(&id) => self.locals[id].used = true,
+                None => {
+                    usage.read.insert(name);
+                }
+            }
+        }
+        usage.calls(expr);
+    }
+}
+
+impl<'a> BeingUsage<'a> {
+    fn calls(&mut self, expr: &'a Expression) {
+        match expr {
+            Expression::BinaryOperation { left, right, .. } => {
+                self.calls(left);
+                self.calls(right);
+            }
+            Expression::UnaryOperation { operand, .. } => self.calls(operand),
+            Expression::FunctionCall { name, arguments, .. } => {
+                self.called.insert(name);
+                for arg in arguments {
+                    self.calls(arg);
+                }
+            }
+            // Le chiamate su un oggetto sono raccolte per tutto il programma da `MemberUsage`
+            Expression::MethodCall { object, arguments, .. } => {
+                self.calls(object);
+                for arg in arguments {
+                    self.calls(arg);
+                }
+            }
+            Expression::FieldAccess { object, .. } => self.calls(object),
+            Expression::Construction { fields, .. } => {
+                for field in fields {
+                    self.calls(&field.value);
+                }
+            }
+            Expression::Index { object, indices, .. } => {
+                self.calls(object);
+                for index in indices {
+                    self.calls(index);
+                }
+            }
+            Expression::Literal(..) | Expression::Variable(..) |
+            Expression::BeingReference { .. } | Expression::Error(_) => {}
+        }
+    }
+}
+
+fn collect_reads<'a>(expr: &'a Expression, reads: &mut Vec<&'a str>) {
+    match expr {
+        Expression::Variable(name, _) => reads.push(name),
+        Expression::BinaryOperation { left, right, .. } => {
+            collect_reads(left, reads);
+            collect_reads(right, reads);
+        }
+        Expression::UnaryOperation { operand, .. } => collect_reads(operand, reads),
+        Expression::FunctionCall { arguments, .. } => {
+            for arg in arguments {
+                collect_reads(arg, reads);
+            }
+        }
+        Expression::FieldAccess { object, .. } => collect_reads(object, reads),
+        Expression::MethodCall { object, arguments, .. } => {
+            collect_reads(object, reads);
+            for arg in arguments {
+                collect_reads(arg, reads);
+            }
+        }
+        Expression::Construction { fields, .. } => {
+            for field in fields {
+                collect_reads(&field.value, reads);
+            }
+        }
+        Expression::Index { object, indices, .. } => {
+            collect_reads(object, reads);
+            for index in indices {
+                collect_reads(index, reads);
+            }
+        }
+        Expression::Literal(..) | Expression::BeingReference { .. } | Expression::Error(_) => {}
+    }
+}
+
+// Membri usati tramite `oggetto.membro` in tutto il programma. Il tipo dell'oggetto non è noto
+// ai lint, quindi un membro conta come usato in ogni being che ne dichiara uno con quel nome
+#[derive(Default)]
+struct MemberUsage<'a> {
+    fields: HashSet<&'a str>,
+    rituals: HashSet<&'a str>,
+}
+
+impl<'a> MemberUsage<'a> {
+    fn collect(program: &'a Program) -> Self {
+        let mut usage = MemberUsage::default();
+        for being in program.realms.iter().flat_map(|realm| &realm.beings) {
+            for init in being.variables.iter().filter_map(|var| var.initializer.as_ref()) {
+                usage.expression(init);
+            }
+            for ritual in &being.rituals {
+                usage.statements(&ritual.body);
+            }
+        }
+        usage
+    }
+
+    fn statements(&mut self, statements: &'a [Statement]) {
+        for stmt in statements {
+            match stmt {
+                Statement::VariableDeclaration { initializer: Some(expr), .. } |
+                Statement::Assignment { value: expr, .. } |
+                Statement::Return(Some(expr), _) |
+                Statement::Expression(expr, _) => self.expression(expr),
+                Statement::FieldAssignment { object, value, .. } => {
+                    self.expression(object);
+                    self.expression(value);
+                }
+                Statement::IndexAssignment { object, indices, value, .. } => {
+                    self.expression(object);
+                    indices.iter().for_each(|index| self.expression(index));
+                    self.expression(value);
+                }
+                Statement::RitualCall { arguments, .. } => arguments.iter().for_each(|arg| self.expression(arg)),
+                Statement::Conditional { condition, true_branch, false_branch, .. } => {
+                    self.expression(condition);
+                    self.statements(true_branch);
+                    if let Some(false_branch) = false_branch {
+                        self.statements(false_branch);
+                    }
+                }
+                Statement::Cycle { condition, body, .. } => {
+                    if let Some(condition) = condition {
+                        self.expression(condition);
+                    }
+                    self.statements(body);
+                }
+                Statement::VariableDeclaration { initializer: None, .. } |
+                Statement::Return(None, _) |
+                Statement::Error(_) => {}
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &'a Expression) {
+        match expr {
+            Expression::FieldAccess { object, field, .. } => {
+                self.fields.insert(field);
+                self.expression(object);
+            }
+            Expression::MethodCall { object, name, arguments, .. } => {
+                self.rituals.insert(name);
+                self.expression(object);
+                arguments.iter().for_each(|arg| self.expression(arg));
+            }
+            Expression::BinaryOperation { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::UnaryOperation { operand, .. } => self.expression(operand),
+            Expression::FunctionCall { arguments, .. } => arguments.iter().for_each(|arg| self.expression(arg)),
+            Expression::Construction { fields, .. } => fields.iter().for_each(|field| self.expression(&field.value)),
+            Expression::Index { object, indices, .. } => {
+                self.expression(object);
+                indices.iter().for_each(|index| self.expression(index));
+            }
+            Expression::Literal(..) | Expression::Variable(..) |
+            Expression::BeingReference { .. } | Expression::Error(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer, parser};
+
+    const PROGRAM: &str = "
+        realm R {
+            being A {
+                int kept: 0;
+
+                public ritual f(p: int, q: int) int {
+                    int unused: 1;
+                    int kept: 2;
+                    kept = kept;
+                    return kept + p;
+                }
+
+                ritual helper() {
+                }
+
+                @deny(unused_parameters)
+                public ritual g(z: int) {
+                }
+            }
+        }
+    ";
+
+    fn lint(config: &LintConfig) -> Vec<(Severity, String)> {
+        let (program, errors) = parser::parse(lexer::tokenize(PROGRAM).expect("the source is lexically valid"));
+        assert!(errors.is_empty(), "{:?}", errors);
+        check_program(&program.expect("the source parses"), config).into_iter()
+            .map(|d| (d.severity, d.message))
+            .collect()
+    }
+
+    #[test]
+    fn suspicious_code_is_reported() {
+        let warning = |message: &str| (Severity::Warning, message.to_string());
+        assert_eq!(lint(&LintConfig::default()), [
+            warning("`kept` shadows a being variable"),
+            warning("`kept` is assigned to itself"),
+            warning("unused parameter `q`"),
+            warning("unused variable `unused`"),
+            (Severity::Error, "unused parameter `z`".to_string()),
+            warning("ritual `helper` is never called"),
+            warning("being variable `kept` is never read"),
+        ]);
+    }
+
+    #[test]
+    fn attributes_take_precedence_over_the_configuration() {
+        let mut config = LintConfig::default();
+        for lint in Lint::ALL {
+            config.set(lint, LintLevel::Allow);
+        }
+        // `@deny` sul ritual `g` vale anche se la configurazione disattiva il lint
+        assert_eq!(lint(&config), [(Severity::Error, "unused parameter `z`".to_string())]);
+    }
+}
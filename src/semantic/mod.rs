@@ -1,26 +1,194 @@
-pub mod analyzer;
-
-use crate::ast::nodes::Program;
-
-#[derive(Debug, thiserror::Error)]
-pub enum SemanticError {
-    #[error("Undefined variable: {0}")]
-    UndefinedVariable(String),
-    
-    #[error("Type mismatch: expected {expected}, found {found}")]
-    TypeMismatch {
-        expected: String,
-        found: String,
-    },
-    
-    #[error("Ritual {0} not found")]
-    UndefinedRitual(String),
-    
-    #[error("Semantic error: {0}")]
-    Generic(String),
-}
-
-// Funzione pubblica per eseguire l'analisi semantica
-pub fn analyze(program: &Program) -> Result<(), Box<dyn std::error::Error>> {
-    analyzer::analyze(program)
-}
\ No newline at end of file
+pub mod analyzer;
+pub mod control_flow;
+pub mod initialization;
+pub mod lints;
+pub mod suggestions;
+
+use crate::ast::nodes::Program;
+use crate::ast::span::Span;
+use crate::diagnostics::Diagnostic;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SemanticError {
+    #[error("Undefined variable: {0}")]
+    UndefinedVariable(String, Span),
+    
+    #[error("Type mismatch: expected {expected}, found {found}")]
+    TypeMismatch {
+        expected: String,
+        found: String,
+        span: Span,
+    },
+    
+    #[error("Ritual {0} not found")]
+    UndefinedRitual(String, Span),
+    
+    #[error("Variable {name} is used before being initialised")]
+    UninitializedVariable {
+        name: String,
+        span: Span,
+        /// Dichiarazione della variabile
+        declaration: Span,
+    },
+    
+    #[error("Ritual {ritual} must return a value of type {return_type}")]
+    MissingReturn {
+        ritual: String,
+        return_type: String,
+        span: Span,
+        /// Percorso che raggiunge la fine del ritual senza return, se individuato
+        path: Option<Span>,
+    },
+    
+    #[error("Unknown type {name}")]
+    UnknownType {
+        name: String,
+        span: Span,
+        /// Nome noto più simile, se esiste
+        suggestion: Option<String>,
+    },
+    
+    #[error("The {kind} {name} of being {being} is private")]
+    PrivateMember {
+        /// "ritual" o "variable"
+        kind: &'static str,
+        name: String,
+        being: String,
+        span: Span,
+    },
+    
+    #[error("Realm {name} is defined more than once")]
+    DuplicateRealm {
+        name: String,
+        span: Span,
+        /// Prima dichiarazione, eventualmente in un altro file
+        previous: Span,
+    },
+    
+    #[error("Perception {perception} of being {being} has no handler")]
+    MissingPerceptionHandler {
+        perception: String,
+        being: String,
+        /// Firma attesa del gestore, come `on_tick(n: int)`
+        signature: String,
+        span: Span,
+    },
+    
+    #[error("The handler of perception {perception} does not match its declaration")]
+    PerceptionHandlerMismatch {
+        perception: String,
+        expected: String,
+        found: String,
+        span: Span,
+        /// Dichiarazione della percezione
+        declaration: Span,
+    },
+    
+    #[error("Semantic error: {0}")]
+    Generic(String, Span),
+}
+
+impl SemanticError {
+    // Posizione nel sorgente del codice che ha causato l'errore
+    pub fn span(&self) -> Span {
+        match self {
+            SemanticError::UndefinedVariable(_, span) |
+            SemanticError::TypeMismatch { span, .. } |
+            SemanticError::UndefinedRitual(_, span) |
+            SemanticError::UninitializedVariable { span, .. } |
+            SemanticError::MissingReturn { span, .. } |
+            SemanticError::UnknownType { span, .. } |
+            SemanticError::PrivateMember { span, .. } |
+            SemanticError::DuplicateRealm { span, .. } |
+            SemanticError::MissingPerceptionHandler { span, .. } |
+            SemanticError::PerceptionHandlerMismatch { span, .. } |
+            SemanticError::Generic(_, span) => span.clone(),
+        }
+    }
+
+    // Converte l'errore in un diagnostic con etichetta sul codice coinvolto
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            SemanticError::UndefinedVariable(name, span) => {
+                Diagnostic::error(format!("undefined variable `{}`", name), span.clone())
+                    .with_primary_label("not found in this scope")
+            }
+            SemanticError::TypeMismatch { expected, found, span } => {
+                Diagnostic::error("mismatched types", span.clone())
+                    .with_primary_label(format!("expected {}, found {}", expected, found))
+            }
+            SemanticError::UndefinedRitual(name, span) => {
+                Diagnostic::error(format!("ritual `{}` not found", name), span.clone())
+                    .with_primary_label("not defined in this being")
+            }
+            SemanticError::UninitializedVariable { name, span, declaration } => {
+                Diagnostic::error(format!("use of possibly uninitialised variable `{}`", name), span.clone())
+                    .with_primary_label("used here before it is assigned on every path")
+                    .with_label(declaration.clone(), "declared here")
+            }
+            SemanticError::MissingReturn { ritual, return_type, span, path } => {
+                let diagnostic = Diagnostic::error(
+                    format!("ritual `{}` must return a value of type {}", ritual, return_type),
+                    span.clone()
+                );
+                match path {
+                    Some(path) => diagnostic.with_label(path.clone(), "missing return on this path"),
+                    None => diagnostic.with_primary_label("missing return at the end of the ritual"),
+                }
+            }
+            SemanticError::UnknownType { name, span, suggestion } => {
+                // Un nome qualificato (`Realm::Nome`) è cercato nel realm indicato
+                let diagnostic = match name.split_once("::") {
+                    Some((realm, _)) => Diagnostic::error(format!("cannot find type `{}`", name), span.clone())
+                        .with_primary_label(format!("not found in realm `{}`", realm)),
+                    None => Diagnostic::error(format!("cannot find type `{}` in this realm", name), span.clone())
+                        .with_primary_label("not found in this realm"),
+                };
+                match suggestion {
+                    Some(suggestion) => diagnostic.with_help(format!("a type with a similar name exists: `{}`", suggestion)),
+                    None => diagnostic,
+                }
+            }
+            SemanticError::PrivateMember { kind, name, being, span } => {
+                Diagnostic::error(format!("{} `{}` of being `{}` is private", kind, name, being), span.clone())
+                    .with_primary_label(format!("private {}", kind))
+                    .with_help(format!("declare it `public` to use it outside `{}`", being))
+            }
+            SemanticError::DuplicateRealm { name, span, previous } => {
+                Diagnostic::error(format!("realm `{}` is defined more than once", name), span.clone())
+                    .with_primary_label("redefined here")
+                    .with_label(previous.clone(), "previously defined here")
+                    .with_help("realm names must be unique across all imported files")
+            }
+            SemanticError::MissingPerceptionHandler { perception, being, signature, span } => {
+                Diagnostic::error(format!("perception `{}` of being `{}` has no handler", perception, being), span.clone())
+                    .with_primary_label("declared here")
+                    .with_help(format!("add a ritual with the same name and parameters: `ritual {} {{ ... }}`", signature))
+            }
+            SemanticError::PerceptionHandlerMismatch { perception, expected, found, span, declaration } => {
+                Diagnostic::error(
+                    format!("the handler of perception `{}` does not match its declaration", perception),
+                    span.clone()
+                )
+                    .with_primary_label(format!("expected `{}`, found `{}`", expected, found))
+                    .with_label(declaration.clone(), "perception declared here")
+                    .with_note("a handler takes the parameters of its perception and returns nothing")
+            }
+            SemanticError::Generic(message, span) => {
+                Diagnostic::error(message.clone(), span.clone())
+            }
+        }
+    }
+}
+
+// Funzione pubblica per eseguire l'analisi semantica; restituisce tutti gli errori e i warning
+pub fn analyze(program: &Program) -> Vec<Diagnostic> {
+    analyze_with_lints(program, &lints::LintConfig::default())
+}
+
+// Come `analyze`, con i livelli dei lint configurati dall'esterno
+pub fn analyze_with_lints(program: &Program, config: &lints::LintConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = analyzer::analyze(program);
+    diagnostics.extend(lints::check_program(program, config));
+    diagnostics
+}
@@ -0,0 +1,262 @@
+// Pipeline di compilazione: lex → parse → import → analyze → codegen → seal
+
+use std::path::{Path, PathBuf};
+use crate::ast::nodes::Program;
+use crate::ast::span::{SourceMap, Span};
+use crate::diagnostics::Diagnostic;
+use crate::lexer::{self, Token};
+use crate::semantic::lints::LintConfig;
+use crate::{codegen, loader, parser, seal, semantic};
+
+/// A stage of the compilation pipeline, in execution order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Lex,
+    Parse,
+    Import,
+    Analyze,
+    Codegen,
+    Seal,
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Stage::Lex => "lexing",
+            Stage::Parse => "parsing",
+            Stage::Import => "import resolution",
+            Stage::Analyze => "semantic analysis",
+            Stage::Codegen => "code generation",
+            Stage::Seal => "sealing",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Options controlling the stages run by [`Session::compile`] after analysis
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Directory receiving the generated C code; no code is generated when `None`
+    pub output_dir: Option<PathBuf>,
+    /// Whether to apply integrity seals to the checked program
+    pub seal: bool,
+    /// Lint levels, overridable in the source with `@allow`, `@warn` and `@deny`
+    pub lints: LintConfig,
+    /// Directories searched for imported files after the directory of the importing file
+    pub search_paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompileError {
+    /// The program has errors; they are available from [`Session::diagnostics`]
+    #[error("{stage} failed with {count} error(s)")]
+    Diagnostics { stage: Stage, count: usize },
+
+    #[error("{stage} failed: {message}")]
+    Backend { stage: Stage, message: String },
+}
+
+/// A compilation of one source file and the files it imports
+pub struct Session {
+    source_map: SourceMap,
+    // Percorso del file principale, da cui si risolvono gli import; `None` per un sorgente in memoria
+    root: Option<PathBuf>,
+    options: CompileOptions,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Session {
+    /// Creates a session over an in-memory source
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Self {
+        Session {
+            source_map: SourceMap::new(name, source),
+            root: None,
+            options: CompileOptions::default(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Creates a session reading the source from a file
+    pub fn from_file(path: &Path) -> std::io::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        let mut session = Session::new(path.display().to_string(), source);
+        session.root = Some(path.to_path_buf());
+        Ok(session)
+    }
+
+    /// Replaces the compile options
+    pub fn with_options(mut self, options: CompileOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn options(&self) -> &CompileOptions {
+        &self.options
+    }
+
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    /// All errors and warnings reported so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.is_error()).count()
+    }
+
+    // Fallisce se lo stage ha prodotto errori
+    fn check(&self, stage: Stage) -> Result<(), CompileError> {
+        match self.error_count() {
+            0 => Ok(()),
+            count => Err(CompileError::Diagnostics { stage, count }),
+        }
+    }
+
+    /// Splits the source into tokens
+    pub fn tokenize(&mut self) -> Result<Vec<(Token, Span)>, CompileError> {
+        match lexer::tokenize(self.source_map.source()) {
+            Ok(tokens) => Ok(tokens),
+            Err(e) => {
+                self.diagnostics.push(e.to_diagnostic());
+                Err(CompileError::Diagnostics { stage: Stage::Lex, count: 1 })
+            }
+        }
+    }
+
+    /// Lexes and parses the main source file into a syntax tree, without loading its imports
+    pub fn parse(&mut self) -> Result<Program, CompileError> {
+        let program = self.parse_recovering()?;
+        self.check(Stage::Parse)?;
+        Ok(program)
+    }
+
+    /// Parses the main file and the files it imports into a single program
+    ///
+    /// Imports of an in-memory source are resolved from the current directory.
+    pub fn load(&mut self) -> Result<Program, CompileError> {
+        let program = self.parse()?;
+        let program = self.load_imports(program);
+        self.check(Stage::Import)?;
+        Ok(program)
+    }
+
+    /// Loads and checks the program
+    ///
+    /// Syntax errors do not stop the analysis: the tree recovered by the parser is checked too,
+    /// with the malformed constructs typed as errors so that they raise nothing further. Lints
+    /// only run on programs without syntax errors.
+    pub fn analyze(&mut self) -> Result<Program, CompileError> {
+        let program = self.parse_recovering()?;
+        let syntax_errors = self.error_count();
+        let program = self.load_imports(program);
+
+        if syntax_errors > 0 {
+            // Con import mancanti l'analisi segnalerebbe solo nomi sconosciuti
+            if self.error_count() == syntax_errors {
+                self.diagnostics.extend(semantic::analyzer::analyze(&program));
+            }
+            self.check(Stage::Parse)?;
+        }
+        self.check(Stage::Import)?;
+        self.diagnostics.extend(semantic::analyze_with_lints(&program, &self.options.lints));
+        self.check(Stage::Analyze)?;
+        Ok(program)
+    }
+
+    // Analizza il file principale registrando gli errori di sintassi; fallisce solo se il parser
+    // non ha recuperato nessun programma
+    fn parse_recovering(&mut self) -> Result<Program, CompileError> {
+        let tokens = self.tokenize()?;
+        let (program, errors) = parser::parse(tokens);
+        self.diagnostics.extend(errors.iter().map(parser::error::to_diagnostic));
+        match program {
+            Some(program) => Ok(program),
+            None => Err(CompileError::Diagnostics { stage: Stage::Parse, count: self.error_count() }),
+        }
+    }
+
+    fn load_imports(&mut self, program: Program) -> Program {
+        let (program, diagnostics) =
+            loader::load(program, self.root.as_deref(), &mut self.source_map, &self.options.search_paths);
+        self.diagnostics.extend(diagnostics);
+        program
+    }
+
+    /// Checks the program, then generates code and seals it as configured in [`CompileOptions`]
+    pub fn compile(&mut self) -> Result<Program, CompileError> {
+        let program = self.analyze()?;
+
+        if let Some(output_dir) = &self.options.output_dir {
+            codegen::generate(&program, output_dir)
+                .map_err(|e| CompileError::Backend { stage: Stage::Codegen, message: e.to_string() })?;
+        }
+
+        if self.options.seal {
+            seal::apply_seals(&program)
+                .map_err(|e| CompileError::Backend { stage: Stage::Seal, message: e.to_string() })?;
+        }
+
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage(source: &str) -> Option<Stage> {
+        match Session::new("main.nervs", source).analyze() {
+            Ok(_) => None,
+            Err(CompileError::Diagnostics { stage, .. }) => Some(stage),
+            Err(e) => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn failures_name_the_stage_that_stopped() {
+        assert_eq!(stage("realm R { being B { int x: $; } }"), Some(Stage::Lex));
+        assert_eq!(stage("realm R { being B { int x: ; } }"), Some(Stage::Parse));
+        assert_eq!(stage("import \"missing.nervs\"; realm R { }"), Some(Stage::Import));
+        assert_eq!(stage("realm R { being B { int x: true; } }"), Some(Stage::Analyze));
+        assert_eq!(stage("realm R { being B { int x: 1; } }"), None);
+    }
+
+    #[test]
+    fn compile_writes_the_generated_code() {
+        let output_dir = std::env::temp_dir().join(format!("nervs-session-{}", std::process::id()));
+        let mut session = Session::new("main.nervs", "realm R { being B { public int x: 1; } }")
+            .with_options(CompileOptions { output_dir: Some(output_dir.clone()), ..CompileOptions::default() });
+        let compiled = session.compile();
+        let written = [codegen::generator::HEADER_FILE, codegen::generator::SOURCE_FILE, codegen::generator::MAIN_FILE]
+            .map(|name| output_dir.join(name).exists());
+        let _ = std::fs::remove_dir_all(&output_dir);
+
+        assert!(compiled.is_ok());
+        assert_eq!(written, [true; 3]);
+    }
+
+    #[test]
+    fn semantic_errors_are_reported_after_syntax_errors() {
+        let mut session = Session::new("broken.nervs", "
+            realm Main {
+                being App {
+                    public ritual f() int {
+                        int x: 1 +;
+                        string s: 5;
+                        x = x * ;
+                        return x;
+                    }
+                }
+            }
+        ");
+        assert!(matches!(session.analyze(), Err(CompileError::Diagnostics { stage: Stage::Parse, count: 3 })));
+
+        let messages: Vec<&str> = session.diagnostics().iter().map(|d| d.message.as_str()).collect();
+        // I due errori di sintassi, poi l'errore di tipo; nessun errore su `x`
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert_eq!(messages[2], "mismatched types");
+    }
+}